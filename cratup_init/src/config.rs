@@ -1,23 +1,215 @@
 use anyhow::{Context, Result};
+use cratup_tree_sitter::TomlGrammar;
 use dialoguer::Input;
 use log::{debug, warn};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Environment variable holding the path of a configuration file to use instead of
+/// the platform-specific location managed by `confy`.
+pub const CONFIG_PATH_ENV: &str = "CRATUP_CONFIG";
+
+/// Version of the stored configuration format written by this build.
+/// Bump it whenever a field is added, so older files get migrated by `migrate_config`.
+pub const CURRENT_CONFIG_VERSION: u32 = 8;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct Config {
+    /// Version of the configuration format. Files written before versioning have none,
+    /// which reads as 0.
+    #[serde(default)]
+    pub config_version: u32,
     /// Always ask for permission to modify files.
     pub always_ask_permission: bool,
+    /// Match count from which the per-file match count is shown in yellow.
+    pub match_warn_threshold: usize,
+    /// Match count from which the per-file match count is shown in red.
+    pub match_error_threshold: usize,
+    /// Deprecated and ignored: [dev-dependencies] are always searched.
+    pub include_dev_dependencies: bool,
+    /// Deprecated and ignored: [build-dependencies] are always searched.
+    pub include_build_dependencies: bool,
+    /// Directory names skipped by `search` and `incv`, in addition to hidden directories.
+    pub excluded_dirs: Vec<String>,
+    /// Milliseconds of Cargo.toml changes batched into one re-run by `search --watch`.
+    pub watch_debounce_ms: u64,
+    /// Stage and commit the updated Cargo.toml files after a successful `incv`.
+    pub auto_git_commit: bool,
+    /// Message of the `auto_git_commit` commit; `{from}` and `{to}` are replaced by the versions.
+    pub git_commit_message_template: String,
+    /// Tag the commit made by `auto_git_commit` with the new version after a successful `incv`.
+    /// Does nothing when `auto_git_commit` is off.
+    pub auto_git_tag: bool,
+    /// Prefix of the `auto_git_tag` tag name, e.g. `v` for `v0.4.2`.
+    pub git_tag_prefix: String,
+    /// Skip crates whose current version is already on crates.io when publishing.
+    pub publish_check_crates_io: bool,
+    /// TOML version the package of each Cargo.toml is read with.
+    pub toml_grammar: TomlGrammar,
 }
 
-// Manually implement Default to set the custom default value.
+// Manually implement Default to set the custom default values.
 impl Default for Config {
     fn default() -> Self {
         Config {
+            config_version: CURRENT_CONFIG_VERSION,
             always_ask_permission: false, // Default is No.
+            match_warn_threshold: 2,
+            match_error_threshold: 5,
+            include_dev_dependencies: false,
+            include_build_dependencies: false,
+            excluded_dirs: Vec::new(),
+            watch_debounce_ms: 300,
+            auto_git_commit: false,
+            git_commit_message_template: "chore: bump version {from} → {to}".to_string(),
+            auto_git_tag: false,
+            git_tag_prefix: "v".to_string(),
+            publish_check_crates_io: false,
+            toml_grammar: TomlGrammar::Toml05,
         }
     }
 }
 
+/// A single configuration field with its default and current value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigField {
+    pub name: &'static str,
+    pub default_value: String,
+    pub current_value: String,
+}
+
+impl ConfigField {
+    /// Returns true if the current value differs from the default.
+    pub fn is_changed(&self) -> bool {
+        self.default_value != self.current_value
+    }
+}
+
+impl Config {
+    /// Returns every configuration field alongside its default value.
+    pub fn fields(&self) -> Vec<ConfigField> {
+        let default = Config::default();
+        vec![
+            ConfigField {
+                name: "always_ask_permission",
+                default_value: default.always_ask_permission.to_string(),
+                current_value: self.always_ask_permission.to_string(),
+            },
+            ConfigField {
+                name: "match_warn_threshold",
+                default_value: default.match_warn_threshold.to_string(),
+                current_value: self.match_warn_threshold.to_string(),
+            },
+            ConfigField {
+                name: "match_error_threshold",
+                default_value: default.match_error_threshold.to_string(),
+                current_value: self.match_error_threshold.to_string(),
+            },
+            ConfigField {
+                name: "include_dev_dependencies",
+                default_value: default.include_dev_dependencies.to_string(),
+                current_value: self.include_dev_dependencies.to_string(),
+            },
+            ConfigField {
+                name: "include_build_dependencies",
+                default_value: default.include_build_dependencies.to_string(),
+                current_value: self.include_build_dependencies.to_string(),
+            },
+            ConfigField {
+                name: "excluded_dirs",
+                default_value: default.excluded_dirs.join(", "),
+                current_value: self.excluded_dirs.join(", "),
+            },
+            ConfigField {
+                name: "watch_debounce_ms",
+                default_value: default.watch_debounce_ms.to_string(),
+                current_value: self.watch_debounce_ms.to_string(),
+            },
+            ConfigField {
+                name: "auto_git_commit",
+                default_value: default.auto_git_commit.to_string(),
+                current_value: self.auto_git_commit.to_string(),
+            },
+            ConfigField {
+                name: "git_commit_message_template",
+                default_value: default.git_commit_message_template.clone(),
+                current_value: self.git_commit_message_template.clone(),
+            },
+            ConfigField {
+                name: "auto_git_tag",
+                default_value: default.auto_git_tag.to_string(),
+                current_value: self.auto_git_tag.to_string(),
+            },
+            ConfigField {
+                name: "git_tag_prefix",
+                default_value: default.git_tag_prefix.clone(),
+                current_value: self.git_tag_prefix.clone(),
+            },
+            ConfigField {
+                name: "publish_check_crates_io",
+                default_value: default.publish_check_crates_io.to_string(),
+                current_value: self.publish_check_crates_io.to_string(),
+            },
+            ConfigField {
+                name: "toml_grammar",
+                default_value: default.toml_grammar.to_string(),
+                current_value: self.toml_grammar.to_string(),
+            },
+        ]
+    }
+
+    /// Reads the configuration from the TOML file at `path`. Missing fields take their
+    /// default value, and a file written by an older version is migrated with
+    /// `migrate_config`.
+    pub fn load_from_path(path: &Path) -> Result<Config> {
+        debug!("Loading configuration from {:?}", path);
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read configuration file {:?}", path))?;
+        let raw: toml::Value = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse configuration file {:?}", path))?;
+
+        let stored_version = raw
+            .get("config_version")
+            .and_then(toml::Value::as_integer)
+            .unwrap_or(0);
+        if stored_version < i64::from(CURRENT_CONFIG_VERSION) {
+            return migrate_config(raw);
+        }
+
+        raw.try_into()
+            .with_context(|| format!("Failed to read configuration file {:?}", path))
+    }
+
+    /// Writes the configuration as TOML to `path`, creating its parent directories.
+    pub fn save_to_path(&self, path: &Path) -> Result<()> {
+        debug!("Saving configuration to {:?}", path);
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+        let content = toml::to_string(self).context("Failed to serialize configuration")?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write configuration file {:?}", path))
+    }
+
+    /// Returns only the fields whose value differs from `Config::default()`.
+    pub fn diff(&self) -> Vec<ConfigField> {
+        self.fields()
+            .into_iter()
+            .filter(ConfigField::is_changed)
+            .collect()
+    }
+}
+
+/// Stores the configuration at `CRATUP_CONFIG` when it is set, or through `confy` otherwise.
+fn store_configuration(config: &Config) -> Result<()> {
+    match std::env::var_os(CONFIG_PATH_ENV) {
+        Some(path) => config.save_to_path(Path::new(&path)),
+        None => confy::store("cratup_auto", "config", config).context("Failed to save configuration"),
+    }
+}
+
 /// Initializes and updates the configuration for file modification permission.
 ///
 /// This function loads the existing configuration, prompts the user with a yes/no question,
@@ -25,9 +217,13 @@ impl Default for Config {
 pub fn initialize_configuration() -> Result<()> {
     debug!("Initializing configuration process started.");
 
-    // Load the configuration using confy.
-    let mut config: Config = confy::load("cratup_auto", "config")
-        .context("Failed to load configuration")?;
+    // Load the configuration, from CRATUP_CONFIG if set. A file that does not exist yet
+    // starts out with the defaults.
+    let mut config: Config = match std::env::var_os(CONFIG_PATH_ENV) {
+        Some(path) if Path::new(&path).exists() => Config::load_from_path(Path::new(&path))?,
+        Some(_) => Config::default(),
+        None => confy::load("cratup_auto", "config").context("Failed to load configuration")?,
+    };
 
     // Determine the current setting as a string.
     let current_value = if config.always_ask_permission { "yes" } else { "no" };
@@ -42,7 +238,7 @@ pub fn initialize_configuration() -> Result<()> {
         .interact_text()?;
 
     // Parse the input: only exactly "yes" (case-sensitive) counts as true.
-    config.always_ask_permission = if input == "yes" { true } else { false };
+    config.always_ask_permission = input == "yes";
 
     debug!(
         "User input received for always_ask_permission: {}",
@@ -50,13 +246,91 @@ pub fn initialize_configuration() -> Result<()> {
     );
 
     // Save the updated configuration.
-    confy::store("cratup_auto", "config", &config)
-        .context("Failed to save configuration")?;
+    store_configuration(&config)?;
+    debug!("Configuration saved successfully.");
+
+    for field in config.diff() {
+        println!(
+            "{} = {} (default: {})",
+            field.name, field.current_value, field.default_value
+        );
+    }
+
+    Ok(())
+}
+
+/// Stores the given configuration without prompting the user.
+///
+/// This is the non-interactive counterpart of `initialize_configuration`,
+/// intended for CI scripts where no terminal is available.
+pub fn initialize_configuration_from_values(values: Config) -> Result<()> {
+    debug!("Storing configuration non-interactively: {:?}", values);
+
+    store_configuration(&values)?;
     debug!("Configuration saved successfully.");
 
     Ok(())
 }
 
+/// Upgrades a stored configuration to `CURRENT_CONFIG_VERSION`.
+///
+/// Every field missing from `raw` is filled in with its value from `Config::default()`,
+/// while the fields the user already stored are kept as they are.
+///
+/// # Returns
+/// - `Result<Config>`: The migrated configuration, or an error if `raw` is not a table
+///   or holds values of the wrong type.
+pub fn migrate_config(raw: toml::Value) -> Result<Config> {
+    let toml::Value::Table(mut table) = raw else {
+        anyhow::bail!("Stored configuration is not a TOML table");
+    };
+
+    let stored_version = table
+        .get("config_version")
+        .and_then(toml::Value::as_integer)
+        .unwrap_or(0);
+    debug!(
+        "Migrating configuration from version {} to {}",
+        stored_version, CURRENT_CONFIG_VERSION
+    );
+
+    let toml::Value::Table(defaults) = toml::Value::try_from(Config::default())
+        .context("Failed to serialize the default configuration")?
+    else {
+        anyhow::bail!("Default configuration is not a TOML table");
+    };
+    for (key, default_value) in defaults {
+        if !table.contains_key(&key) {
+            debug!("Adding missing configuration field '{}'", key);
+            table.insert(key, default_value);
+        }
+    }
+    table.insert(
+        "config_version".to_string(),
+        toml::Value::Integer(CURRENT_CONFIG_VERSION.into()),
+    );
+
+    toml::Value::Table(table)
+        .try_into()
+        .context("Failed to read the migrated configuration")
+}
+
+/// Reads the stored configuration file, migrates it and stores the result.
+fn migrate_stored_configuration() -> Result<Config> {
+    let path = confy::get_configuration_file_path("cratup_auto", "config")
+        .context("Failed to locate configuration file")?;
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read configuration file {:?}", path))?;
+    let raw: toml::Value = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse configuration file {:?}", path))?;
+
+    let config = migrate_config(raw)?;
+    confy::store("cratup_auto", "config", &config).context("Failed to save configuration")?;
+    debug!("Migrated configuration saved to {:?}", path);
+
+    Ok(config)
+}
+
 /// Loads and provides default configuration settings for the application.
 ///
 /// This function attempts to load existing configuration settings and falls
@@ -69,13 +343,38 @@ pub fn initialize_configuration() -> Result<()> {
 ///
 /// # Notes
 /// - If configuration loading fails, default values will be used.
+/// - A configuration stored by an older version is migrated with `migrate_config`
+///   and saved back.
+/// - When the `CRATUP_CONFIG` environment variable is set, the configuration is read
+///   from that file with `Config::load_from_path` instead.
 pub fn load_default_configuration() -> Result<Config> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_ENV) {
+        debug!("Loading configuration from {} = {:?}", CONFIG_PATH_ENV, path);
+        return Config::load_from_path(Path::new(&path)).or_else(|err| {
+            warn!(
+                "Failed to load configuration: {:#}. Using default configuration.",
+                err
+            );
+            Ok(Config::default())
+        });
+    }
+
     debug!("Default configuration loading using confy...");
 
     // Attempt to load the configuration using confy
     match confy::load("cratup_auto", "config") {
         Ok(config) => {
+            let config: Config = config;
             debug!("Configuration successfully loaded.");
+            if config.config_version < CURRENT_CONFIG_VERSION {
+                return migrate_stored_configuration().or_else(|err| {
+                    warn!(
+                        "Failed to migrate configuration: {}. Using loaded configuration.",
+                        err
+                    );
+                    Ok(config)
+                });
+            }
             Ok(config)
         }
         Err(err) => {
@@ -87,3 +386,143 @@ pub fn load_default_configuration() -> Result<Config> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_has_empty_diff() {
+        assert!(Config::default().diff().is_empty());
+    }
+
+    #[test]
+    fn test_changed_field_appears_in_diff() {
+        let config = Config {
+            always_ask_permission: true,
+            ..Config::default()
+        };
+        let diff = config.diff();
+        assert_eq!(
+            diff,
+            vec![ConfigField {
+                name: "always_ask_permission",
+                default_value: "false".to_string(),
+                current_value: "true".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_migrate_version_0_config() {
+        let raw: toml::Value = toml::from_str(
+            r#"
+always_ask_permission = true
+match_warn_threshold = 3
+"#,
+        )
+        .unwrap();
+
+        let config = migrate_config(raw).unwrap();
+        let default = Config::default();
+
+        assert_eq!(config.config_version, CURRENT_CONFIG_VERSION);
+        assert!(config.always_ask_permission);
+        assert_eq!(config.match_warn_threshold, 3);
+        assert_eq!(config.match_error_threshold, default.match_error_threshold);
+        assert_eq!(
+            config.include_dev_dependencies,
+            default.include_dev_dependencies
+        );
+        assert_eq!(
+            config.include_build_dependencies,
+            default.include_build_dependencies
+        );
+        assert_eq!(config.excluded_dirs, default.excluded_dirs);
+        assert_eq!(config.watch_debounce_ms, default.watch_debounce_ms);
+        assert_eq!(config.auto_git_commit, default.auto_git_commit);
+        assert_eq!(
+            config.git_commit_message_template,
+            default.git_commit_message_template
+        );
+        assert_eq!(config.auto_git_tag, default.auto_git_tag);
+        assert_eq!(config.git_tag_prefix, default.git_tag_prefix);
+        assert_eq!(
+            config.publish_check_crates_io,
+            default.publish_check_crates_io
+        );
+        assert_eq!(config.toml_grammar, default.toml_grammar);
+    }
+
+    #[test]
+    fn test_missing_config_version_reads_as_0() {
+        let config: Config = toml::from_str("always_ask_permission = true\n").unwrap();
+        assert_eq!(config.config_version, 0);
+    }
+
+    #[test]
+    fn test_save_and_load_from_path_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("nested").join("config.toml");
+        let config = Config {
+            always_ask_permission: true,
+            match_warn_threshold: 4,
+            excluded_dirs: vec!["examples".to_string(), "benches".to_string()],
+            watch_debounce_ms: 50,
+            toml_grammar: TomlGrammar::Toml10,
+            ..Config::default()
+        };
+
+        config.save_to_path(&path).unwrap();
+        let loaded = Config::load_from_path(&path).unwrap();
+
+        assert_eq!(loaded.fields(), config.fields());
+        assert_eq!(loaded.config_version, CURRENT_CONFIG_VERSION);
+    }
+
+    #[test]
+    fn test_initialize_configuration_from_values_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config.toml");
+        // SAFETY: the other tests only access the environment through `std::env`, which
+        // serialises these calls, and none of them reads CRATUP_CONFIG.
+        unsafe { std::env::set_var(CONFIG_PATH_ENV, &path) };
+
+        let config = Config {
+            always_ask_permission: true,
+            match_error_threshold: 9,
+            ..Config::default()
+        };
+        initialize_configuration_from_values(config.clone()).unwrap();
+        let mut loaded = load_default_configuration().unwrap();
+        assert_eq!(loaded.fields(), config.fields());
+
+        loaded.always_ask_permission = false;
+        initialize_configuration_from_values(loaded.clone()).unwrap();
+        let reloaded = load_default_configuration().unwrap();
+        unsafe { std::env::remove_var(CONFIG_PATH_ENV) };
+
+        assert_eq!(reloaded.fields(), loaded.fields());
+        assert!(!reloaded.always_ask_permission);
+        assert_eq!(reloaded.match_error_threshold, 9);
+    }
+
+    #[test]
+    fn test_load_from_path_fills_missing_fields() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config.toml");
+        std::fs::write(&path, "match_error_threshold = 9\n").unwrap();
+
+        let loaded = Config::load_from_path(&path).unwrap();
+
+        assert_eq!(loaded.match_error_threshold, 9);
+        assert_eq!(loaded.config_version, CURRENT_CONFIG_VERSION);
+        assert_eq!(loaded.watch_debounce_ms, Config::default().watch_debounce_ms);
+    }
+
+    #[test]
+    fn test_load_from_path_missing_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(Config::load_from_path(&tmp.path().join("missing.toml")).is_err());
+    }
+}
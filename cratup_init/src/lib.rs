@@ -2,6 +2,11 @@ mod config;
 mod log;
 
 pub use config::initialize_configuration;
+pub use config::initialize_configuration_from_values;
 pub use config::load_default_configuration;
+pub use config::migrate_config;
 pub use config::Config;
+pub use config::CURRENT_CONFIG_VERSION;
+pub use config::CONFIG_PATH_ENV;
+pub use config::ConfigField;
 pub use log::initialize_logger;
@@ -1,8 +1,9 @@
 use anyhow::{Context, Result};
 use env_logger::Builder;
-use log::{debug, warn, LevelFilter};
+use log::{LevelFilter, Log, Metadata, Record, debug, warn};
 use rolling_file::{BasicRollingFileAppender, RollingConditionBasic};
-use std::fs::{create_dir_all, read_dir, remove_file};
+use std::fs::{File, OpenOptions, create_dir_all, read_dir, remove_file};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
@@ -13,7 +14,9 @@ use std::sync::Mutex;
 /// a global log level for the application.
 ///
 /// # Parameters
-/// - `log_level`: The level of logging to be displayed (e.g., debug, info, warn, error)
+/// - `filter`: An `env_logger` filter string, e.g. `"debug"` or `"debug,tree_sitter=off"`
+/// - `log_file`: Optional file that additionally receives every log message at `Trace`
+///   level, whatever the filter
 ///
 /// # Returns
 /// - `Result<()>`: Indicates successful initialization of the logger
@@ -25,9 +28,10 @@ use std::sync::Mutex;
 /// - Logs are formatted with timestamp, log level, and message
 /// - Creates the log directory if it doesn't exist
 /// - Deletes older log files if the maximum number of files is exceeded
-/// - Initializes the global logger with the specified log level
+/// - Initializes the global logger with the specified filter
 /// - Logs errors when writing to the log file fails
-pub fn initialize_logger(log_level: LevelFilter) -> Result<()> {
+/// - Appends to `log_file` when given, creating it if needed
+pub fn initialize_logger(filter: &str, log_file: Option<&Path>) -> Result<()> {
     let max_log_files = 2;
     let log_dir = directories::UserDirs::new()
         .and_then(|dirs| dirs.document_dir().map(|d| d.join("cratup_auto_logs")))
@@ -55,7 +59,7 @@ pub fn initialize_logger(log_level: LevelFilter) -> Result<()> {
     manage_log_files(&log_dir, max_log_files).context("Failed to manage log files")?;
 
     let mut builder = Builder::new();
-    builder.filter(None, log_level);
+    builder.parse_filters(filter);
 
     builder.format(move |buf, record| {
         use console::style;
@@ -82,25 +86,103 @@ pub fn initialize_logger(log_level: LevelFilter) -> Result<()> {
 
         // Also write a plain-text log entry to the rolling file.
         let log_entry = format!("{} - {} - {}\n", ts, level, msg);
-        if let Ok(mut appender) = rolling_appender.lock() {
-            if let Err(e) = appender.write(log_entry.as_bytes()) {
-                warn!("Failed to write log entry to file: {:?}", e);
-            }
+        if let Ok(mut appender) = rolling_appender.lock()
+            && let Err(e) = appender.write(log_entry.as_bytes())
+        {
+            warn!("Failed to write log entry to file: {:?}", e);
         }
 
         Ok(())
     });
 
-    builder.try_init().context("Failed to initialize logger")?;
+    let logger = TeeLogger::new(builder.build(), log_file)?;
+    log::set_max_level(logger.max_level());
+    log::set_boxed_logger(Box::new(logger)).context("Failed to initialize logger")?;
 
     debug!(
         "Logger initialized successfully. Logs will be written to {:?}",
         log_file_path
     );
+    if let Some(log_file) = log_file {
+        debug!("Trace logs will also be written to {:?}", log_file);
+    }
 
     Ok(())
 }
 
+/// Logger sending records that pass the console filter to `console`, and every record
+/// to `log_file` when there is one.
+struct TeeLogger {
+    console: env_logger::Logger,
+    log_file: Option<Mutex<File>>,
+}
+
+// TeeLogger
+impl TeeLogger {
+    /// Wraps `console`, opening `log_file` in append mode when given.
+    fn new(console: env_logger::Logger, log_file: Option<&Path>) -> Result<Self> {
+        let log_file = match log_file {
+            Some(path) => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Failed to open log file {:?}", path))?;
+                Some(Mutex::new(file))
+            }
+            None => None,
+        };
+        Ok(TeeLogger { console, log_file })
+    }
+
+    /// Most verbose level any of the outputs accepts.
+    fn max_level(&self) -> LevelFilter {
+        if self.log_file.is_some() {
+            LevelFilter::Trace
+        } else {
+            self.console.filter()
+        }
+    }
+}
+
+impl Log for TeeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.log_file.is_some() || self.console.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.console.matches(record) {
+            self.console.log(record);
+        }
+
+        if let Some(log_file) = &self.log_file
+            && let Ok(mut file) = log_file.lock()
+        {
+            // Nowhere left to report a failed write, the file is the fallback output.
+            let _ = writeln!(
+                file,
+                "{} - {} - {} - {}",
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default(),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+        if let Some(log_file) = &self.log_file
+            && let Ok(mut file) = log_file.lock()
+        {
+            let _ = file.flush();
+        }
+    }
+}
+
 /// Manages log files in a directory, ensuring the number of files does not exceed a specified limit.
 ///
 /// This function handles log file management by reading the directory, collecting and filtering log files,
@@ -134,7 +216,7 @@ fn manage_log_files(log_dir: &Path, max_log_files: usize) -> Result<()> {
             path
         })
         .filter(|path| {
-            let is_log = path.is_file() && path.extension().map_or(false, |ext| ext == "log");
+            let is_log = path.is_file() && path.extension().is_some_and(|ext| ext == "log");
             if is_log {
                 debug!("Identified as log file: {:?}", path);
             }
@@ -150,7 +232,7 @@ fn manage_log_files(log_dir: &Path, max_log_files: usize) -> Result<()> {
 
     // Delete old log files if the number exceeds the limit
     while log_files.len() > max_log_files {
-        if let Some(old_file) = log_files.get(0).cloned() {
+        if let Some(old_file) = log_files.first().cloned() {
             debug!("Attempting to delete old log file: {:?}", old_file);
             if let Err(e) = remove_file(&old_file) {
                 warn!("Failed to delete old log file {:?}: {}", old_file, e);
@@ -165,3 +247,48 @@ fn manage_log_files(log_dir: &Path, max_log_files: usize) -> Result<()> {
     debug!("Log file management completed successfully.");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn log_record(logger: &TeeLogger, level: Level, message: &str) {
+        logger.log(
+            &Record::builder()
+                .level(level)
+                .target("cratup_init")
+                .args(format_args!("{}", message))
+                .build(),
+        );
+        logger.flush();
+    }
+
+    fn console_logger(filter: &str) -> env_logger::Logger {
+        Builder::new().parse_filters(filter).build()
+    }
+
+    #[test]
+    fn test_log_file_receives_trace_messages() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log_path = tmp.path().join("cratup.log");
+
+        let logger = TeeLogger::new(console_logger("warn"), Some(&log_path)).unwrap();
+        assert_eq!(logger.max_level(), LevelFilter::Trace);
+        log_record(&logger, Level::Trace, "tracing into the file");
+
+        let content = std::fs::read_to_string(&log_path).unwrap();
+        assert!(content.contains("TRACE - cratup_init - tracing into the file"));
+    }
+
+    #[test]
+    fn test_no_log_file_is_created_without_path() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        let logger = TeeLogger::new(console_logger("warn"), None).unwrap();
+        assert_eq!(logger.max_level(), LevelFilter::Warn);
+        log_record(&logger, Level::Warn, "console only");
+
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 0);
+    }
+}
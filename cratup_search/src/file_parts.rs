@@ -10,6 +10,61 @@ pub struct FileParts {
     pub file: String,
 }
 
+impl FileParts {
+    /// Creates the file parts from already known path components.
+    pub fn new(
+        prefix: impl Into<String>,
+        parent: impl Into<String>,
+        file: impl Into<String>,
+    ) -> Self {
+        FileParts {
+            prefix: prefix.into(),
+            parent: parent.into(),
+            file: file.into(),
+        }
+    }
+
+    /// Returns a builder where every component starts out empty.
+    pub fn builder() -> FilePartsBuilder {
+        FilePartsBuilder::default()
+    }
+}
+
+/// Builder for `FileParts`, for callers that set the path components one by one.
+#[derive(Debug, Default)]
+pub struct FilePartsBuilder {
+    prefix: String,
+    parent: String,
+    file: String,
+}
+
+impl FilePartsBuilder {
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    pub fn parent(mut self, parent: impl Into<String>) -> Self {
+        self.parent = parent.into();
+        self
+    }
+
+    pub fn file(mut self, file: impl Into<String>) -> Self {
+        self.file = file.into();
+        self
+    }
+
+    pub fn build(self) -> FileParts {
+        FileParts::new(self.prefix, self.parent, self.file)
+    }
+}
+
+impl From<FilePartsBuilder> for FileParts {
+    fn from(builder: FilePartsBuilder) -> Self {
+        builder.build()
+    }
+}
+
 /// Directory type with file parts.
 #[derive(Debug, PartialEq, Eq)]
 pub enum DirectoryType {
@@ -17,6 +72,26 @@ pub enum DirectoryType {
     Nested(FileParts),
 }
 
+impl DirectoryType {
+    /// Returns how many path components deep the file is relative to the current directory:
+    /// 0 for an empty `Start`, 1 for a file in the start directory, 2 for a file in a
+    /// direct subdirectory, and 2 plus the number of prefix components for deeper files.
+    pub fn depth(&self) -> usize {
+        match self {
+            DirectoryType::Start(parts) => {
+                if parts.prefix.is_empty() && parts.parent.is_empty() && parts.file.is_empty() {
+                    0
+                } else {
+                    1
+                }
+            }
+            DirectoryType::Nested(parts) => {
+                2 + parts.prefix.split('/').filter(|c| !c.is_empty()).count()
+            }
+        }
+    }
+}
+
 /// Splits the given file path string relative to the current directory string.
 ///
 /// # Parameters
@@ -34,14 +109,10 @@ pub enum DirectoryType {
 ///
 pub fn split_dir_path_parts_str(file_path_str: &str, current_dir_str: &str) -> DirectoryType {
     // Determine the relative path by attempting to remove the current_dir prefix.
-    let relative = if file_path_str.starts_with(current_dir_str) {
-        // Remove the prefix. If the prefix is not empty and is immediately followed by a separator,
+    let relative = if let Some(rel) = file_path_str.strip_prefix(current_dir_str) {
+        // If the prefix is not empty and is immediately followed by a separator,
         // also remove the separator.
-        let mut rel = &file_path_str[current_dir_str.len()..];
-        if rel.starts_with('/') {
-            rel = &rel[1..];
-        }
-        rel
+        rel.strip_prefix('/').unwrap_or(rel)
     } else {
         file_path_str
     };
@@ -58,45 +129,23 @@ pub fn split_dir_path_parts_str(file_path_str: &str, current_dir_str: &str) -> D
 
     // Match based on the number of components
     match components.len() {
-        0 => {
-            // In case there is no remaining information in the path.
-            let parts = FileParts {
-                prefix: String::new(),
-                parent: String::new(),
-                file: String::new(),
-            };
-            DirectoryType::Start(parts)
-        }
-        1 => {
-            // Only a single component means it is a file in the start directory.
-            let parts = FileParts {
-                prefix: String::new(),
-                parent: String::new(),
-                file: components[0].clone(),
-            };
-            DirectoryType::Start(parts)
-        }
-        2 => {
-            // Two components: first is parent directory, second is file.
-            let parts = FileParts {
-                prefix: String::new(),
-                parent: components[0].clone(),
-                file: components[1].clone(),
-            };
-            DirectoryType::Nested(parts)
-        }
+        // In case there is no remaining information in the path.
+        0 => DirectoryType::Start(FileParts::new("", "", "")),
+        // Only a single component means it is a file in the start directory.
+        1 => DirectoryType::Start(FileParts::new("", "", components[0].clone())),
+        // Two components: first is parent directory, second is file.
+        2 => DirectoryType::Nested(FileParts::new(
+            "",
+            components[0].clone(),
+            components[1].clone(),
+        )),
         _ => {
             // More than two components: the last component is the file,
             // the second-to-last is the parent, and any preceding are joined as a prefix.
             let file = components.last().unwrap().clone();
             let parent = components[components.len() - 2].clone();
             let prefix = components[..components.len() - 2].join("/");
-            let parts = FileParts {
-                prefix,
-                parent,
-                file,
-            };
-            DirectoryType::Nested(parts)
+            DirectoryType::Nested(FileParts::new(prefix, parent, file))
         }
     }
 }
@@ -117,7 +166,10 @@ pub fn build_directory_display(file_path: &str, current_dir: &str) -> String {
     match dir_type {
         DirectoryType::Start(ref parts) => {
             debug!("Processing Start directory type with parts: {:?}", parts);
-            if parts.prefix.is_empty() && parts.parent.is_empty() {
+            if parts.parent.is_empty() && parts.file.is_empty() {
+                debug!("Path is the current directory itself");
+                ".".green().to_string()
+            } else if parts.prefix.is_empty() && parts.parent.is_empty() {
                 debug!("Simple file case - no prefix or parent");
                 format!("{}/{}", ".".green(), parts.file)
             } else if !parts.prefix.is_empty() {
@@ -156,6 +208,10 @@ mod tests {
             file: String::new(),
         });
         assert_eq!(result, expected);
+        assert_eq!(
+            build_directory_display("some/path", "some/path"),
+            ".".green().to_string()
+        );
     }
 
     #[test]
@@ -206,4 +262,67 @@ mod tests {
         });
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_depth_values() {
+        let cases = [
+            ("base/dir", 0),
+            ("base/dir/Cargo.toml", 1),
+            ("base/dir/a/Cargo.toml", 2),
+            ("base/dir/a/b/Cargo.toml", 3),
+            ("base/dir/a/b/c/d/Cargo.toml", 5),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(
+                split_dir_path_parts_str(path, "base/dir").depth(),
+                expected,
+                "unexpected depth for {}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_is_shorter_for_shallower_depths() {
+        let lengths: Vec<usize> = [
+            "base/dir/Cargo.toml",
+            "base/dir/crate_a/Cargo.toml",
+            "base/dir/crate_a/crate_b/Cargo.toml",
+            "base/dir/crate_a/crate_b/crate_c/Cargo.toml",
+        ]
+        .iter()
+        .map(|path| build_directory_display(path, "base/dir").len())
+        .collect();
+
+        assert!(lengths.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", lengths);
+    }
+
+    #[test]
+    fn test_builder_matches_split_output() {
+        let from_split = split_dir_path_parts_str("base/dir/crates/core/Cargo.toml", "base/dir");
+        let from_builder = DirectoryType::Nested(
+            FileParts::builder()
+                .prefix("crates")
+                .parent("core")
+                .file("Cargo.toml")
+                .build(),
+        );
+        assert_eq!(from_split, from_builder);
+
+        let from_split = split_dir_path_parts_str("base/dir/Cargo.toml", "base/dir");
+        let from_builder = DirectoryType::Start(FileParts::builder().file("Cargo.toml").into());
+        assert_eq!(from_split, from_builder);
+    }
+
+    #[test]
+    fn test_new_matches_builder() {
+        assert_eq!(
+            FileParts::new("a/b", "c", "Cargo.toml"),
+            FileParts::builder()
+                .file("Cargo.toml")
+                .parent("c")
+                .prefix("a/b")
+                .build()
+        );
+    }
 }
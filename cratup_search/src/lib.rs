@@ -1,9 +1,25 @@
 mod file_parts;
 mod search;
 mod string_format;
+#[cfg(test)]
+mod test_support;
 
+pub use file_parts::DirectoryType;
+pub use file_parts::FileParts;
+pub use file_parts::FilePartsBuilder;
+pub use file_parts::split_dir_path_parts_str;
 pub use search::Search;
+pub use search::TransitiveDependency;
+pub use search::TraversalOrder;
+pub use search::filter_by_package_name;
+pub use search::filter_by_version;
+pub use search::find_manifests;
+pub use string_format::MatchThresholds;
 pub use string_format::VersionMatch;
+pub use string_format::VersionMatchSummary;
+pub use string_format::build_matches_info;
+pub use string_format::build_matches_info_with_format;
 pub use string_format::get_colored_dir_path_and_matches;
 pub use string_format::get_colored_dir_path;
+pub use string_format::get_colored_dir_path_str;
 pub use string_format::get_colored_pkg_deps;
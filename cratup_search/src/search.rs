@@ -2,24 +2,81 @@ use anyhow::Context;
 use anyhow::Result;
 // use anyhow::Error;
 
-use colored::ColoredString;
-use log::{debug, trace};
+use colored::{ColoredString, Colorize};
+use glob::Pattern;
+use log::{debug, trace, warn};
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use semver::{Version, VersionReq};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 // use std::process;
 use strsim::levenshtein;
 use walkdir::WalkDir;
 
-use crate::string_format::{get_colored_pkg_deps, get_colored_dir_path};
-use cratup_tree_sitter::{PackageAndDeps, PkgInfo, TomlParser};
+use crate::string_format::{get_colored_pkg_deps, get_colored_dir_path_str};
+use cratup_tree_sitter::{
+    PackageAndDeps, PkgInfo, TomlGrammar, TomlParser, TomlParserError, VersionUpdate,
+};
+#[cfg(feature = "serde")]
+use cratup_tree_sitter::DepsInfo;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Document written by `Search::export_toml`.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct TomlExport<'a> {
+    package: Vec<TomlExportEntry<'a>>,
+}
+
+/// One `[[package]]` element of `TomlExport`. Entries without a `[package]` section
+/// only have `path` and `dependencies`.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct TomlExportEntry<'a> {
+    path: String,
+    #[serde(flatten)]
+    package: Option<&'a PkgInfo>,
+    dependencies: &'a [DepsInfo],
+}
+
+/// A package that depends on the searched package, directly or through other
+/// packages of the workspace, as returned by `Search::search_transitive`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitiveDependency {
+    /// The package that (transitively) depends on the searched one.
+    pub dependee: PkgInfo,
+    /// Package names from `dependee` down to the searched package, both included.
+    pub chain: Vec<String>,
+}
+
+/// Order of the search results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraversalOrder {
+    /// Sorted by full path, so every crate is followed by the crates nested below it.
+    #[default]
+    DepthFirst,
+    /// Sorted by the number of path components, shallowest first, then by path, so
+    /// top-level crates come before nested ones.
+    BreadthFirst,
+}
 
 /// The Search struct holds the current directory, the version to query, and optionally a package name.
 /// It also includes a list of directories with package/dependency information.
 pub struct Search {
     dir_path: PathBuf,
+    /// `dir_path` as a string, converted once for the display of every result.
+    dir_path_str: String,
     version: Option<String>,
     package_name: Option<String>,
+    /// Keeps the path and git dependencies without a version in the results.
+    show_unversioned: bool,
+    traversal_order: TraversalOrder,
+    /// TOML version the package of each Cargo.toml is read with, also when replacing.
+    grammar: TomlGrammar,
     pub pkg_deps_dirs: Vec<(PathBuf, PackageAndDeps)>,
 }
 
@@ -31,18 +88,181 @@ impl Search {
         version: Option<String>,
         package_name: Option<String>,
     ) -> Result<Self, Box<dyn Error>> {
-        let package_dirs = load_dirs_pkgs_deps(&dir_path)?;
+        Self::new_excluding(dir_path, version, package_name, &[])
+    }
+
+    /// Same as `new`, but also skips every directory named in `excluded_dirs`
+    /// while walking `dir_path`.
+    pub fn new_excluding(
+        dir_path: PathBuf,
+        version: Option<String>,
+        package_name: Option<String>,
+        excluded_dirs: &[String],
+    ) -> Result<Self, Box<dyn Error>> {
+        Self::new_excluding_with_jobs(dir_path, version, package_name, excluded_dirs, 1)
+    }
+
+    /// Same as `new_excluding`, but parses the Cargo.toml files on `jobs` threads
+    /// when `jobs` is greater than 1.
+    pub fn new_excluding_with_jobs(
+        dir_path: PathBuf,
+        version: Option<String>,
+        package_name: Option<String>,
+        excluded_dirs: &[String],
+        jobs: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        Self::new_matching(
+            dir_path,
+            version,
+            package_name,
+            excluded_dirs,
+            jobs,
+            "Cargo.toml",
+            TomlGrammar::default(),
+        )
+    }
+
+    /// Same as `new_excluding_with_jobs`, but loads the files whose name matches the glob
+    /// `file_pattern`, e.g. `Pkgfile.toml`, `Cargo*` or `*.toml`, instead of only
+    /// `Cargo.toml`, and reads their package with `grammar`. Fails on an invalid pattern or
+    /// a matching file that cannot be parsed.
+    pub fn new_matching(
+        dir_path: PathBuf,
+        version: Option<String>,
+        package_name: Option<String>,
+        excluded_dirs: &[String],
+        jobs: usize,
+        file_pattern: &str,
+        grammar: TomlGrammar,
+    ) -> Result<Self, Box<dyn Error>> {
+        let file_pattern = Pattern::new(file_pattern)
+            .with_context(|| format!("Invalid file pattern '{}'", file_pattern))?;
+        let package_dirs = if jobs > 1 {
+            let jobs = Some(jobs);
+            load_dirs_pkgs_deps_parallel(&dir_path, excluded_dirs, &file_pattern, jobs, grammar)?
+        } else {
+            load_dirs_pkgs_deps(&dir_path, excluded_dirs, &file_pattern, grammar)?
+        };
+        let mut search = Self {
+            dir_path_str: dir_path.to_string_lossy().into_owned(),
+            dir_path,
+            version,
+            package_name,
+            show_unversioned: false,
+            traversal_order: TraversalOrder::default(),
+            grammar,
+            pkg_deps_dirs: package_dirs,
+        };
+        search.sort_by_path();
+        Ok(search)
+    }
+
+    /// Creates a Search instance for the given Cargo.toml only, without walking any directory.
+    /// `pkg_deps_dirs` holds at most one entry. No filtering is done at this stage.
+    pub fn new_from_manifest(
+        manifest_path: PathBuf,
+        version: Option<String>,
+        package_name: Option<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        debug!("Loading single manifest: {:?}", manifest_path);
+        let dir_path = manifest_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let package_dirs = load_pkg_deps_from_file(&manifest_path, TomlGrammar::default())?
+            .into_iter()
+            .collect();
+
         Ok(Self {
+            dir_path_str: dir_path.to_string_lossy().into_owned(),
             dir_path,
             version,
             package_name,
+            show_unversioned: false,
+            traversal_order: TraversalOrder::default(),
+            grammar: TomlGrammar::default(),
             pkg_deps_dirs: package_dirs,
         })
     }
 
+    /// Reads only `manifest_path`, applies the version and package name filters like
+    /// `search` does and returns what is left, or `None` when nothing matches.
+    pub fn search_single_file(
+        manifest_path: &Path,
+        version: Option<String>,
+        package_name: Option<String>,
+    ) -> Result<Option<PackageAndDeps>, Box<dyn Error>> {
+        let mut search = Self::new_from_manifest(manifest_path.to_path_buf(), version, package_name)?;
+        search.search()?;
+        Ok(search
+            .pkg_deps_dirs
+            .into_iter()
+            .next()
+            .map(|(_, pkg_and_deps)| pkg_and_deps))
+    }
+
+    /// Parses the Cargo.toml contents of `contents`, keyed by their path, and applies the
+    /// version and package name filters like `search` does, without touching the file
+    /// system. Contents with neither a package nor dependencies are left out.
+    pub fn search_in_files(
+        contents: HashMap<PathBuf, String>,
+        version: Option<String>,
+        package_name: Option<String>,
+    ) -> Result<Vec<(PathBuf, PackageAndDeps)>, Box<dyn Error>> {
+        debug!("Searching {} in-memory Cargo.toml file(s)", contents.len());
+        let package_dirs = contents
+            .into_iter()
+            .map(|(file_path, content)| {
+                pkg_deps_from_source(file_path, &content, TomlGrammar::default())
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let mut search = Self {
+            dir_path: PathBuf::new(),
+            dir_path_str: String::new(),
+            version,
+            package_name,
+            show_unversioned: false,
+            traversal_order: TraversalOrder::default(),
+            grammar: TomlGrammar::default(),
+            pkg_deps_dirs: package_dirs,
+        };
+        search.search()?;
+        Ok(search.pkg_deps_dirs)
+    }
+
+    /// Returns the directory the search was started from.
+    pub fn dir_path(&self) -> &Path {
+        &self.dir_path
+    }
+
+    /// Keeps the path and git dependencies without a version in the results of `search`,
+    /// e.g. `local = { path = "../local" }`, so that non-registry dependencies show up.
+    /// They are matched by the package name filter only and dropped by a version filter.
+    pub fn with_show_unversioned(mut self, show: bool) -> Self {
+        self.show_unversioned = show;
+        self
+    }
+
+    /// Sets the order of the results, `TraversalOrder::DepthFirst` by default.
+    pub fn with_traversal_order(mut self, order: TraversalOrder) -> Self {
+        self.traversal_order = order;
+        self.sort_by_path();
+        self
+    }
+
     /// The search method applies filtering by version and package name.
     /// It updates the pkg_deps_dirs field with the filtered results and returns a clone of it.
     pub fn search(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.show_unversioned {
+            self.pkg_deps_dirs
+                .iter_mut()
+                .for_each(|(_, pkg_and_deps)| pkg_and_deps.unversioned_dependencies.clear());
+        }
+
         // Apply version filtering if specified.
         if let Some(ref ver) = self.version {
             self.pkg_deps_dirs = filter_by_version(self.pkg_deps_dirs.clone(), ver);
@@ -55,10 +275,85 @@ impl Search {
 
         // Create a clone of package name to avoid borrowing `self.package_name` immutably for too long.
         if let Some(pkg_name) = self.package_name.clone() {
-            {
-                // Use a narrower scope so that the immutable borrow from pkg_name ends quickly.
-                self.filter_by_package_name(&pkg_name)?;
+            self.pkg_deps_dirs = filter_by_package_name(self.pkg_deps_dirs.clone(), &pkg_name);
+            debug!(
+                "After filtering by package name '{}', {} result(s) remain",
+                pkg_name,
+                self.pkg_deps_dirs.len()
+            );
+        }
+
+        self.sort_by_path();
+        Ok(())
+    }
+
+    /// Replaces `current_version` with `new_version` in the package and dependencies of
+    /// every Cargo.toml currently in the results, only those named `package_name` when it
+    /// is given, and returns the paths of the files written. Call `search` first to only
+    /// update the files it finds.
+    pub fn search_and_replace(
+        &self,
+        current_version: &str,
+        new_version: &str,
+        package_name: Option<&str>,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let version_update = VersionUpdate {
+            package_name,
+            current_version,
+            new_version,
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: self.grammar,
+        };
+        debug!(
+            "Replacing {} with {} in {} file(s)",
+            current_version,
+            new_version,
+            self.pkg_deps_dirs.len()
+        );
+
+        let mut updated_files = Vec::new();
+        for (file_path, _) in &self.pkg_deps_dirs {
+            let content = fs::read_to_string(file_path)
+                .with_context(|| format!("Failed to read {:?}", file_path))?;
+            let updated_source = version_update.update_all_pkg_and_deps(&content);
+            if updated_source == content {
+                debug!("Nothing to replace in {:?}", file_path);
+                continue;
             }
+
+            fs::write(file_path, updated_source)
+                .with_context(|| format!("Failed to write {:?}", file_path))?;
+            updated_files.push(file_path.clone());
+        }
+
+        debug!("Updated {} file(s)", updated_files.len());
+        Ok(updated_files)
+    }
+
+    /// Runs `search` and returns the number of matching Cargo.toml entries, without
+    /// falling back to the fuzzy search.
+    pub fn count_matches(&mut self) -> Result<usize, Box<dyn Error>> {
+        self.search()?;
+        debug!("Counted {} matching entries", self.pkg_deps_dirs.len());
+        Ok(self.pkg_deps_dirs.len())
+    }
+
+    /// Filters by a semver requirement such as `"^1"` or `">=0.4, <0.5"` instead of an exact
+    /// version, then applies the package name filter like `search` does.
+    pub fn search_by_range(&mut self, req: &str) -> Result<(), Box<dyn Error>> {
+        let version_req = VersionReq::parse(req)
+            .with_context(|| format!("Invalid version requirement '{}'", req))?;
+
+        self.pkg_deps_dirs = filter_by_version_req(self.pkg_deps_dirs.clone(), &version_req);
+        debug!(
+            "After filtering by version requirement '{}', {} result(s) remain",
+            req,
+            self.pkg_deps_dirs.len()
+        );
+
+        if let Some(pkg_name) = self.package_name.clone() {
+            self.pkg_deps_dirs = filter_by_package_name(self.pkg_deps_dirs.clone(), &pkg_name);
             debug!(
                 "After filtering by package name '{}', {} result(s) remain",
                 pkg_name,
@@ -66,52 +361,363 @@ impl Search {
             );
         }
 
+        self.sort_by_path();
         Ok(())
     }
 
+    /// Keeps the entries whose version starts with `prefix` (e.g. `"0.4."` for every
+    /// 0.4.x version), then applies the package name filter like `search` does.
+    /// Updates `pkg_deps_dirs` with the results and returns a clone of them.
+    pub fn find_with_version_prefix(
+        &mut self,
+        prefix: &str,
+    ) -> Result<Vec<(PathBuf, PackageAndDeps)>, Box<dyn Error>> {
+        self.pkg_deps_dirs = filter_by_version_prefix(self.pkg_deps_dirs.clone(), prefix);
+        debug!(
+            "After filtering by version prefix '{}', {} result(s) remain",
+            prefix,
+            self.pkg_deps_dirs.len()
+        );
+
+        if let Some(pkg_name) = self.package_name.clone() {
+            self.pkg_deps_dirs = filter_by_package_name(self.pkg_deps_dirs.clone(), &pkg_name);
+            debug!(
+                "After filtering by package name '{}', {} result(s) remain",
+                pkg_name,
+                self.pkg_deps_dirs.len()
+            );
+        }
+
+        self.sort_by_path();
+        Ok(self.pkg_deps_dirs.clone())
+    }
+
+    /// Sorts the results by their file path so the output does not depend on the
+    /// order in which `WalkDir` returns directory entries. With
+    /// `TraversalOrder::BreadthFirst`, shallower paths come first.
+    fn sort_by_path(&mut self) {
+        match self.traversal_order {
+            TraversalOrder::DepthFirst => self
+                .pkg_deps_dirs
+                .sort_by(|(a, _), (b, _)| a.to_string_lossy().cmp(&b.to_string_lossy())),
+            TraversalOrder::BreadthFirst => self.pkg_deps_dirs.sort_by(|(a, _), (b, _)| {
+                a.components()
+                    .count()
+                    .cmp(&b.components().count())
+                    .then_with(|| a.to_string_lossy().cmp(&b.to_string_lossy()))
+            }),
+        }
+    }
+
+    /// Sorts the results by their number of matching elements, `PackageAndDeps::count`,
+    /// the most matches first when `descending` is true. Call it after `search`; results
+    /// with the same count keep their order.
+    pub fn sort_by_match_count(&mut self, descending: bool) {
+        if descending {
+            self.pkg_deps_dirs
+                .sort_by_key(|(_, pkg_and_deps)| std::cmp::Reverse(pkg_and_deps.count()));
+        } else {
+            self.pkg_deps_dirs
+                .sort_by_key(|(_, pkg_and_deps)| pkg_and_deps.count());
+        }
+        debug!("Sorted {} result(s) by match count", self.pkg_deps_dirs.len());
+    }
+
     /// The fuzzy_search method is used as a fallback when the normal search yields no results.
-    /// It uses similarity scoring (with a given threshold) to search for similar package names.
+    /// It returns every package whose name is similar to the searched one, closest first.
     pub fn fuzzy_search(&self) -> Result<Vec<(PathBuf, PackageAndDeps)>, Box<dyn std::error::Error>> {
         if let Some(ref pkg_name) = self.package_name {
             debug!(
                 "Performing fuzzy search for package: '{}'",
                 pkg_name
             );
-            if let Some((path, pkg_and_deps)) = find_closest_package(&self.dir_path, pkg_name)? {
-                Ok(vec![(path, pkg_and_deps)])
-            } else {
-                Ok(vec![])
-            }
+            find_closest_package(&self.dir_path, pkg_name)
         } else {
             Ok(vec![])
         }
     }
 
+    /// Returns the packages that do not depend on `pkg_name`, sorted by path.
+    /// Entries without a `[package]` section (e.g. virtual workspace manifests) are skipped.
+    pub fn inverse(&self, pkg_name: &str) -> Result<Vec<(PathBuf, PkgInfo)>, Box<dyn Error>> {
+        debug!("Searching for packages that do not depend on: {}", pkg_name);
+
+        let mut results: Vec<(PathBuf, PkgInfo)> = self
+            .pkg_deps_dirs
+            .iter()
+            .filter(|(_, pkg_and_deps)| {
+                !pkg_and_deps
+                    .dependencies
+                    .iter()
+                    .any(|dep| dep.name.replace("\"", "") == pkg_name)
+            })
+            .filter_map(|(path, pkg_and_deps)| {
+                pkg_and_deps
+                    .package
+                    .clone()
+                    .map(|pkg| (path.clone(), pkg))
+            })
+            .collect();
+        results.sort_by(|(a, _), (b, _)| a.to_string_lossy().cmp(&b.to_string_lossy()));
+
+        debug!(
+            "Found {} package(s) without a dependency on '{}'",
+            results.len(),
+            pkg_name
+        );
+        Ok(results)
+    }
+
+    /// Returns the current results as a TOML document with one `[[package]]` element
+    /// per Cargo.toml, holding its `path`, the package `name` and `version`, and a
+    /// `[[package.dependencies]]` array.
+    #[cfg(feature = "serde")]
+    pub fn export_toml(&self) -> Result<String, Box<dyn Error>> {
+        let export = TomlExport {
+            package: self
+                .pkg_deps_dirs
+                .iter()
+                .map(|(path, pkg_and_deps)| TomlExportEntry {
+                    path: path.to_string_lossy().into_owned(),
+                    package: pkg_and_deps.package.as_ref(),
+                    dependencies: &pkg_and_deps.dependencies,
+                })
+                .collect(),
+        };
+        debug!("Exporting {} package(s) as TOML", export.package.len());
+
+        Ok(toml::to_string(&export)?)
+    }
+
+    /// Returns the current results as CSV, with a `path,kind,name,version` header and
+    /// one row per package (`kind` is `package`) and per dependency (`dependency`).
+    #[cfg(feature = "csv")]
+    pub fn export_csv(&self) -> String {
+        let mut out = Vec::new();
+        self.output_csv(&mut out)
+            .expect("Writing to a Vec cannot fail");
+        String::from_utf8(out).expect("CSV fields are valid UTF-8")
+    }
+
+    /// Same as `export_csv`, writing the rows to `writer`.
+    #[cfg(feature = "csv")]
+    pub fn output_csv(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
+        debug!("Exporting {} Cargo.toml file(s) as CSV", self.pkg_deps_dirs.len());
+        let mut csv_writer = csv::Writer::from_writer(writer);
+        csv_writer.write_record(["path", "kind", "name", "version"])?;
+        for (path, pkg_and_deps) in &self.pkg_deps_dirs {
+            let path = path.to_string_lossy();
+            let package = pkg_and_deps
+                .package
+                .iter()
+                .map(|pkg| ("package", &pkg.name, &pkg.version));
+            let dependencies = pkg_and_deps
+                .dependencies
+                .iter()
+                .map(|dep| ("dependency", &dep.name, &dep.version));
+            for (kind, name, version) in package.chain(dependencies) {
+                csv_writer.write_record([path.as_ref(), kind, name, version])?;
+            }
+        }
+        csv_writer.flush()
+    }
+
+    /// Returns every version of the package `name` found in the loaded Cargo.toml files,
+    /// as its own `[package]` version or in any dependency table, mapped to the files
+    /// using it, sorted by path. More than one key means the versions drifted apart.
+    pub fn find_all_versions_of(&self, name: &str) -> HashMap<String, Vec<PathBuf>> {
+        debug!("Collecting every version of '{}'", name);
+        let strip_quotes = |s: &str| s.replace("\"", "");
+
+        let mut versions: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for (path, pkg_and_deps) in &self.pkg_deps_dirs {
+            let mut found: Vec<String> = pkg_and_deps
+                .package
+                .iter()
+                .map(|pkg| (&pkg.name, &pkg.version))
+                .chain(
+                    pkg_and_deps
+                        .all_dependencies()
+                        .map(|dep| (&dep.name, &dep.version)),
+                )
+                .filter(|(entry_name, _)| strip_quotes(entry_name) == name)
+                .map(|(_, version)| version.clone())
+                .collect();
+
+            found.sort();
+            found.dedup();
+            for version in found {
+                versions.entry(version).or_default().push(path.clone());
+            }
+        }
+
+        for paths in versions.values_mut() {
+            paths.sort();
+        }
+        debug!("Found {} version(s) of '{}'", versions.len(), name);
+        versions
+    }
+
+    /// Returns every loaded package that depends on `pkg_name`, directly or through a
+    /// chain of loaded packages, with the shortest such chain. Results are sorted by chain
+    /// length and then by package name.
+    pub fn search_transitive(
+        &self,
+        pkg_name: &str,
+    ) -> Result<Vec<TransitiveDependency>, Box<dyn Error>> {
+        debug!("Searching for packages transitively depending on: {}", pkg_name);
+        let strip_quotes = |s: &str| s.replace("\"", "");
+
+        // Reverse dependency graph: dependency name -> packages depending on it.
+        let mut dependents: HashMap<String, Vec<&PkgInfo>> = HashMap::new();
+        for (_, pkg_and_deps) in &self.pkg_deps_dirs {
+            let Some(pkg) = &pkg_and_deps.package else {
+                continue;
+            };
+            for dep in &pkg_and_deps.dependencies {
+                dependents.entry(strip_quotes(&dep.name)).or_default().push(pkg);
+            }
+        }
+
+        // Breadth-first from the searched package, so every package is reached first
+        // through its shortest chain.
+        let mut results = Vec::new();
+        let mut visited: HashSet<String> = HashSet::from([pkg_name.to_string()]);
+        let mut queue = VecDeque::from([vec![pkg_name.to_string()]]);
+        while let Some(chain) = queue.pop_front() {
+            for pkg in dependents.get(&chain[0]).into_iter().flatten() {
+                let name = strip_quotes(&pkg.name);
+                if !visited.insert(name.clone()) {
+                    continue;
+                }
+                let mut dependee_chain = vec![name];
+                dependee_chain.extend(chain.iter().cloned());
+                debug!("Found transitive dependent: {}", dependee_chain.join(" -> "));
+
+                results.push(TransitiveDependency {
+                    dependee: (*pkg).clone(),
+                    chain: dependee_chain.clone(),
+                });
+                queue.push_back(dependee_chain);
+            }
+        }
+
+        results.sort_by(|a, b| {
+            a.chain
+                .len()
+                .cmp(&b.chain.len())
+                .then_with(|| a.dependee.name.cmp(&b.dependee.name))
+        });
+        debug!("Found {} transitive dependent(s) of '{}'", results.len(), pkg_name);
+        Ok(results)
+    }
+
     /// The `display` method iterates through the package/dependency directories,
     /// formats the package and dependency information using `get_colored_pkg_deps`,
     /// and prints the results.
-    pub fn display<F>(&self, color_version: F)
+    pub fn display<F>(&self, color_version: F) -> Result<(), std::io::Error>
+    where
+        F: Fn(&str) -> ColoredString,
+    {
+        self.display_with_template(DEFAULT_DISPLAY_TEMPLATE, color_version)
+    }
+
+    /// Same as `display`, writing to `writer` instead of stdout.
+    pub fn display_with_writer<F>(
+        &self,
+        writer: &mut dyn Write,
+        color_version: F,
+    ) -> Result<(), std::io::Error>
+    where
+        F: Fn(&str) -> ColoredString,
+    {
+        self.write_with_template(writer, DEFAULT_DISPLAY_TEMPLATE, color_version)
+    }
+
+    /// Prints one line per result following `template`, in which `{name}` and
+    /// `{version}` are replaced by the package name and version (empty when the
+    /// Cargo.toml has no package), `{path}` by the path of the Cargo.toml and `{deps}`
+    /// by the package and dependencies as `display` shows them.
+    pub fn display_with_template<F>(
+        &self,
+        template: &str,
+        color_version: F,
+    ) -> Result<(), std::io::Error>
+    where
+        F: Fn(&str) -> ColoredString,
+    {
+        self.write_with_template(&mut std::io::stdout(), template, color_version)
+    }
+
+    /// Same as `display_with_template`, writing to `writer` instead of stdout.
+    pub fn write_with_template<F>(
+        &self,
+        writer: &mut dyn Write,
+        template: &str,
+        color_version: F,
+    ) -> Result<(), std::io::Error>
     where
         F: Fn(&str) -> ColoredString,
     {
+        debug!("Displaying {} result(s) with template {:?}", self.pkg_deps_dirs.len(), template);
         for (pkg_dir, pkg_deps) in &self.pkg_deps_dirs {
-            // Call the new function with the search directory and the current package directory.
-            let colored_path = get_colored_dir_path(pkg_dir, &self.dir_path);
-            let formatted = get_colored_pkg_deps(pkg_deps, &color_version);
-            println!("{}\n{}", colored_path, formatted);
+            let (name, version) = match &pkg_deps.package {
+                Some(pkg) => (pkg.name.purple().to_string(), color_version(&pkg.version).to_string()),
+                None => (String::new(), String::new()),
+            };
+            let line = substitute_tokens(
+                template,
+                &[
+                    ("{name}", name),
+                    ("{version}", version),
+                    ("{path}", get_colored_dir_path_str(pkg_dir, &self.dir_path_str)),
+                    ("{deps}", get_colored_pkg_deps(pkg_deps, &color_version)),
+                ],
+            );
+            writeln!(writer, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Template of `display`: the path of each Cargo.toml, followed by its package and
+/// dependencies on the next lines.
+const DEFAULT_DISPLAY_TEMPLATE: &str = "{path}\n{deps}";
+
+/// Replaces every token of `values` found in `template` by its value in a single pass,
+/// so a value containing a token is left as it is.
+fn substitute_tokens(template: &str, values: &[(&str, String)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match values.iter().find(|(token, _)| rest.starts_with(token)) {
+            Some((token, value)) => {
+                result.push_str(value);
+                rest = &rest[token.len()..];
+            }
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            }
         }
     }
+    result.push_str(rest);
+    result
 }
 
-/// Searches for the first package with a name similar to `package_name` based on
-/// the Levenshtein distance. Returns the package directory and its package/dependency
-/// info if the distance is within the given threshold.
-/// Finds the package with the smallest Levenshtein distance from the provided package name.
-/// Returns the closest package (if any) regardless of the distance.
+/// Packages within this Levenshtein distance of the searched name are reported by
+/// `find_closest_package`, in addition to the closest ones.
+const FUZZY_DISTANCE_THRESHOLD: usize = 3;
+
+/// Finds the packages with a name similar to `package_name` based on the Levenshtein distance.
+/// The closest packages are always returned (ties included), along with any other package
+/// within `FUZZY_DISTANCE_THRESHOLD`, sorted by distance and then alphabetically by name.
 fn find_closest_package(
     dir_path: &PathBuf,
     package_name: &str,
-) -> Result<Option<(PathBuf, PackageAndDeps)>, Box<dyn std::error::Error>> {
+) -> Result<Vec<(PathBuf, PackageAndDeps)>, Box<dyn std::error::Error>> {
     debug!(
         "Searching for the closest match to package '{}' in directory {:?}",
         package_name, dir_path
@@ -119,12 +725,36 @@ fn find_closest_package(
 
     // Load directories containing only package information.
     debug!("Loading package directories from {:?}", dir_path);
-    let pkg_dirs = load_dirs_pkgs(&dir_path)?;
+    let pkg_dirs = load_dirs_pkgs(dir_path)?;
     debug!("Found {} potential package directories", pkg_dirs.len());
 
-    // Find the package with the minimum Levenshtein distance.
+    let candidates = rank_fuzzy_candidates(pkg_dirs, package_name);
+    debug!("Found {} similar package(s)", candidates.len());
+
+    Ok(candidates
+        .into_iter()
+        .map(|(path, pkg_info)| {
+            let pkg_and_deps = PackageAndDeps {
+                package: Some(pkg_info),
+                dependencies: Vec::new(),
+                dev_dependencies: Vec::new(),
+                build_dependencies: Vec::new(),
+                target_dependencies: Vec::new(),
+                unversioned_dependencies: Vec::new(),
+            };
+            (path, pkg_and_deps)
+        })
+        .collect())
+}
+
+/// Ranks packages by the Levenshtein distance of their name to `package_name`,
+/// keeping those accepted by `find_closest_package`.
+fn rank_fuzzy_candidates(
+    pkg_dirs: Vec<(PathBuf, PkgInfo)>,
+    package_name: &str,
+) -> Vec<(PathBuf, PkgInfo)> {
     debug!("Calculating Levenshtein distances for all potential matches");
-    let candidate = pkg_dirs
+    let mut candidates: Vec<(usize, PathBuf, PkgInfo)> = pkg_dirs
         .into_iter()
         .map(|(path, pkg_info)| {
             let distance = levenshtein(&pkg_info.name, package_name);
@@ -134,66 +764,67 @@ fn find_closest_package(
             );
             (distance, path, pkg_info)
         })
-        .min_by_key(|(distance, _, _)| *distance);
+        .collect();
 
-    if let Some((distance, path, pkg_info)) = &candidate {
-        debug!(
-            "Found best match: '{}' at path {:?} with distance {}",
-            pkg_info.name, path, distance
-        );
-        let pkg_and_deps = PackageAndDeps {
-            package: Some(pkg_info.clone()),
-            dependencies: Vec::new(),
-        };
-        Ok(Some((path.clone(), pkg_and_deps)))
-    } else {
+    let Some(min_distance) = candidates.iter().map(|(distance, _, _)| *distance).min() else {
         debug!("No matching package found");
-        Ok(None)
-    }
+        return Vec::new();
+    };
+    let threshold = min_distance.max(FUZZY_DISTANCE_THRESHOLD);
+
+    candidates.retain(|(distance, _, _)| *distance <= threshold);
+    candidates.sort_by(|(dist_a, _, pkg_a), (dist_b, _, pkg_b)| {
+        dist_a.cmp(dist_b).then_with(|| pkg_a.name.cmp(&pkg_b.name))
+    });
+
+    candidates
+        .into_iter()
+        .map(|(_, path, pkg_info)| (path, pkg_info))
+        .collect()
 }
 
-// Filters package directories by exact package name (or dependency name) match.
-// If no exact matches exist, the caller (in `new`) will perform the fallback similarity search.
-impl Search {
-    /// Filters the internal package directories by exact package or dependency name match.
-    /// The method updates the `pkg_deps_dirs` field in place.
-    fn filter_by_package_name(&mut self, pkg_name: &str) -> Result<(), Box<dyn Error>> {
-        debug!("Filtering packages by name: {}", pkg_name);
-        debug!("Total packages to check: {}", self.pkg_deps_dirs.len());
+/// Keeps the entries whose package or dependencies of any kind, unversioned ones
+/// included, are named exactly `pkg_name`, dropping the other ones, and the entries where
+/// nothing is left.
+/// Quotes around the names are ignored.
+pub fn filter_by_package_name(
+    package_dirs: Vec<(PathBuf, PackageAndDeps)>,
+    pkg_name: &str,
+) -> Vec<(PathBuf, PackageAndDeps)> {
+    debug!("Filtering packages by name: {}", pkg_name);
+    debug!("Total packages to check: {}", package_dirs.len());
 
-        self.pkg_deps_dirs = self
-            .pkg_deps_dirs
-            .clone()
-            .into_iter()
-            .map(|(path, pkg_and_deps)| {
-                let filtered_pkg_and_deps = filter_package_and_deps(pkg_and_deps, pkg_name);
-                (path, filtered_pkg_and_deps)
-            })
-            .filter(|(_, pkg_and_deps)| {
-                pkg_and_deps.package.is_some() || !pkg_and_deps.dependencies.is_empty()
-            })
-            .collect();
+    let filtered: Vec<_> = package_dirs
+        .into_iter()
+        .map(|(path, pkg_and_deps)| {
+            let filtered_pkg_and_deps = filter_package_and_deps(pkg_and_deps, pkg_name);
+            (path, filtered_pkg_and_deps)
+        })
+        .filter(|(_, pkg_and_deps)| {
+            pkg_and_deps.count() > 0 || !pkg_and_deps.unversioned_dependencies.is_empty()
+        })
+        .collect();
 
-        debug!("Found {} matching packages", self.pkg_deps_dirs.len());
-        Ok(())
-    }
+    debug!("Found {} matching packages", filtered.len());
+    filtered
 }
 
 fn filter_package_and_deps(mut pkg_and_deps: PackageAndDeps, pkg_name: &str) -> PackageAndDeps {
     let strip_quotes = |s: &str| s.replace("\"", "");
 
-    if let Some(pkg) = &mut pkg_and_deps.package {
-        if strip_quotes(&pkg.name) != pkg_name {
-            debug!(
-                "Package '{}' does not match '{}', setting package to None",
-                pkg.name, pkg_name
-            );
-            pkg_and_deps.package = None;
-        }
+    if let Some(pkg) = &mut pkg_and_deps.package
+        && strip_quotes(&pkg.name) != pkg_name
+    {
+        debug!(
+            "Package '{}' does not match '{}', setting package to None",
+            pkg.name, pkg_name
+        );
+        pkg_and_deps.package = None;
     }
 
+    pkg_and_deps.retain_dependencies(|dep| strip_quotes(&dep.name) == pkg_name);
     pkg_and_deps
-        .dependencies
+        .unversioned_dependencies
         .retain(|dep| strip_quotes(&dep.name) == pkg_name);
     debug!(
         "Filtered dependencies for package: {:?}",
@@ -203,7 +834,9 @@ fn filter_package_and_deps(mut pkg_and_deps: PackageAndDeps, pkg_name: &str) ->
     pkg_and_deps
 }
 
- fn filter_by_version(
+/// Keeps the package and the dependencies of every kind at exactly `version`, dropping
+/// the entries where nothing matches. Dependencies without a version are dropped.
+pub fn filter_by_version(
      package_dirs: Vec<(PathBuf, PackageAndDeps)>,
      version: &str,
  ) -> Vec<(PathBuf, PackageAndDeps)> {
@@ -213,11 +846,22 @@ fn filter_package_and_deps(mut pkg_and_deps: PackageAndDeps, pkg_name: &str) ->
      package_dirs
          .into_iter()
          .filter_map(|(path, mut pkg_and_deps)| {
+             let overall_match = pkg_and_deps.has_version(version);
+             debug!(
+                 "Package at {:?} {} match version criteria",
+                 path,
+                 if overall_match { "does" } else { "does not" }
+             );
+
+             if !overall_match {
+                return None;
+             }
+
              // check if the package itself matches
              let pkg_matches = pkg_and_deps
                  .package
                  .as_ref()
-                 .map_or(false, |pkg| {
+                 .is_some_and(|pkg| {
                      let m = pkg.version == version;
                      debug!(
                          "Package '{}' version '{}' {} match target '{}'",
@@ -229,29 +873,11 @@ fn filter_package_and_deps(mut pkg_and_deps: PackageAndDeps, pkg_name: &str) ->
                      m
                  });
 
-             // check if any dependency matches
-             let deps_matches = pkg_and_deps
-                 .dependencies
-                 .iter()
-                 .any(|dep| dep.version == version);
-
-             let overall_match = pkg_matches || deps_matches;
-             debug!(
-                 "Package at {:?} {} match version criteria",
-                 path,
-                 if overall_match { "does" } else { "does not" }
-             );
-
-             if !overall_match {
-                return None;
-             }
-
              // prune out any deps that aren’t exactly this version
-             let before = pkg_and_deps.dependencies.len();
-             pkg_and_deps
-                 .dependencies
-                 .retain(|dep| dep.version == version);
-             let after = pkg_and_deps.dependencies.len();
+             let before = pkg_and_deps.all_dependencies().count();
+             pkg_and_deps.retain_dependencies(|dep| dep.version == version);
+             pkg_and_deps.unversioned_dependencies.clear();
+             let after = pkg_and_deps.all_dependencies().count();
              debug!(
                  "Pruned dependencies: {} → {} entries (version '{}')",
                  before, after, version
@@ -268,83 +894,134 @@ fn filter_package_and_deps(mut pkg_and_deps: PackageAndDeps, pkg_name: &str) ->
          .collect()
  }
 
-/// Loads directories and their package/dependency information.
-/// This method walks the directory recursively and collects package information from Cargo.toml files.
-fn load_dirs_pkgs_deps(dir_path: &Path) -> Result<Vec<(PathBuf, PackageAndDeps)>> {
-    debug!(
-        "Starting directory scan for Cargo.toml files in: {:?}",
-        dir_path
-    );
+/// Returns true if `version` parses as semver and satisfies `version_req`. A version with
+/// fewer than three components, as dependencies are often written (e.g. `"1.0"`), is read
+/// with the missing components set to zero, so `"1.0"` matches as `1.0.0`.
+fn version_matches_req(version: &str, version_req: &VersionReq) -> bool {
+    let components = version.split('.').count();
+    let padded;
+    let version = if components < 3 && !version.contains(['-', '+']) {
+        padded = format!("{}{}", version, ".0".repeat(3 - components));
+        &padded
+    } else {
+        version
+    };
+    Version::parse(version).is_ok_and(|v| version_req.matches(&v))
+}
 
-    // Create an iterator over all Cargo.toml files in the directory.
-    let cargo_toml_entries = WalkDir::new(dir_path)
+/// Same as `filter_by_version`, but keeps entries whose version satisfies a semver requirement.
+fn filter_by_version_req(
+    package_dirs: Vec<(PathBuf, PackageAndDeps)>,
+    version_req: &VersionReq,
+) -> Vec<(PathBuf, PackageAndDeps)> {
+    debug!("Filtering packages by version requirement: {}", version_req);
+    filter_by_version_with(package_dirs, |version| version_matches_req(version, version_req))
+}
+
+/// Same as `filter_by_version`, but keeps entries whose version, without quotes,
+/// starts with `prefix`.
+fn filter_by_version_prefix(
+    package_dirs: Vec<(PathBuf, PackageAndDeps)>,
+    prefix: &str,
+) -> Vec<(PathBuf, PackageAndDeps)> {
+    debug!("Filtering packages by version prefix: {}", prefix);
+    filter_by_version_with(package_dirs, |version| {
+        version.replace("\"", "").starts_with(prefix)
+    })
+}
+
+/// Keeps the package and the dependencies of every kind whose version is accepted by
+/// `matches`, dropping entries where nothing is left. Dependencies without a version are
+/// dropped.
+fn filter_by_version_with<F>(
+    package_dirs: Vec<(PathBuf, PackageAndDeps)>,
+    matches: F,
+) -> Vec<(PathBuf, PackageAndDeps)>
+where
+    F: Fn(&str) -> bool,
+{
+    package_dirs
         .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|entry| entry.file_type().is_file() && entry.file_name() == "Cargo.toml");
+        .filter_map(|(path, mut pkg_and_deps)| {
+            let pkg_matches = pkg_and_deps
+                .package
+                .as_ref()
+                .is_some_and(|pkg| matches(&pkg.version));
 
-    // Process each file using iterator combinators.
-    let results: Vec<(PathBuf, PackageAndDeps)> = cargo_toml_entries
-        .map(|entry| -> Result<Option<(PathBuf, PackageAndDeps)>> {
-            let file_path = entry.path().to_path_buf();
-            debug!("Found Cargo.toml at: {:?}", file_path);
-
-            // Read the file content.
-            let content = fs::read_to_string(&file_path)
-                .with_context(|| format!("Failed to read file {:?}", file_path))?;
-            debug!("Successfully read file ({} bytes)", content.len());
-
-            debug!("Parsing TOML content...");
-            let toml_parser = TomlParser::new(&content)
-                .with_context(|| format!("Failed to parse TOML in {:?}", file_path))?;
-            debug!("TOML parsed successfully");
-
-            // Look for package and dependencies in the TOML.
-            debug!("Looking for package and dependencies in TOML...");
-            if let Some(pkg_deps_nodes) = toml_parser.find_package_and_deps() {
-                debug!("Found package/dependencies section in TOML");
-
-                let package = pkg_deps_nodes.package.map(|(_node, pkg_info)| {
-                    debug!("Found package: {}", pkg_info.name);
-                    pkg_info
-                });
+            pkg_and_deps.retain_dependencies(|dep| matches(&dep.version));
+            pkg_and_deps.unversioned_dependencies.clear();
 
-                let dependencies: Vec<_> = pkg_deps_nodes
-                    .dependencies
-                    .into_iter()
-                    .map(|(_node, deps_info)| {
-                        debug!(
-                            "Found dependency: {} = {}",
-                            deps_info.name, deps_info.version
-                        );
-                        deps_info
-                    })
-                    .collect();
-
-                let pkg_and_deps = PackageAndDeps {
-                    package,
-                    dependencies,
-                };
-                debug!(
-                    "Processed package with {} dependencies",
-                    pkg_and_deps.dependencies.len()
-                );
-
-                Ok(Some((file_path, pkg_and_deps)))
-            } else {
-                debug!("No package/dependencies section found in this TOML file");
-                Ok(None)
+            if !pkg_matches {
+                pkg_and_deps.package = None;
+            }
+
+            if pkg_and_deps.count() == 0 {
+                debug!("Package at {:?} does not match version criteria", path);
+                return None;
             }
+
+            Some((path, pkg_and_deps))
         })
+        .collect()
+}
+
+/// Directories that never contain crates of the workspace, skipped in addition to
+/// hidden directories and the user's excluded directories.
+const SKIPPED_DIRS: [&str; 2] = ["node_modules", "vendor"];
+
+/// Returns true for a directory below the search root that should not be walked into:
+/// hidden directories such as `.git`, `SKIPPED_DIRS` and `excluded_dirs`.
+fn is_skipped_dir(entry: &walkdir::DirEntry, excluded_dirs: &[String]) -> bool {
+    if entry.depth() == 0 || !entry.file_type().is_dir() {
+        return false;
+    }
+
+    let name = entry.file_name().to_string_lossy();
+    name.starts_with('.')
+        || SKIPPED_DIRS.contains(&name.as_ref())
+        || excluded_dirs.iter().any(|dir| *dir == name)
+}
+
+/// Loads directories and their package/dependency information.
+/// This method walks the directory recursively and collects package information from the
+/// files whose name matches `file_pattern`, `Cargo.toml` by default.
+fn load_dirs_pkgs_deps(
+    dir_path: &Path,
+    excluded_dirs: &[String],
+    file_pattern: &Pattern,
+    grammar: TomlGrammar,
+) -> Result<Vec<(PathBuf, PackageAndDeps)>> {
+    debug!(
+        "Starting directory scan for {} files in: {:?}",
+        file_pattern, dir_path
+    );
+
+    // Canonical paths of the Cargo.toml files seen so far, so that a file reached
+    // through a symlinked directory is only processed once.
+    let mut seen_paths: HashSet<PathBuf> = HashSet::new();
+
+    // Create an iterator over all Cargo.toml files in the directory.
+    let cargo_toml_entries = walk_cargo_tomls(dir_path, excluded_dirs, file_pattern)
+        .filter(|entry| {
+            let canonical_path =
+                fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf());
+            let first_seen = seen_paths.insert(canonical_path);
+            if !first_seen {
+                debug!("Skipping already processed Cargo.toml at: {:?}", entry.path());
+            }
+            first_seen
+        });
+
+    // Process each file using iterator combinators.
+    let results: Vec<(PathBuf, PackageAndDeps)> = cargo_toml_entries
+        .map(|entry| load_pkg_deps_from_file(entry.path(), grammar))
         // Collect all the results, propagating any errors.
         .collect::<Result<Vec<_>, _>>()?
         // Flatten out files where no package/dependencies section was found.
         .into_iter()
         .flatten()
         // Now filter out any paths that contain "target/release" or "target/debug".
-        .filter(|(file_path, _)| {
-            let path_str = file_path.to_string_lossy();
-            !(path_str.contains("/target/") || path_str.contains("/target/"))
-        })
+        .filter(|(file_path, _)| !is_in_target_dir(file_path))
         .collect();
 
     debug!(
@@ -357,6 +1034,156 @@ fn load_dirs_pkgs_deps(dir_path: &Path) -> Result<Vec<(PathBuf, PackageAndDeps)>
     Ok(results)
 }
 
+/// Same as `load_dirs_pkgs_deps`, but the files are read and parsed on a
+/// thread pool of `jobs` threads, or rayon's global pool when `jobs` is `None`, while the
+/// directory is still being walked. The results are sorted by path, and of the files
+/// reached through symlinks only the first path of each canonical file is kept.
+fn load_dirs_pkgs_deps_parallel(
+    dir_path: &Path,
+    excluded_dirs: &[String],
+    file_pattern: &Pattern,
+    jobs: Option<usize>,
+    grammar: TomlGrammar,
+) -> Result<Vec<(PathBuf, PackageAndDeps)>> {
+    debug!(
+        "Starting parallel directory scan for {} files in: {:?} with {:?} jobs",
+        file_pattern, dir_path, jobs
+    );
+
+    let load = || {
+        walk_cargo_tomls(dir_path, excluded_dirs, file_pattern)
+            .par_bridge()
+            .map(|entry| {
+                let canonical_path = fs::canonicalize(entry.path())
+                    .unwrap_or_else(|_| entry.path().to_path_buf());
+                load_pkg_deps_from_file(entry.path(), grammar)
+                    .map(|loaded| (canonical_path, loaded))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let mut loaded = match jobs {
+        Some(jobs) => rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build()?
+            .install(load)?,
+        None => load()?,
+    };
+
+    // The threads finish in any order, sort to get the same order on every run.
+    loaded.sort_by(|(_, a), (_, b)| {
+        let path = |loaded: &Option<(PathBuf, PackageAndDeps)>| {
+            loaded.as_ref().map(|(path, _)| path.clone())
+        };
+        path(a).cmp(&path(b))
+    });
+
+    let mut seen_paths: HashSet<PathBuf> = HashSet::new();
+    let results: Vec<(PathBuf, PackageAndDeps)> = loaded
+        .into_iter()
+        .filter(|(canonical_path, _)| {
+            let first_seen = seen_paths.insert(canonical_path.clone());
+            if !first_seen {
+                debug!("Skipping already processed Cargo.toml at: {:?}", canonical_path);
+            }
+            first_seen
+        })
+        .filter_map(|(_, loaded)| loaded)
+        .filter(|(file_path, _)| !is_in_target_dir(file_path))
+        .collect();
+
+    debug!("Total packages found: {}", results.len());
+    Ok(results)
+}
+
+/// Walks `dir_path`, without following symlinks, and yields every file whose name
+/// matches `file_pattern` outside of the directories skipped by `is_skipped_dir`.
+fn walk_cargo_tomls<'a>(
+    dir_path: &Path,
+    excluded_dirs: &'a [String],
+    file_pattern: &'a Pattern,
+) -> impl Iterator<Item = walkdir::DirEntry> + Send + 'a {
+    WalkDir::new(dir_path)
+        .into_iter()
+        .filter_entry(move |entry| {
+            let skipped = is_skipped_dir(entry, excluded_dirs);
+            if skipped {
+                debug!("Skipping directory: {:?}", entry.path());
+            }
+            !skipped
+        })
+        .filter_map(|e| e.ok())
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && file_pattern.matches(&entry.file_name().to_string_lossy())
+        })
+}
+
+/// Returns the path of every Cargo.toml under `dir_path` that `Search` would load: outside
+/// of hidden directories, `node_modules`, `vendor`, `excluded_dirs` and `target`
+/// directories. Sorted by path.
+pub fn find_manifests(dir_path: &Path, excluded_dirs: &[String]) -> Vec<PathBuf> {
+    let file_pattern = Pattern::new("Cargo.toml").expect("Cargo.toml is a valid pattern");
+    let mut manifests: Vec<PathBuf> = walk_cargo_tomls(dir_path, excluded_dirs, &file_pattern)
+        .map(|entry| entry.into_path())
+        .filter(|file_path| !is_in_target_dir(file_path))
+        .collect();
+    manifests.sort();
+
+    debug!("Found {} Cargo.toml file(s) in {:?}", manifests.len(), dir_path);
+    manifests
+}
+
+/// Returns true for a Cargo.toml below a `target` directory, e.g. a packaged crate.
+fn is_in_target_dir(file_path: &Path) -> bool {
+    file_path.to_string_lossy().contains("/target/")
+}
+
+/// Reads and parses a single Cargo.toml, reading its package with `grammar`. Returns
+/// `None` when the file has neither a package nor dependencies.
+fn load_pkg_deps_from_file(
+    file_path: &Path,
+    grammar: TomlGrammar,
+) -> Result<Option<(PathBuf, PackageAndDeps)>> {
+    let file_path = file_path.to_path_buf();
+    debug!("Loading Cargo.toml at: {:?}", file_path);
+
+    let content = fs::read_to_string(&file_path)
+        .with_context(|| format!("Failed to load TOML from {:?}", file_path))?;
+    pkg_deps_from_source(file_path, &content, grammar)
+}
+
+/// Parses the content of the Cargo.toml at `file_path`. Returns `None` when it has
+/// neither a package nor dependencies, or is empty.
+fn pkg_deps_from_source(
+    file_path: PathBuf,
+    content: &str,
+    grammar: TomlGrammar,
+) -> Result<Option<(PathBuf, PackageAndDeps)>> {
+    let pkg_deps = match PackageAndDeps::from_toml_str_with_grammar(content, grammar) {
+        Err(TomlParserError::EmptySource) => {
+            warn!("Skipping empty {:?}", file_path);
+            return Ok(None);
+        }
+        pkg_deps => pkg_deps.with_context(|| format!("Failed to load TOML from {:?}", file_path))?,
+    };
+    debug!("TOML parsed successfully ({} bytes)", content.len());
+
+    match pkg_deps {
+        Some(pkg_and_deps) => {
+            debug!(
+                "Processed package {:?} with {} dependencies",
+                pkg_and_deps.package.as_ref().map(|pkg| &pkg.name),
+                pkg_and_deps.dependencies.len()
+            );
+            Ok(Some((file_path, pkg_and_deps)))
+        }
+        None => {
+            debug!("No package/dependencies section found in this TOML file");
+            Ok(None)
+        }
+    }
+}
+
 fn load_dirs_pkgs(dir_path: &Path) -> Result<Vec<(PathBuf, PkgInfo)>> {
     debug!("Starting package discovery in directory: {:?}", dir_path);
 
@@ -375,16 +1202,15 @@ fn load_dirs_pkgs(dir_path: &Path) -> Result<Vec<(PathBuf, PkgInfo)>> {
             let file_path = entry.path().to_path_buf();
             debug!("[{}] Processing Cargo.toml at: {:?}", i + 1, file_path);
 
-            // Read file content.
-            let content = fs::read_to_string(&file_path)
-                .with_context(|| format!("Failed to read file {:?}", file_path))?;
-            debug!("  Read {} bytes from file", content.len());
-
-            // Parse the content using the TomlParser.
-            debug!("  Parsing TOML content...");
-            let toml_parser = TomlParser::new(&content)
-                .with_context(|| format!("Failed to parse TOML in {:?}", file_path))?;
-            debug!("  TOML parsed successfully");
+            // Read and parse the file content using the TomlParser.
+            let (toml_parser, content) = match TomlParser::new_from_path(&file_path) {
+                Err(TomlParserError::EmptySource) => {
+                    warn!("Skipping empty {:?}", file_path);
+                    return Ok(None);
+                }
+                parsed => parsed.with_context(|| format!("Failed to load TOML from {:?}", file_path))?,
+            };
+            debug!("  TOML parsed successfully ({} bytes)", content.len());
 
             // Extract the package info.
             debug!("  Searching for package information...");
@@ -410,8 +1236,14 @@ fn load_dirs_pkgs(dir_path: &Path) -> Result<Vec<(PathBuf, PkgInfo)>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use colored::Colorize;
+    use crate::test_support::strip_ansi;
+    use cratup_tree_sitter::{DepKind, DepsInfo, PackageAndDepBuilder};
+
+    fn cargo_toml_pattern() -> Pattern {
+        Pattern::new("Cargo.toml").unwrap()
+    }
 
-    use cratup_tree_sitter::DepsInfo;
     #[test]
     fn test_filter_package_and_deps_match() {
         let pkg_and_deps = PackageAndDeps {
@@ -427,14 +1259,17 @@ mod tests {
                     version: "1.0.0".to_string(),
                     name_pair: "test-package".to_string(),
                     version_pair: "1.0.0".to_string(),
+                    kind: DepKind::Normal,
                 },
                 DepsInfo {
                     name: "\"other-package\"".to_string(),
                     version: "2.0.0".to_string(),
                     name_pair: "other-package".to_string(),
                     version_pair: "2.0.0".to_string(),
+                    kind: DepKind::Normal,
                 },
             ],
+            ..PackageAndDeps::default()
         };
 
         let filtered = filter_package_and_deps(pkg_and_deps.clone(), "test-package");
@@ -460,14 +1295,17 @@ mod tests {
                     version: "1.0.0".to_string(),
                     name_pair: "test-package".to_string(),
                     version_pair: "1.0.0".to_string(),
+                    kind: DepKind::Normal,
                 },
                 DepsInfo {
                     name: "\"other-package\"".to_string(),
                     version: "2.0.0".to_string(),
                     name_pair: "other-package".to_string(),
                     version_pair: "2.0.0".to_string(),
+                    kind: DepKind::Normal,
                 },
             ],
+            ..PackageAndDeps::default()
         };
 
         let filtered = filter_package_and_deps(pkg_and_deps.clone(), "non-existent-package");
@@ -485,6 +1323,7 @@ mod tests {
                 version_pair: "1.0.0".to_string(),
             }),
             dependencies: vec![],
+            ..PackageAndDeps::default()
         };
 
         let filtered = filter_package_and_deps(pkg_and_deps.clone(), "test-package");
@@ -504,11 +1343,982 @@ mod tests {
                 version: "1.0.0".to_string(),
                 name_pair: "test-package".to_string(),
                 version_pair: "1.0.0".to_string(),
+                kind: DepKind::Normal,
             }],
+            ..PackageAndDeps::default()
         };
 
         let filtered = filter_package_and_deps(pkg_and_deps.clone(), "test-package");
         assert!(filtered.package.is_none());
         assert_eq!(filtered.dependencies.len(), 1);
     }
+
+    #[test]
+    fn test_filter_package_and_deps_every_kind() {
+        let pkg_and_deps = PackageAndDepBuilder::new()
+            .package("\"app\"", "1.0.0")
+            .dep("\"test-package\"", "1.0.0")
+            .dev_dep("\"test-package\"", "1.0.0")
+            .build_dep("\"test-package\"", "1.0.0")
+            .dev_dep("\"other-package\"", "2.0.0")
+            .build();
+
+        let filtered = filter_package_and_deps(pkg_and_deps, "test-package");
+        assert!(filtered.package.is_none());
+        assert_eq!(filtered.dependencies.len(), 1);
+        assert_eq!(filtered.dev_dependencies.len(), 1);
+        assert_eq!(filtered.build_dependencies.len(), 1);
+    }
+
+    #[test]
+    fn test_filter_by_package_name_drops_unmatched_entries() {
+        let package_dirs = vec![
+            (
+                PathBuf::from("app/Cargo.toml"),
+                PackageAndDepBuilder::new()
+                    .package("\"app\"", "1.0.0")
+                    .dep("\"test-package\"", "1.0.0")
+                    .dep("\"other-package\"", "2.0.0")
+                    .build(),
+            ),
+            (
+                PathBuf::from("other/Cargo.toml"),
+                PackageAndDepBuilder::new().package("\"other-package\"", "2.0.0").build(),
+            ),
+        ];
+
+        let filtered = crate::filter_by_package_name(package_dirs, "test-package");
+
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].0, PathBuf::from("app/Cargo.toml"));
+        assert!(filtered[0].1.package.is_none());
+        assert_eq!(filtered[0].1.dependencies.len(), 1);
+        assert_eq!(filtered[0].1.dependencies[0].name, "\"test-package\"");
+    }
+
+    #[test]
+    fn test_filter_by_version_keeps_matching_entries() {
+        let package_dirs = vec![
+            (
+                PathBuf::from("app/Cargo.toml"),
+                PackageAndDepBuilder::new()
+                    .package("app", "0.4.1")
+                    .dep("core", "0.4.1")
+                    .dep("serde", "1.0")
+                    .build(),
+            ),
+            (
+                PathBuf::from("tool/Cargo.toml"),
+                PackageAndDepBuilder::new().package("tool", "0.2.0").build(),
+            ),
+        ];
+
+        let filtered = crate::filter_by_version(package_dirs, "0.4.1");
+
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].1, PackageAndDepBuilder::new().package("app", "0.4.1").dep("core", "0.4.1").build());
+    }
+
+    fn pkg_with_deps(name: &str, version: &str, deps: &[(&str, &str)]) -> PackageAndDeps {
+        deps.iter()
+            .fold(
+                PackageAndDepBuilder::new().package(name, version),
+                |builder, (dep_name, dep_version)| builder.dep(dep_name, dep_version),
+            )
+            .build()
+    }
+
+    fn search_fixture(package_name: Option<&str>) -> Search {
+        Search {
+            dir_path: PathBuf::from("/ws"),
+            dir_path_str: "/ws".to_string(),
+            version: None,
+            package_name: package_name.map(str::to_string),
+            show_unversioned: false,
+            traversal_order: TraversalOrder::default(),
+            grammar: TomlGrammar::Toml05,
+            pkg_deps_dirs: vec![
+                (
+                    PathBuf::from("/ws/a/Cargo.toml"),
+                    pkg_with_deps("a", "0.4.3", &[("b", "1.2.0"), ("c", "0.5.0")]),
+                ),
+                (
+                    PathBuf::from("/ws/b/Cargo.toml"),
+                    pkg_with_deps("b", "1.2.0", &[("c", "0.4.0")]),
+                ),
+                (
+                    PathBuf::from("/ws/c/Cargo.toml"),
+                    pkg_with_deps("c", "2.0.0", &[]),
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_count_matches_counts_entries() {
+        let mut search = search_fixture(Some("c"));
+        assert_eq!(search.count_matches().unwrap(), 3);
+
+        let mut search = search_fixture(None);
+        search.version = Some("1.2.0".to_string());
+        assert_eq!(search.count_matches().unwrap(), 2);
+
+        let mut search = search_fixture(Some("missing"));
+        assert_eq!(search.count_matches().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_search_transitive_reports_full_chain() {
+        let mut search = search_fixture(None);
+        search.pkg_deps_dirs = vec![
+            (
+                PathBuf::from("/ws/app/Cargo.toml"),
+                pkg_with_deps("app", "0.1.0", &[("mid", "0.2.0"), ("serde", "1.0.0")]),
+            ),
+            (
+                PathBuf::from("/ws/mid/Cargo.toml"),
+                pkg_with_deps("mid", "0.2.0", &[("core", "0.3.0")]),
+            ),
+            (
+                PathBuf::from("/ws/core/Cargo.toml"),
+                pkg_with_deps("core", "0.3.0", &[]),
+            ),
+            (
+                PathBuf::from("/ws/tool/Cargo.toml"),
+                pkg_with_deps("tool", "0.1.0", &[("serde", "1.0.0")]),
+            ),
+        ];
+
+        let found = search.search_transitive("core").unwrap();
+        let chains: Vec<Vec<&str>> = found
+            .iter()
+            .map(|dep| dep.chain.iter().map(String::as_str).collect())
+            .collect();
+        assert_eq!(chains, vec![vec!["mid", "core"], vec!["app", "mid", "core"]]);
+        assert_eq!(found[1].dependee.name, "app");
+        assert_eq!(found[1].dependee.version, "0.1.0");
+
+        assert!(search.search_transitive("app").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sort_by_match_count() {
+        let mut search = search_fixture(None);
+        search.pkg_deps_dirs.push((
+            PathBuf::from("/ws/d/Cargo.toml"),
+            pkg_with_deps("d", "0.1.0", &[("a", "0.4.3"), ("b", "1.2.0"), ("c", "2.0.0")]),
+        ));
+
+        search.sort_by_match_count(true);
+        let paths: Vec<&str> = search.pkg_deps_dirs.iter().map(|(path, _)| path.to_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            ["/ws/d/Cargo.toml", "/ws/a/Cargo.toml", "/ws/b/Cargo.toml", "/ws/c/Cargo.toml"]
+        );
+
+        search.sort_by_match_count(false);
+        assert_eq!(search.pkg_deps_dirs[0].0, PathBuf::from("/ws/c/Cargo.toml"));
+        assert_eq!(search.pkg_deps_dirs[3].0, PathBuf::from("/ws/d/Cargo.toml"));
+    }
+
+    #[test]
+    fn test_search_by_range_single_constraint() {
+        let mut search = search_fixture(None);
+        search.search_by_range("^1").unwrap();
+
+        assert_eq!(search.pkg_deps_dirs.len(), 2);
+        let (_, a) = &search.pkg_deps_dirs[0];
+        assert!(a.package.is_none());
+        assert_eq!(a.dependencies.len(), 1);
+        assert_eq!(a.dependencies[0].name, "b");
+        let (_, b) = &search.pkg_deps_dirs[1];
+        assert_eq!(b.package.as_ref().unwrap().name, "b");
+        assert!(b.dependencies.is_empty());
+    }
+
+    #[test]
+    fn test_search_by_range_double_constraint() {
+        let mut search = search_fixture(None);
+        search.search_by_range(">=0.4, <0.5").unwrap();
+
+        assert_eq!(search.pkg_deps_dirs.len(), 2);
+        let (_, a) = &search.pkg_deps_dirs[0];
+        assert_eq!(a.package.as_ref().unwrap().version, "0.4.3");
+        assert!(a.dependencies.is_empty());
+        let (_, b) = &search.pkg_deps_dirs[1];
+        assert!(b.package.is_none());
+        assert_eq!(b.dependencies[0].version, "0.4.0");
+    }
+
+    #[test]
+    fn test_search_by_range_with_package_name() {
+        let mut search = search_fixture(Some("c"));
+        search.search_by_range(">=0.4, <0.5").unwrap();
+
+        assert_eq!(search.pkg_deps_dirs.len(), 1);
+        assert_eq!(search.pkg_deps_dirs[0].0, PathBuf::from("/ws/b/Cargo.toml"));
+    }
+
+    #[test]
+    fn test_search_by_range_two_component_versions() {
+        let mut search = search_fixture(None);
+        search.pkg_deps_dirs = vec![(
+            PathBuf::from("/ws/a/Cargo.toml"),
+            pkg_with_deps("a", "0.1.0", &[("serde", "1.0"), ("log", "0.4"), ("rand", "1")]),
+        )];
+        search.search_by_range("^1").unwrap();
+
+        let names: Vec<&str> = search.pkg_deps_dirs[0]
+            .1
+            .dependencies
+            .iter()
+            .map(|dep| dep.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["serde", "rand"]);
+    }
+
+    #[test]
+    fn test_search_by_range_invalid_requirement() {
+        let mut search = search_fixture(None);
+        assert!(search.search_by_range("not a version").is_err());
+        assert_eq!(search.pkg_deps_dirs.len(), 3);
+    }
+
+    #[test]
+    fn test_find_with_version_prefix_matches_minor_family() {
+        let mut search = search_fixture(None);
+        search.pkg_deps_dirs = vec![(
+            PathBuf::from("/ws/a/Cargo.toml"),
+            pkg_with_deps(
+                "a",
+                "0.4.3",
+                &[("b", "\"0.4.10\""), ("c", "0.40.0"), ("d", "1.4.3")],
+            ),
+        )];
+
+        let found = search.find_with_version_prefix("0.4.").unwrap();
+
+        assert_eq!(found, search.pkg_deps_dirs);
+        assert_eq!(found.len(), 1);
+        let (_, a) = &found[0];
+        assert_eq!(a.package.as_ref().unwrap().version, "0.4.3");
+        let dep_names: Vec<&str> = a.dependencies.iter().map(|dep| dep.name.as_str()).collect();
+        assert_eq!(dep_names, vec!["b"]);
+    }
+
+    #[test]
+    fn test_find_with_version_prefix_drops_unmatched_entries() {
+        let mut search = search_fixture(None);
+        let found = search.find_with_version_prefix("2.").unwrap();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, PathBuf::from("/ws/c/Cargo.toml"));
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_export_csv_rows() {
+        let mut search = search_fixture(None);
+        search.pkg_deps_dirs.truncate(2);
+        search.pkg_deps_dirs.push((
+            PathBuf::from("/ws/odd, name/Cargo.toml"),
+            PackageAndDepBuilder::new().dep("\"quoted\"", "1.0").build(),
+        ));
+
+        assert_eq!(
+            search.export_csv(),
+            "path,kind,name,version
+/ws/a/Cargo.toml,package,a,0.4.3
+/ws/a/Cargo.toml,dependency,b,1.2.0
+/ws/a/Cargo.toml,dependency,c,0.5.0
+/ws/b/Cargo.toml,package,b,1.2.0
+/ws/b/Cargo.toml,dependency,c,0.4.0
+\"/ws/odd, name/Cargo.toml\",dependency,\"\"\"quoted\"\"\",1.0
+"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_export_toml_structure() {
+        let mut search = search_fixture(None);
+        search.pkg_deps_dirs.push((
+            PathBuf::from("/ws/virtual/Cargo.toml"),
+            PackageAndDepBuilder::new().dep("a", "0.4.3").build(),
+        ));
+
+        let exported = search.export_toml().unwrap();
+        let value = toml::from_str::<toml::Value>(&exported).unwrap();
+
+        let packages = value["package"].as_array().unwrap();
+        assert_eq!(packages.len(), 4);
+
+        let a = &packages[0];
+        assert_eq!(a["path"].as_str(), Some("/ws/a/Cargo.toml"));
+        assert_eq!(a["name"].as_str(), Some("a"));
+        assert_eq!(a["version"].as_str(), Some("0.4.3"));
+        let deps = a["dependencies"].as_array().unwrap();
+        let dep_pairs: Vec<(&str, &str)> = deps
+            .iter()
+            .map(|dep| (dep["name"].as_str().unwrap(), dep["version"].as_str().unwrap()))
+            .collect();
+        assert_eq!(dep_pairs, vec![("b", "1.2.0"), ("c", "0.5.0")]);
+
+        let c = &packages[2];
+        assert!(c["dependencies"].as_array().unwrap().is_empty());
+
+        let virtual_manifest = &packages[3];
+        assert!(virtual_manifest.get("name").is_none());
+        assert_eq!(
+            virtual_manifest["dependencies"][0]["name"].as_str(),
+            Some("a")
+        );
+    }
+
+    fn write_manifest(root: &Path, dir: &str, content: &str) {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        fs::write(root.join(dir).join("Cargo.toml"), content).unwrap();
+    }
+
+    #[test]
+    fn test_new_skips_empty_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_manifest(tmp.path(), "empty", "");
+        write_manifest(tmp.path(), "pkg", "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n");
+
+        let search = Search::new(tmp.path().to_path_buf(), None, None).unwrap();
+        assert_eq!(search.pkg_deps_dirs.len(), 1);
+        assert_eq!(search.inverse("pkg").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_is_sorted_and_deterministic() {
+        let tmp = tempfile::tempdir().unwrap();
+        for name in ["zeta", "alpha", "mid/inner", "beta"] {
+            let crate_name = name.replace('/', "_");
+            write_manifest(
+                tmp.path(),
+                name,
+                &format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", crate_name),
+            );
+        }
+
+        let run = || {
+            let mut search =
+                Search::new(tmp.path().to_path_buf(), Some("0.1.0".to_string()), None).unwrap();
+            search.search().unwrap();
+            search
+                .pkg_deps_dirs
+                .iter()
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let first = run();
+        assert_eq!(first, run());
+        assert_eq!(first.len(), 4);
+
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(first, sorted);
+    }
+
+    fn pkg_dir(name: &str) -> (PathBuf, PkgInfo) {
+        let pkg_and_deps = pkg_with_deps(name, "0.1.0", &[]);
+        (
+            PathBuf::from(format!("/ws/{}/Cargo.toml", name)),
+            pkg_and_deps.package.unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_rank_fuzzy_candidates_returns_ties() {
+        let pkg_dirs = vec![pkg_dir("serde_b"), pkg_dir("completely_unrelated"), pkg_dir("serde_a")];
+        let candidates = rank_fuzzy_candidates(pkg_dirs, "serde_x");
+
+        let names: Vec<&str> = candidates.iter().map(|(_, pkg)| pkg.name.as_str()).collect();
+        assert_eq!(names, vec!["serde_a", "serde_b"]);
+    }
+
+    #[test]
+    fn test_rank_fuzzy_candidates_single_closest() {
+        let pkg_dirs = vec![pkg_dir("tokio_util"), pkg_dir("serde_json"), pkg_dir("clap_derive")];
+        let candidates = rank_fuzzy_candidates(pkg_dirs, "serdejson");
+
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].1.name, "serde_json");
+    }
+
+    #[test]
+    fn test_rank_fuzzy_candidates_sorted_by_distance() {
+        let pkg_dirs = vec![pkg_dir("cratup"), pkg_dir("cratup_a"), pkg_dir("cratu")];
+        let candidates = rank_fuzzy_candidates(pkg_dirs, "cratup");
+
+        let names: Vec<&str> = candidates.iter().map(|(_, pkg)| pkg.name.as_str()).collect();
+        assert_eq!(names, vec!["cratup", "cratu", "cratup_a"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_load_dirs_pkgs_deps_skips_symlinked_duplicates() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_manifest(
+            tmp.path(),
+            "real",
+            "[package]\nname = \"real\"\nversion = \"0.1.0\"\n",
+        );
+        write_manifest(
+            tmp.path(),
+            "other",
+            "[package]\nname = \"other\"\nversion = \"0.1.0\"\n",
+        );
+        std::os::unix::fs::symlink(tmp.path().join("real"), tmp.path().join("link")).unwrap();
+
+        let results = load_dirs_pkgs_deps(tmp.path(), &[], &cargo_toml_pattern(), TomlGrammar::Toml05).unwrap();
+
+        let mut names: Vec<String> = results
+            .iter()
+            .map(|(_, pkg_and_deps)| pkg_and_deps.package.as_ref().unwrap().name.clone())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["other", "real"]);
+    }
+
+    #[test]
+    fn test_load_dirs_pkgs_deps_parallel_matches_sequential() {
+        let tmp = tempfile::tempdir().unwrap();
+        for i in 0..12 {
+            write_manifest(
+                tmp.path(),
+                &format!("crates/crate_{}", i),
+                &format!(
+                    "[package]\nname = \"crate_{}\"\nversion = \"0.1.{}\"\n\n[dependencies]\nserde = \"1.0\"\n",
+                    i, i
+                ),
+            );
+        }
+        write_manifest(tmp.path(), ".hidden", "[package]\nname = \"hidden\"\nversion = \"0.1.0\"\n");
+        write_manifest(tmp.path(), "scratch", "[package]\nname = \"scratch\"\nversion = \"0.1.0\"\n");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(tmp.path().join("crates/crate_0"), tmp.path().join("link"))
+            .unwrap();
+
+        let excluded = ["scratch".to_string()];
+        let as_set = |results: Vec<(PathBuf, PackageAndDeps)>| {
+            results
+                .into_iter()
+                .map(|(_, pkg_and_deps)| {
+                    let pkg = pkg_and_deps.package.unwrap();
+                    (pkg.name, pkg.version, pkg_and_deps.dependencies.len())
+                })
+                .collect::<HashSet<_>>()
+        };
+
+        let pattern = cargo_toml_pattern();
+        let sequential = load_dirs_pkgs_deps(tmp.path(), &excluded, &pattern, TomlGrammar::Toml05).unwrap();
+        let parallel =
+            load_dirs_pkgs_deps_parallel(tmp.path(), &excluded, &pattern, Some(4), TomlGrammar::Toml05).unwrap();
+        let global_pool =
+            load_dirs_pkgs_deps_parallel(tmp.path(), &excluded, &pattern, None, TomlGrammar::Toml05).unwrap();
+
+        assert_eq!(sequential.len(), 12);
+        assert_eq!(parallel.len(), 12);
+        assert_eq!(as_set(parallel), as_set(sequential.clone()));
+        assert_eq!(as_set(global_pool), as_set(sequential));
+    }
+
+    #[test]
+    fn test_display_with_writer_output() {
+        let mut search = search_fixture(None);
+        search.pkg_deps_dirs.truncate(2);
+
+        let mut out = Vec::new();
+        search.display_with_writer(&mut out, |s| s.normal()).unwrap();
+
+        assert_eq!(
+            strip_ansi(&String::from_utf8(out).unwrap()),
+            "a/Cargo.toml
+\ta: a v0.4.3
+\tb: b = \"1.2.0\"
+\tc: c = \"0.5.0\"
+b/Cargo.toml
+\tb: b v1.2.0
+\tc: c = \"0.4.0\"
+"
+        );
+    }
+
+    #[test]
+    fn test_write_with_template_replaces_tokens() {
+        let mut search = search_fixture(None);
+        search.pkg_deps_dirs.truncate(1);
+
+        let render = |template: &str| {
+            let mut out = Vec::new();
+            search.write_with_template(&mut out, template, |s| s.normal()).unwrap();
+            strip_ansi(&String::from_utf8(out).unwrap())
+        };
+
+        assert_eq!(render("{name}"), "a\n");
+        assert_eq!(render("{version}"), "0.4.3\n");
+        assert_eq!(render("{path}"), "a/Cargo.toml\n");
+        assert_eq!(
+            render("{deps}"),
+            "\ta: a v0.4.3\n\tb: b = \"1.2.0\"\n\tc: c = \"0.5.0\"\n"
+        );
+        assert_eq!(render("{name} v{version} at {path} {unknown}"), "a v0.4.3 at a/Cargo.toml {unknown}\n");
+    }
+
+    #[test]
+    fn test_write_with_template_leaves_name_empty_without_package() {
+        let mut search = search_fixture(None);
+        search.pkg_deps_dirs = vec![(
+            search.dir_path.join("a").join("Cargo.toml"),
+            PackageAndDepBuilder::new().dep("b", "1.2.0").build(),
+        )];
+
+        let mut out = Vec::new();
+        search.write_with_template(&mut out, "[{name}][{version}]", |s| s.normal()).unwrap();
+
+        assert_eq!(strip_ansi(&String::from_utf8(out).unwrap()), "[][]\n");
+    }
+
+    #[test]
+    fn test_traversal_order_of_nested_crates() {
+        let tmp = tempfile::tempdir().unwrap();
+        for dir in ["a", "a/b", "a/b/c", "z"] {
+            let name = dir.rsplit('/').next().unwrap();
+            write_manifest(
+                tmp.path(),
+                dir,
+                &format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", name),
+            );
+        }
+        let relative_paths = |search: &Search| -> Vec<String> {
+            search
+                .pkg_deps_dirs
+                .iter()
+                .map(|(path, _)| {
+                    path.parent()
+                        .unwrap()
+                        .strip_prefix(tmp.path())
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+
+        let mut depth_first = Search::new(tmp.path().to_path_buf(), None, None).unwrap();
+        depth_first.search().unwrap();
+        assert_eq!(relative_paths(&depth_first), ["a", "a/b", "a/b/c", "z"]);
+
+        let mut breadth_first = Search::new(tmp.path().to_path_buf(), None, None)
+            .unwrap()
+            .with_traversal_order(TraversalOrder::BreadthFirst);
+        breadth_first.search().unwrap();
+        assert_eq!(relative_paths(&breadth_first), ["a", "z", "a/b", "a/b/c"]);
+    }
+
+    #[test]
+    fn test_find_all_versions_of_reports_drift() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifests = [
+            ("core", "[package]\nname = \"core\"\nversion = \"0.4.1\"\n"),
+            ("app", "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\ncore = { version = \"0.4.1\", path = \"../core\" }\n"),
+            ("tool", "[package]\nname = \"tool\"\nversion = \"0.2.0\"\n\n[dev-dependencies]\ncore = { version = \"0.3.0\" }\n"),
+            ("old", "[package]\nname = \"old\"\nversion = \"0.1.0\"\n\n[dependencies]\ncore = { version = \"0.2.0\" }\n\n[build-dependencies]\ncore = { version = \"0.2.0\" }\n"),
+        ];
+        for (dir, content) in manifests {
+            write_manifest(tmp.path(), dir, content);
+        }
+        let manifest = |dir: &str| tmp.path().join(dir).join("Cargo.toml");
+
+        let search = Search::new(tmp.path().to_path_buf(), None, None).unwrap();
+        let versions = search.find_all_versions_of("core");
+
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions["0.4.1"], vec![manifest("app"), manifest("core")]);
+        assert_eq!(versions["0.3.0"], vec![manifest("tool")]);
+        assert_eq!(versions["0.2.0"], vec![manifest("old")]);
+        assert!(search.find_all_versions_of("missing").is_empty());
+    }
+
+    #[test]
+    fn test_search_and_replace_updates_found_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\ncore = { version = \"0.4.1\", path = \"../core\" }\nother = \"0.4.1\"\n";
+        let core = "[package]\nname = \"core\"\nversion = \"0.4.1\"\n";
+        let tool = "[package]\nname = \"tool\"\nversion = \"0.2.0\"\n\n[dependencies]\ncore = { version = \"0.3.0\" }\n";
+        for (dir, content) in [("app", app), ("core", core), ("tool", tool)] {
+            write_manifest(tmp.path(), dir, content);
+        }
+
+        let mut search = Search::new(
+            tmp.path().to_path_buf(),
+            Some("0.4.1".to_string()),
+            Some("core".to_string()),
+        )
+        .unwrap();
+        search.search().unwrap();
+        assert_eq!(search.pkg_deps_dirs.len(), 2);
+
+        let updated = search.search_and_replace("0.4.1", "0.4.2", Some("core")).unwrap();
+
+        assert_eq!(
+            updated,
+            vec![
+                tmp.path().join("app").join("Cargo.toml"),
+                tmp.path().join("core").join("Cargo.toml"),
+            ]
+        );
+        let read = |dir: &str| fs::read_to_string(tmp.path().join(dir).join("Cargo.toml")).unwrap();
+        assert_eq!(read("app"), app.replacen("0.4.1", "0.4.2", 1));
+        assert_eq!(read("core"), core.replace("0.4.1", "0.4.2"));
+        assert_eq!(read("tool"), tool);
+    }
+
+    #[test]
+    fn test_inverse_returns_packages_without_dependency() {
+        let search = search_fixture(None);
+
+        let results = search.inverse("c").unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, PathBuf::from("/ws/c/Cargo.toml"));
+        assert_eq!(results[0].1.name, "c");
+    }
+
+    #[test]
+    fn test_search_finds_dev_only_dependency() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_manifest(
+            tmp.path(),
+            "app",
+            r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+runtime_dep = { version = "1.0.0" }
+
+[dev-dependencies]
+test_helper = { version = "0.3.0" }
+"#,
+        );
+
+        let mut search = Search::new(
+            tmp.path().to_path_buf(),
+            None,
+            Some("test_helper".to_string()),
+        )
+        .unwrap();
+        search.search().unwrap();
+        assert_eq!(search.pkg_deps_dirs.len(), 1);
+        assert!(search.pkg_deps_dirs[0].1.dependencies.is_empty());
+        assert_eq!(search.pkg_deps_dirs[0].1.dev_dependencies[0].name, "test_helper");
+    }
+
+    #[test]
+    fn test_new_matching_loads_other_manifests() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_manifest(tmp.path(), "app", "[package]\nname = \"app\"\nversion = \"0.1.0\"\n");
+        fs::write(
+            tmp.path().join("app").join("dev.toml"),
+            "[package]\nname = \"dev\"\nversion = \"0.2.0\"\n",
+        )
+        .unwrap();
+        fs::write(tmp.path().join("app").join("rustfmt.toml"), "edition = \"2021\"\n").unwrap();
+        let names = |search: &Search| -> Vec<String> {
+            search
+                .pkg_deps_dirs
+                .iter()
+                .map(|(_, pkg_and_deps)| pkg_and_deps.package.as_ref().unwrap().name.clone())
+                .collect()
+        };
+
+        let matching = |jobs: usize, pattern: &str| {
+            Search::new_matching(
+                tmp.path().to_path_buf(),
+                None,
+                None,
+                &[],
+                jobs,
+                pattern,
+                TomlGrammar::Toml05,
+            )
+        };
+
+        let search = Search::new(tmp.path().to_path_buf(), None, None).unwrap();
+        assert_eq!(names(&search), vec!["app"]);
+
+        for jobs in [1, 4] {
+            let search = matching(jobs, "*.toml").unwrap();
+            assert_eq!(names(&search), vec!["app", "dev"]);
+            assert_eq!(search.pkg_deps_dirs[1].0, tmp.path().join("app").join("dev.toml"));
+
+            let search = matching(jobs, "Cargo*").unwrap();
+            assert_eq!(names(&search), vec!["app"]);
+        }
+
+        assert!(matching(1, "[").is_err());
+    }
+
+    #[test]
+    fn test_new_matching_with_toml10_reads_dotted_key_package() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dotted = "package.name = \"dotted\"\npackage.version = \"0.4.1\"\n";
+        write_manifest(tmp.path(), "dotted", dotted);
+        let matching = |grammar| {
+            Search::new_matching(tmp.path().to_path_buf(), None, None, &[], 1, "Cargo.toml", grammar)
+                .unwrap()
+        };
+
+        assert!(matching(TomlGrammar::Toml05).pkg_deps_dirs.is_empty());
+
+        let search = matching(TomlGrammar::Toml10);
+        let package = search.pkg_deps_dirs[0].1.package.as_ref().unwrap();
+        assert_eq!((package.name.as_str(), package.version.as_str()), ("dotted", "0.4.1"));
+
+        // The replacement reads the package with the same grammar.
+        let updated = search.search_and_replace("0.4.1", "0.4.2", None).unwrap();
+        assert_eq!(updated, vec![tmp.path().join("dotted").join("Cargo.toml")]);
+        assert_eq!(
+            fs::read_to_string(&updated[0]).unwrap(),
+            dotted.replace("0.4.1", "0.4.2")
+        );
+    }
+
+    #[test]
+    fn test_load_dirs_pkgs_deps_skips_hidden_and_excluded_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        for dir in ["visible", ".hidden", "node_modules/pkg", "vendor/pkg", "scratch/pkg"] {
+            let name = dir.replace(['/', '.'], "_");
+            write_manifest(
+                tmp.path(),
+                dir,
+                &format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", name),
+            );
+        }
+
+        let results = load_dirs_pkgs_deps(
+            tmp.path(),
+            &["scratch".to_string()],
+            &cargo_toml_pattern(),
+            TomlGrammar::Toml05,
+        ).unwrap();
+
+        let names: Vec<String> = results
+            .iter()
+            .map(|(_, pkg_and_deps)| pkg_and_deps.package.as_ref().unwrap().name.clone())
+            .collect();
+        assert_eq!(names, vec!["visible"]);
+    }
+
+    #[test]
+    fn test_new_from_manifest_loads_only_that_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        for name in ["one", "two", "three"] {
+            write_manifest(
+                tmp.path(),
+                name,
+                &format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", name),
+            );
+        }
+        let manifest_path = tmp.path().join("two").join("Cargo.toml");
+
+        let mut search = Search::new_from_manifest(manifest_path.clone(), None, None).unwrap();
+        search.search().unwrap();
+        assert_eq!(search.pkg_deps_dirs.len(), 1);
+        assert_eq!(search.pkg_deps_dirs[0].0, manifest_path);
+
+        let mut search =
+            Search::new_from_manifest(manifest_path.clone(), Some("0.1.0".to_string()), None)
+                .unwrap();
+        search.search().unwrap();
+        assert_eq!(search.pkg_deps_dirs.len(), 1);
+
+        let mut search =
+            Search::new_from_manifest(manifest_path.clone(), Some("0.2.0".to_string()), None)
+                .unwrap();
+        search.search().unwrap();
+        assert!(search.pkg_deps_dirs.is_empty());
+
+        let mut search =
+            Search::new_from_manifest(manifest_path, None, Some("one".to_string())).unwrap();
+        search.search().unwrap();
+        assert!(search.pkg_deps_dirs.is_empty());
+    }
+
+    #[test]
+    fn test_search_single_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_manifest(
+            tmp.path(),
+            "one",
+            "[package]\nname = \"one\"\nversion = \"0.1.0\"\n\n[dependencies]\ntwo = { version = \"0.2.0\" }\n",
+        );
+        let manifest_path = tmp.path().join("one").join("Cargo.toml");
+
+        let found = Search::search_single_file(&manifest_path, Some("0.2.0".to_string()), None)
+            .unwrap()
+            .unwrap();
+        assert!(found.package.is_none());
+        assert_eq!(found.dependencies.len(), 1);
+        assert_eq!(found.dependencies[0].name, "two");
+
+        let found = Search::search_single_file(&manifest_path, None, Some("one".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.package.unwrap().name, "one");
+        assert!(found.dependencies.is_empty());
+    }
+
+    #[test]
+    fn test_search_single_file_no_match() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_manifest(tmp.path(), "one", "[package]\nname = \"one\"\nversion = \"0.1.0\"\n");
+        let manifest_path = tmp.path().join("one").join("Cargo.toml");
+
+        assert!(
+            Search::search_single_file(&manifest_path, Some("9.9.9".to_string()), None)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            Search::search_single_file(&manifest_path, None, Some("other".to_string()))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_search_single_file_missing_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let result = Search::search_single_file(&tmp.path().join("Cargo.toml"), None, None);
+        assert!(result.is_err());
+    }
+
+    fn in_memory_fixture() -> HashMap<PathBuf, String> {
+        HashMap::from([
+            (
+                PathBuf::from("/ws/app/Cargo.toml"),
+                "[package]\nname = \"app\"\nversion = \"0.2.0\"\n\n[dependencies]\ncore = { version = \"0.4.1\", path = \"../core\" }\nlog = { version = \"0.4.1\" }\n".to_string(),
+            ),
+            (
+                PathBuf::from("/ws/core/Cargo.toml"),
+                "[package]\nname = \"core\"\nversion = \"0.4.1\"\n".to_string(),
+            ),
+        ])
+    }
+
+    fn dep_names_and_versions(pkg_and_deps: &PackageAndDeps) -> Vec<(String, String)> {
+        let mut deps: Vec<_> = pkg_and_deps
+            .dependencies
+            .iter()
+            .map(|dep| (dep.name.clone(), dep.version.clone()))
+            .collect();
+        deps.sort();
+        deps
+    }
+
+    #[test]
+    fn test_search_in_files_filters_by_version() {
+        let found = Search::search_in_files(in_memory_fixture(), Some("0.4.1".to_string()), None).unwrap();
+
+        let paths: Vec<&Path> = found.iter().map(|(path, _)| path.as_path()).collect();
+        assert_eq!(paths, [Path::new("/ws/app/Cargo.toml"), Path::new("/ws/core/Cargo.toml")]);
+        // app itself is at 0.2.0, only its dependencies match.
+        assert!(found[0].1.package.is_none());
+        assert_eq!(
+            dep_names_and_versions(&found[0].1),
+            [("core".to_string(), "0.4.1".to_string()), ("log".to_string(), "0.4.1".to_string())]
+        );
+        assert_eq!(found[1].1.package.as_ref().map(|pkg| pkg.name.as_str()), Some("core"));
+    }
+
+    #[test]
+    fn test_search_in_files_filters_by_package_name() {
+        let found = Search::search_in_files(in_memory_fixture(), None, Some("log".to_string())).unwrap();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, PathBuf::from("/ws/app/Cargo.toml"));
+        assert!(found[0].1.package.is_none());
+        assert_eq!(
+            dep_names_and_versions(&found[0].1),
+            [("log".to_string(), "0.4.1".to_string())]
+        );
+
+        let none = Search::search_in_files(in_memory_fixture(), Some("0.2.0".to_string()), Some("core".to_string())).unwrap();
+        assert!(none.is_empty());
+    }
+    #[test]
+    fn test_search_in_files_finds_dev_dependencies() {
+        let contents = HashMap::from([(
+            PathBuf::from("/ws/app/Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.2.0\"\n\n[dev-dependencies]\nmy-helper = { version = \"0.3.0\" }\nother-helper = { version = \"0.3.1\" }\n".to_string(),
+        )]);
+
+        let found = Search::search_in_files(contents.clone(), None, Some("my-helper".to_string())).unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(found[0].1.package.is_none());
+        assert!(found[0].1.dependencies.is_empty());
+        assert_eq!(found[0].1.dev_dependencies.len(), 1);
+        assert_eq!(found[0].1.dev_dependencies[0].name, "my-helper");
+
+        let found = Search::search_in_files(contents, Some("0.3.0".to_string()), None).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].1.count(), 1);
+        assert_eq!(found[0].1.dev_dependencies[0].version, "0.3.0");
+    }
+    #[test]
+    fn test_search_in_files_finds_target_dependencies() {
+        let contents = HashMap::from([(
+            PathBuf::from("/ws/app/Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.2.0\"\n\n[target.'cfg(unix)'.dependencies]\nnix = { version = \"0.29.0\" }\nlibc = { version = \"0.2.0\" }\n".to_string(),
+        )]);
+
+        let found = Search::search_in_files(contents.clone(), None, Some("nix".to_string())).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].1.target_dependencies.len(), 1);
+        assert_eq!(found[0].1.target_dependencies[0].0, "cfg(unix)");
+        assert_eq!(found[0].1.target_dependencies[0].1.name, "nix");
+
+        // The package and libc are both at 0.2.0.
+        let found = Search::search_in_files(contents, Some("0.2.0".to_string()), None).unwrap();
+        assert_eq!(found[0].1.count(), 2);
+        assert_eq!(found[0].1.target_dependencies[0].1.name, "libc");
+    }
+    #[test]
+    fn test_search_with_show_unversioned() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_manifest(
+            tmp.path(),
+            "app",
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nlocal = { path = \"../local\" }\nserde = \"1.0\"\n",
+        );
+        let search_local = |show_unversioned: bool| {
+            let mut search = Search::new(tmp.path().to_path_buf(), None, Some("local".to_string()))
+                .unwrap()
+                .with_show_unversioned(show_unversioned);
+            search.search().unwrap();
+            search.pkg_deps_dirs
+        };
+
+        assert!(search_local(false).is_empty());
+
+        let found = search_local(true);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].1.count(), 0);
+        assert_eq!(found[0].1.unversioned_dependencies[0].name, "local");
+        let display = get_colored_pkg_deps(&found[0].1, |s| s.normal());
+        assert!(display.contains(r#"local = { path = "../local" }"#));
+
+        // A version filter drops them.
+        let mut search = Search::new(tmp.path().to_path_buf(), Some("1.0".to_string()), None)
+            .unwrap()
+            .with_show_unversioned(true);
+        search.search().unwrap();
+        assert!(search.pkg_deps_dirs[0].1.unversioned_dependencies.is_empty());
+    }
 }
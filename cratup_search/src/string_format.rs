@@ -1,6 +1,7 @@
 // use anyhow::Result;
 use colored::{ColoredString, Colorize};
 use log::debug;
+use std::fmt;
 // use std::fs;
 use std::path::{Path, PathBuf};
 // use walkdir::WalkDir;
@@ -8,31 +9,50 @@ use std::path::{Path, PathBuf};
 use crate::file_parts::build_directory_display;
 use cratup_tree_sitter::PackageAndDeps;
 
+/// Match counts at which `build_matches_info` switches color: below `warn` the count is
+/// green, from `warn` it is yellow and from `error` it is red.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchThresholds {
+    pub warn: usize,
+    pub error: usize,
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct VersionMatch {
     pub file_path: String,
+    /// `file_path` relative to the root directory given to `VersionMatch::new`, or
+    /// `file_path` itself when it is outside of it. Used for display.
+    pub relative_path: String,
     pub matches: usize,
     pub pkg_deps: PackageAndDeps,
 }
 
 impl VersionMatch {
-    /// Constructs a new VersionMatch by extracting a String from a PathBuf and counting
-    /// the package and dependency elements.
-    pub fn new(file_path: PathBuf, pkg_deps: PackageAndDeps) -> Self {
+    /// Constructs a new VersionMatch by extracting a String from a PathBuf, relative to
+    /// `root_dir` for display, and counting the package and dependency elements.
+    pub fn new(file_path: PathBuf, pkg_deps: PackageAndDeps, root_dir: &Path) -> Self {
         // Immediately extract the displayable string from the PathBuf.
         let file_path_str = file_path
             .to_str()
             .expect("Invalid Unicode in file_path")
             .to_owned();
+        let relative_path = file_path
+            .strip_prefix(root_dir)
+            .map(|relative| relative.to_str().expect("Invalid Unicode in file_path").to_owned())
+            .unwrap_or_else(|_| file_path_str.clone());
 
-        debug!("Creating new VersionMatch for file: {:?}", file_path_str);
+        debug!(
+            "Creating new VersionMatch for file: {:?} (relative: {:?})",
+            file_path_str, relative_path
+        );
         debug!(
             "Initial package/deps state - has package: {}, dependencies count: {}",
             pkg_deps.package.is_some(),
             pkg_deps.dependencies.len()
         );
 
-        // Count 1 if a package exists, plus the number of dependencies.
+        // Count 1 if a package exists, plus the number of dependencies of every kind.
         let mut count = 0;
         if pkg_deps.package.is_some() {
             count += 1;
@@ -41,15 +61,16 @@ impl VersionMatch {
             debug!("No package found in this match");
         }
 
-        count += pkg_deps.dependencies.len();
+        count += pkg_deps.all_dependencies().count();
         debug!(
-            "Added {} dependencies - final match count: {}",
-            pkg_deps.dependencies.len(),
+            "Added {} dependencies of every kind - final match count: {}",
+            pkg_deps.all_dependencies().count(),
             count
         );
 
         let version_match = VersionMatch {
             file_path: file_path_str,
+            relative_path,
             matches: count,
             pkg_deps,
         };
@@ -59,8 +80,56 @@ impl VersionMatch {
     }
 }
 
-/// Returns a colored string representing the package info and its dependencies.
-/// The function takes a reference to a PackageAndDeps and a closure for coloring the version.
+/// Totals of a list of `VersionMatch`, as returned by `VersionMatch::summarise`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionMatchSummary {
+    /// Number of files with at least one match.
+    pub total_files: usize,
+    /// Number of matches across all files.
+    pub total_matches: usize,
+}
+
+impl VersionMatch {
+    /// Adds up the matches of every file.
+    pub fn summarise(matches: &[VersionMatch]) -> VersionMatchSummary {
+        let summary = VersionMatchSummary {
+            total_files: matches.iter().filter(|m| m.matches > 0).count(),
+            total_matches: matches.iter().map(|m| m.matches).sum(),
+        };
+        debug!("Summarised {} version match(es): {:?}", matches.len(), summary);
+        summary
+    }
+
+    /// Merges the matches of the same `file_path` into one, in the order each file first
+    /// appears, combining their `pkg_deps` with `PackageAndDeps::merge` and recounting
+    /// `matches` from the merged result.
+    pub fn merge_matches(matches: Vec<VersionMatch>) -> Vec<VersionMatch> {
+        let before = matches.len();
+        let mut merged: Vec<VersionMatch> = Vec::with_capacity(before);
+        for version_match in matches {
+            match merged.iter_mut().find(|m| m.file_path == version_match.file_path) {
+                Some(existing) => {
+                    existing.pkg_deps.merge(version_match.pkg_deps);
+                    existing.matches = existing.pkg_deps.count();
+                }
+                None => merged.push(version_match),
+            }
+        }
+        debug!("Merged {} version match(es) into {}", before, merged.len());
+        merged
+    }
+}
+
+impl fmt::Display for VersionMatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Found {} matches in {} files",
+            self.total_matches, self.total_files
+        )
+    }
+}
+
 /// Returns a colored string representing the package info and its dependencies.
 /// The function takes a reference to a PackageAndDeps and a closure for coloring the version.
 pub fn get_colored_pkg_deps<F>(pkg_deps: &PackageAndDeps, color_version: F) -> String
@@ -69,42 +138,39 @@ where
 {
     debug!(
         "Starting get_colored_pkg_deps for package: {:?}",
-        pkg_deps.package.as_ref().map(|p| &p.name)
+        pkg_deps.package.as_ref().map(|p| p.to_display_string())
     );
 
-    // Process dependencies.
-        debug!("Processing {} dependencies", pkg_deps.dependencies.len());
+    // Process dependencies, followed by the dev-, build- and target-specific dependencies.
+        debug!("Processing {} dependencies", pkg_deps.all_dependencies().count());
         let deps_display = pkg_deps
-            .dependencies
-            .iter()
+            .all_dependencies()
             .map(|dep| {
-                debug!("Processing dependency: {}", dep.name);
-                // Format each dependency's name pair with the provided color for the version.
-                let formatted_name_pair =
-                    format_pair_with_version(&dep.name_pair, &dep.version, |s| color_version(s));
-                format!("\t{}: {}", dep.name.yellow(), formatted_name_pair)
+                let display_string = dep.to_display_string();
+                debug!("Processing dependency: {}", display_string);
+                // Color the version within the plain `name = "<version>"` form.
+                let formatted_dep =
+                    format_pair_with_version(&display_string, &dep.version, |s| color_version(s));
+                format!("\t{}: {}", dep.name.yellow(), formatted_dep)
             })
+            // The dependencies without a version have nothing to color.
+            .chain(
+                pkg_deps
+                    .unversioned_dependencies
+                    .iter()
+                    .map(|dep| format!("\t{}: {}", dep.name.yellow(), dep.name_pair)),
+            )
             .collect::<Vec<String>>()
             .join("\n");
 
     // If package info is available, include it in the output.
     if let Some(pkg) = &pkg_deps.package {
-        debug!("Package info available, processing package: {}", pkg.name);
-        let formatted_name_pair =
-            format_pair_with_version(&pkg.name_pair, &pkg.version, |s| color_version(s));
-        debug!("Formatted name pair: {:?}", formatted_name_pair);
-
-        // Use `format_pair_with_version` to format pkg.version_pair, coloring the version.
-        let formatted_version_pair =
-            format_pair_with_version(&pkg.version_pair, &pkg.version, |s| color_version(s));
-        debug!("Formatted version pair: {:?}", formatted_version_pair);
-
-        let pkg_display = format!(
-            "{}: {} {}",
-            pkg.name.purple(),
-            formatted_name_pair,
-            formatted_version_pair
-        );
+        let display_string = pkg.to_display_string();
+        debug!("Package info available, processing package: {}", display_string);
+        // Color the version within the plain `name v<version>` form.
+        let formatted_pkg =
+            format_pair_with_version(&display_string, &pkg.version, |s| color_version(s));
+        let pkg_display = format!("{}: {}", pkg.name.purple(), formatted_pkg);
         debug!("Final package display string: {}", pkg_display);
 
         format!("\t{}\n{}", pkg_display, deps_display)
@@ -144,22 +210,20 @@ where
     }
 }
 
-pub fn get_colored_dir_path_and_matches(version_match: &VersionMatch, current_dir: &Path) -> String {
+pub fn get_colored_dir_path_and_matches(
+    version_match: &VersionMatch,
+    thresholds: &MatchThresholds,
+) -> String {
     debug!("Starting to build match info for version update");
     debug!("VersionMatch details: {:?}", version_match);
-    debug!("Current directory: {:?}", current_dir);
 
-    // Here, file_path is already a String that was derived from the PathBuf.
-    let current_dir_str = current_dir
-        .to_str()
-        .expect("Invalid Unicode in current_dir");
-
-    // Use the file_path string directly.
-    let display_path = build_directory_display(&version_match.file_path, current_dir_str);
+    // The relative path was computed when the match was created, so there is no
+    // root directory left to strip.
+    let display_path = build_directory_display(&version_match.relative_path, "");
     debug!("Built display path: '{}'", display_path);
 
     // Build the matches info using another helper function.
-    let matches_info = build_matches_info(version_match.matches);
+    let matches_info = build_matches_info(version_match.matches, thresholds);
     debug!("Built matches info: '{}'", matches_info);
 
     // Append the matches info to the display path.
@@ -171,17 +235,22 @@ pub fn get_colored_dir_path_and_matches(version_match: &VersionMatch, current_di
 }
 
 pub fn get_colored_dir_path(file_path: &Path, root_dir: &Path) -> String {
+    let root_dir_str = root_dir
+        .to_str()
+        .expect("Invalid Unicode in package directory");
+    get_colored_dir_path_str(file_path, root_dir_str)
+}
+
+/// Same as `get_colored_dir_path`, with the root directory already converted to a
+/// string, so a caller displaying many paths under the same root converts it only once.
+pub fn get_colored_dir_path_str(file_path: &Path, root_dir_str: &str) -> String {
     debug!("Starting to build colored directory display for package directory");
     debug!("Search directory: {:?}", file_path);
-    debug!("Package directory: {:?}", root_dir);
+    debug!("Package directory: {:?}", root_dir_str);
 
-    // Convert both paths to string slices.
     let file_path_str = file_path
         .to_str()
         .expect("Invalid Unicode in search directory");
-    let root_dir_str = root_dir
-        .to_str()
-        .expect("Invalid Unicode in package directory");
 
     // Use the existing helper to build the relative display path.
     let colored_display = build_directory_display( file_path_str, root_dir_str);
@@ -192,14 +261,178 @@ pub fn get_colored_dir_path(file_path: &Path, root_dir: &Path) -> String {
     colored_display
 }
 
-fn build_matches_info(matches: usize) -> String {
+/// Formats the match count as `(N matches)`, or `(1 match)`, colored by `thresholds`.
+pub fn build_matches_info(matches: usize, thresholds: &MatchThresholds) -> String {
+    build_matches_info_with_format(matches, thresholds, "match", "matches")
+}
+
+/// Same as `build_matches_info`, naming what is counted with `singular` for exactly one
+/// and `plural` otherwise, e.g. `"file"` and `"files"`.
+pub fn build_matches_info_with_format(
+    matches: usize,
+    thresholds: &MatchThresholds,
+    singular: &str,
+    plural: &str,
+) -> String {
     debug!("Building matches info for count: {}", matches);
 
-    let colored_matches = matches.to_string().green();
+    let colored_matches = if matches >= thresholds.error {
+        matches.to_string().red()
+    } else if matches >= thresholds.warn {
+        matches.to_string().yellow()
+    } else {
+        matches.to_string().green()
+    };
     debug!("Formatted colored matches: {:?}", colored_matches);
 
-    let result = format!("({} matches)", colored_matches);
+    let noun = if matches == 1 { singular } else { plural };
+    let result = format!("({} {})", colored_matches, noun);
     debug!("Final matches info string: '{}'", result);
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::strip_ansi;
+
+    #[test]
+    fn test_build_matches_info_color_bands() {
+        let thresholds = MatchThresholds { warn: 2, error: 5 };
+        let expected = |colored: ColoredString| format!("({} matches)", colored);
+
+        assert_eq!(build_matches_info(0, &thresholds), expected("0".green()));
+        assert_eq!(build_matches_info(1, &thresholds), format!("({} match)", "1".green()));
+        assert_eq!(build_matches_info(2, &thresholds), expected("2".yellow()));
+        assert_eq!(build_matches_info(4, &thresholds), expected("4".yellow()));
+        assert_eq!(build_matches_info(5, &thresholds), expected("5".red()));
+        assert_eq!(build_matches_info(12, &thresholds), expected("12".red()));
+    }
+
+    #[test]
+    fn test_build_matches_info_custom_thresholds() {
+        let thresholds = MatchThresholds { warn: 1, error: 3 };
+        let expected = |colored: ColoredString| format!("({} matches)", colored);
+
+        assert_eq!(build_matches_info(0, &thresholds), expected("0".green()));
+        assert_eq!(build_matches_info(1, &thresholds), format!("({} match)", "1".yellow()));
+        assert_eq!(build_matches_info(3, &thresholds), expected("3".red()));
+    }
+
+    #[test]
+    fn test_build_matches_info_with_format_singular_and_plural() {
+        let thresholds = MatchThresholds { warn: 2, error: 5 };
+        let files = |matches| {
+            strip_ansi(&build_matches_info_with_format(matches, &thresholds, "file", "files"))
+        };
+
+        assert_eq!(files(0), "(0 files)");
+        assert_eq!(files(1), "(1 file)");
+        assert_eq!(files(2), "(2 files)");
+        assert_eq!(files(17), "(17 files)");
+        assert_eq!(strip_ansi(&build_matches_info(1, &thresholds)), "(1 match)");
+    }
+
+    fn version_match(file_path: &str, deps: usize, with_package: bool) -> VersionMatch {
+        let builder = (0..deps).fold(
+            cratup_tree_sitter::PackageAndDepBuilder::new(),
+            |builder, i| builder.dep(&format!("dep_{}", i), "0.4.1"),
+        );
+        let builder = if with_package {
+            builder.package("pkg", "0.4.1")
+        } else {
+            builder
+        };
+        VersionMatch::new(PathBuf::from(file_path), builder.build(), Path::new("/ws"))
+    }
+
+    #[test]
+    fn test_merge_matches_sums_same_file() {
+        let dev_deps = cratup_tree_sitter::PackageAndDepBuilder::new().dev_dep("dep_dev", "0.4.1").build();
+        let matches = vec![
+            version_match("/ws/a/Cargo.toml", 2, true),
+            version_match("/ws/b/Cargo.toml", 1, false),
+            VersionMatch::new(PathBuf::from("/ws/a/Cargo.toml"), dev_deps, Path::new("/ws")),
+        ];
+
+        let merged = VersionMatch::merge_matches(matches);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].file_path, "/ws/a/Cargo.toml");
+        assert_eq!(merged[0].matches, 3 + 1);
+        assert_eq!(merged[0].pkg_deps.dependencies.len(), 2);
+        assert_eq!(merged[0].pkg_deps.dev_dependencies.len(), 1);
+        assert_eq!(merged[1].file_path, "/ws/b/Cargo.toml");
+        assert_eq!(merged[1].matches, 1);
+    }
+
+    #[test]
+    fn test_version_match_relative_path() {
+        assert_eq!(version_match("/ws/a/Cargo.toml", 1, true).relative_path, "a/Cargo.toml");
+        assert_eq!(version_match("/ws/Cargo.toml", 1, true).relative_path, "Cargo.toml");
+        assert_eq!(
+            version_match("/other/Cargo.toml", 1, true).relative_path,
+            "/other/Cargo.toml"
+        );
+    }
+
+    #[test]
+    fn test_colored_dir_path_and_matches_uses_relative_path() {
+        let thresholds = MatchThresholds { warn: 2, error: 5 };
+        for file_path in [
+            "/ws/Cargo.toml",
+            "/ws/a/Cargo.toml",
+            "/ws/crates/nested/b/Cargo.toml",
+        ] {
+            let version_match = version_match(file_path, 1, true);
+            assert_eq!(
+                get_colored_dir_path_and_matches(&version_match, &thresholds),
+                format!(
+                    "{} {}",
+                    build_directory_display(file_path, "/ws"),
+                    build_matches_info(2, &thresholds)
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_colored_dir_path_str_matches_path_version() {
+        let root = Path::new("/ws");
+        for file_path in ["/ws/Cargo.toml", "/ws/a/Cargo.toml", "/ws/nested/deeper/b/Cargo.toml", "/other/Cargo.toml"] {
+            let file_path = Path::new(file_path);
+            assert_eq!(
+                get_colored_dir_path_str(file_path, "/ws"),
+                get_colored_dir_path(file_path, root)
+            );
+        }
+    }
+
+    #[test]
+    fn test_summarise_counts_files_and_matches() {
+        let matches = vec![
+            version_match("/ws/a/Cargo.toml", 2, true),
+            version_match("/ws/b/Cargo.toml", 4, false),
+            version_match("/ws/c/Cargo.toml", 0, true),
+            version_match("/ws/d/Cargo.toml", 0, false),
+        ];
+
+        let summary = VersionMatch::summarise(&matches);
+
+        assert_eq!(summary.total_files, 3);
+        assert_eq!(summary.total_matches, 8);
+        assert_eq!(summary.to_string(), "Found 8 matches in 3 files");
+    }
+
+    #[test]
+    fn test_summarise_empty() {
+        assert_eq!(
+            VersionMatch::summarise(&[]),
+            VersionMatchSummary {
+                total_files: 0,
+                total_matches: 0,
+            }
+        );
+    }
+}
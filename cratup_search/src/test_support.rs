@@ -0,0 +1,17 @@
+//! Helpers shared by the unit tests of the crate.
+
+/// Removes the ANSI color codes from `s`, so colored output can be compared as plain text
+/// whatever the global `colored` override of the other tests running in parallel.
+pub fn strip_ansi(s: &str) -> String {
+    let mut plain = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip the `[...m` of a Select Graphic Rendition sequence.
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
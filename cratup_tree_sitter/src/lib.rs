@@ -1,9 +1,23 @@
 mod tree_traversal;
 mod version_finder;
 
+pub use tree_traversal::BinTarget;
+pub use tree_traversal::DepKind;
+pub use tree_traversal::DepSource;
 pub use tree_traversal::DepsInfo;
+pub use tree_traversal::ExampleTarget;
+pub use tree_traversal::LibTarget;
+pub use tree_traversal::PackageAndDepBuilder;
 pub use tree_traversal::PackageAndDeps;
 pub use tree_traversal::PkgInfo;
+pub use tree_traversal::ProfileInfo;
+pub use tree_traversal::TomlGrammar;
 pub use tree_traversal::TomlParser;
+pub use tree_traversal::TomlParserError;
+pub use tree_traversal::UnversionedDep;
+pub use version_finder::PositionKind;
+pub use version_finder::UpdateProgress;
+pub use version_finder::VersionChangePosition;
 pub use version_finder::VersionUpdate;
+pub use version_finder::VersionUpdateError;
 // pub use version_finder::find_package;
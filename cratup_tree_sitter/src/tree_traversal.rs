@@ -1,13 +1,27 @@
 use tree_sitter::{Node, Parser, Tree};
 // use semver::Version;
 use derive_more::Display;
-use log::debug;
-use std::collections::HashMap;
+use log::{debug, warn};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub enum TomlParserError {
-    ParseError,
+    /// The TOML grammar could not be loaded into the tree-sitter parser.
+    UnsupportedLanguage,
+    /// The source holds nothing but whitespace.
+    EmptySource,
+    /// tree-sitter produced no tree for the source, with `error_nodes` 0, or, from
+    /// `TomlParser::new_strict`, the tree has `error_nodes` error or missing nodes.
+    ParseFailed { error_nodes: usize },
+    /// The file read by `new_from_path` is not valid UTF-8.
+    InvalidUtf8,
+    IoError(io::Error),
+    PatternError(glob::PatternError),
 }
 
 // New struct to hold the package info and dependency info.
@@ -15,15 +29,35 @@ pub enum TomlParserError {
 pub struct PackageAndDepsNodes<'a> {
     pub package: Option<(Node<'a>, PkgInfo)>,
     pub dependencies: HashMap<Node<'a>, DepsInfo>,
+    pub dev_dependencies: HashMap<Node<'a>, DepsInfo>,
+    pub build_dependencies: HashMap<Node<'a>, DepsInfo>,
+    /// The dependencies of the `[target.<predicate>.*dependencies]` tables, with their
+    /// predicate.
+    pub target_dependencies: HashMap<Node<'a>, (String, DepsInfo)>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PackageAndDeps {
     pub package: Option<PkgInfo>,
     pub dependencies: Vec<DepsInfo>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dev_dependencies: Vec<DepsInfo>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub build_dependencies: Vec<DepsInfo>,
+    /// The dependencies of the `[target.<predicate>.*dependencies]` tables, with their
+    /// predicate without quotes, e.g. `cfg(unix)` or `x86_64-pc-windows-gnu`. Their
+    /// `kind` tells which of the three tables of the target they were read from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target_dependencies: Vec<(String, DepsInfo)>,
+    /// The dependencies of every table declared with only a `path` or `git` source.
+    /// They have no version, so they are neither counted nor updated.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unversioned_dependencies: Vec<UnversionedDep>,
 }
 
 #[derive(Debug, Display, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[display(
     // "Package {} (pair: {}) version {} (pair: {})",
     "{}",
@@ -40,6 +74,7 @@ pub struct PkgInfo {
 }
 
 #[derive(Debug, Display, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[display(
     // "Dependencies {} (pair: {}) version {} (pair: {})",
     "{}",
@@ -53,23 +88,329 @@ pub struct DepsInfo {
     pub version: String,
     pub name_pair: String,
     pub version_pair: String,
+    /// The table the dependency was read from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: DepKind,
+}
+
+/// The table of a `DepsInfo`: `[dependencies]`, `[dev-dependencies]` or
+/// `[build-dependencies]`.
+#[derive(Debug, Display, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DepKind {
+    #[default]
+    #[display("dependencies")]
+    Normal,
+    #[display("dev-dependencies")]
+    Dev,
+    #[display("build-dependencies")]
+    Build,
+}
+
+/// Where a dependency without a version comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DepSource {
+    /// `path = "..."`
+    Path(String),
+    /// `git = "..."`, with its `rev`, `tag` or `branch` when one is given.
+    Git { url: String, reference: Option<String> },
+}
+
+/// A dependency declared with a `path` or `git` source and no `version`,
+/// e.g. `local = { path = "../local" }`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnversionedDep {
+    pub name: String,
+    pub name_pair: String,
+    pub source: DepSource,
+    pub kind: DepKind,
+}
+
+/// A `[[bin]]` target of the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinTarget {
+    pub name: String,
+    pub path: Option<String>,
+    pub required_features: Vec<String>,
+}
+
+/// An `[[example]]` target of the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExampleTarget {
+    pub name: String,
+    pub path: Option<String>,
+    pub required_features: Vec<String>,
+}
+
+/// The `[lib]` target of the manifest. Cargo defaults the name to the package name,
+/// so it is only set when the manifest overrides it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibTarget {
+    pub name: Option<String>,
+    pub path: Option<String>,
+}
+
+/// The settings of a `[profile.<name>]` section that `find_profiles` reads. A setting
+/// missing from the section is `None`, i.e. cargo's default for that profile applies.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileInfo {
+    /// `opt-level`, e.g. `3` or `s`.
+    pub opt_level: Option<String>,
+    /// `lto`, e.g. `true`, `false`, `thin` or `fat`.
+    pub lto: Option<String>,
+    /// Whether `debug` enables debug info: `false`, `0` and `"none"` disable it.
+    pub debug: Option<bool>,
+}
+
+/// TOML version a `TomlParser` reads the package of a Cargo.toml with.
+#[derive(Debug, Display, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TomlGrammar {
+    /// TOML 0.5: the package is only read from the `[package]` table.
+    #[default]
+    #[display("toml05")]
+    Toml05,
+    /// TOML 1.0: the package is also read from `package.name` and `package.version`
+    /// dotted keys at the top of the document. tree-sitter-toml has no separate 1.0
+    /// grammar, so this is done on the same tree.
+    #[display("toml10")]
+    Toml10,
 }
 
 #[derive(Debug)]
 pub struct TomlParser<'a> {
-    pub source: &'a str,
+    /// Borrowed for `TomlParser::new`, owned for `TomlParser::new_from_path`.
+    source: Cow<'a, str>,
     pub tree: Tree,
+    /// `TomlGrammar::Toml05` unless created with `new_with_grammar`.
+    grammar: TomlGrammar,
 }
 
 impl PackageAndDeps {
+    /// Parses `source` and returns its package and dependencies of every kind, the same as
+    /// `TomlParser::new` followed by `find_package_and_deps`, along with the dependencies
+    /// of `find_unversioned_deps`. `None` when the source has no `[package]`.
+    pub fn from_toml_str(source: &str) -> Result<Option<PackageAndDeps>, TomlParserError> {
+        Self::from_toml_str_with_grammar(source, TomlGrammar::default())
+    }
+
+    /// Same as `from_toml_str`, reading the package with `grammar`.
+    pub fn from_toml_str_with_grammar(
+        source: &str,
+        grammar: TomlGrammar,
+    ) -> Result<Option<PackageAndDeps>, TomlParserError> {
+        let toml_parser = TomlParser::new_with_grammar(source, grammar)?;
+        Ok(toml_parser.find_package_and_deps().map(|nodes| {
+            let mut pkg_and_deps = PackageAndDeps::from(nodes);
+            pkg_and_deps.unversioned_dependencies = toml_parser.find_unversioned_deps();
+            pkg_and_deps
+        }))
+    }
+
     /// Returns the number of valid elements.
     ///
     /// This method adds 1 if `package` is present (Some),
-    /// plus the number of dependency entries in `dependencies`.
+    /// plus the number of entries in `all_dependencies`.
     /// If neither exists, it returns zero.
     pub fn count(&self) -> usize {
         let pkg_count = if self.package.is_some() { 1 } else { 0 };
-        pkg_count + self.dependencies.len()
+        pkg_count + self.all_dependencies().count()
+    }
+
+    /// Iterates over `dependencies`, then `dev_dependencies`, `build_dependencies` and
+    /// `target_dependencies`.
+    pub fn all_dependencies(&self) -> impl Iterator<Item = &DepsInfo> {
+        self.dependencies
+            .iter()
+            .chain(&self.dev_dependencies)
+            .chain(&self.build_dependencies)
+            .chain(self.target_dependencies.iter().map(|(_, dep)| dep))
+    }
+
+    /// Keeps only the dependencies of every kind for which `f` returns true.
+    pub fn retain_dependencies<F: FnMut(&DepsInfo) -> bool>(&mut self, mut f: F) {
+        self.dependencies.retain(&mut f);
+        self.dev_dependencies.retain(&mut f);
+        self.build_dependencies.retain(&mut f);
+        self.target_dependencies.retain(|(_, dep)| f(dep));
+    }
+
+    /// Returns every version string, the package's first (if present) followed by
+    /// the version of each dependency in `all_dependencies` order.
+    pub fn all_versions(&self) -> Vec<&str> {
+        self.package
+            .iter()
+            .map(|pkg| pkg.version.as_str())
+            .chain(self.all_dependencies().map(|dep| dep.version.as_str()))
+            .collect()
+    }
+
+    /// Returns true if `version` is the version of the package or of any dependency.
+    pub fn has_version(&self, version: &str) -> bool {
+        self.all_versions().contains(&version)
+    }
+
+    /// Appends the dependencies of every kind of `other`, taking its package when `self`
+    /// has none, and removes the duplicates with `dedup_dependencies`.
+    pub fn merge(&mut self, other: PackageAndDeps) {
+        if self.package.is_none() {
+            self.package = other.package;
+        }
+        self.dependencies.extend(other.dependencies);
+        self.dev_dependencies.extend(other.dev_dependencies);
+        self.build_dependencies.extend(other.build_dependencies);
+        self.target_dependencies.extend(other.target_dependencies);
+        self.unversioned_dependencies.extend(other.unversioned_dependencies);
+        self.dedup_dependencies();
+    }
+
+    /// Removes the dependencies whose `(name, version)` already appeared earlier in the
+    /// list, keeping the first occurrence. `dev_dependencies` and `build_dependencies` are
+    /// deduplicated on their own, `target_dependencies` by predicate, kind, name and version
+    /// and `unversioned_dependencies` as a whole.
+    pub fn dedup_dependencies(&mut self) {
+        let before = self.all_dependencies().count();
+        for deps in [
+            &mut self.dependencies,
+            &mut self.dev_dependencies,
+            &mut self.build_dependencies,
+        ] {
+            let mut seen: HashSet<(String, String)> = HashSet::new();
+            deps.retain(|dep| seen.insert((dep.name.clone(), dep.version.clone())));
+        }
+        let mut seen: HashSet<(String, DepKind, String, String)> = HashSet::new();
+        self.target_dependencies.retain(|(predicate, dep)| {
+            seen.insert((predicate.clone(), dep.kind, dep.name.clone(), dep.version.clone()))
+        });
+        let mut seen: HashSet<UnversionedDep> = HashSet::new();
+        self.unversioned_dependencies.retain(|dep| seen.insert(dep.clone()));
+        debug!(
+            "Removed {} duplicate dependencies",
+            before - self.all_dependencies().count()
+        );
+    }
+}
+
+// PkgInfo and DepsInfo are identified by name and version only; the pairs are
+// formatting artifacts of the manifest they were read from, and the kind of a DepsInfo
+// is told by the list it is in.
+impl PartialEq for PkgInfo {
+    fn eq(&self, other: &Self) -> bool {
+        (&self.name, &self.version) == (&other.name, &other.version)
+    }
+}
+
+impl Eq for PkgInfo {}
+
+impl Hash for PkgInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.version.hash(state);
+    }
+}
+
+impl PartialEq for DepsInfo {
+    fn eq(&self, other: &Self) -> bool {
+        (&self.name, &self.version) == (&other.name, &other.version)
+    }
+}
+
+impl Eq for DepsInfo {}
+
+impl Hash for DepsInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.version.hash(state);
+    }
+}
+
+impl PkgInfo {
+    /// Plain text form of the package, e.g. `cratup v0.4.1`.
+    pub fn to_display_string(&self) -> String {
+        format!("{} v{}", self.name, self.version)
+    }
+}
+
+impl DepsInfo {
+    /// Plain text form of the dependency, e.g. `serde = "1.0"`.
+    pub fn to_display_string(&self) -> String {
+        format!("{} = \"{}\"", self.name, self.version)
+    }
+}
+
+impl DepKind {
+    /// The kind of the dependencies of the `[table_name]` table, `Normal` for anything
+    /// other than `dev-dependencies` and `build-dependencies`.
+    pub fn from_table_name(table_name: &str) -> Self {
+        match table_name {
+            "dev-dependencies" => DepKind::Dev,
+            "build-dependencies" => DepKind::Build,
+            _ => DepKind::Normal,
+        }
+    }
+}
+
+/// Builds a `PackageAndDeps` without going through a Cargo.toml, filling in
+/// `name_pair` and `version_pair` the way they are written in a manifest.
+#[derive(Debug, Default)]
+pub struct PackageAndDepBuilder {
+    pkg_and_deps: PackageAndDeps,
+}
+
+impl PackageAndDepBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `[package]` name and version.
+    pub fn package(mut self, name: &str, version: &str) -> Self {
+        self.pkg_and_deps.package = Some(PkgInfo {
+            name: name.to_string(),
+            version: version.to_string(),
+            name_pair: format!("name = \"{}\"", name),
+            version_pair: format!("version = \"{}\"", version),
+        });
+        self
+    }
+
+    /// Adds a `[dependencies]` entry.
+    pub fn dep(self, name: &str, version: &str) -> Self {
+        self.dep_of_kind(name, version, DepKind::Normal)
+    }
+
+    /// Adds a `[dev-dependencies]` entry.
+    pub fn dev_dep(self, name: &str, version: &str) -> Self {
+        self.dep_of_kind(name, version, DepKind::Dev)
+    }
+
+    /// Adds a `[build-dependencies]` entry.
+    pub fn build_dep(self, name: &str, version: &str) -> Self {
+        self.dep_of_kind(name, version, DepKind::Build)
+    }
+
+    fn dep_of_kind(mut self, name: &str, version: &str, kind: DepKind) -> Self {
+        let dep = DepsInfo {
+            name: name.to_string(),
+            version: version.to_string(),
+            name_pair: format!("{} = {{ version = \"{}\" }}", name, version),
+            version_pair: format!("version = \"{}\"", version),
+            kind,
+        };
+        match kind {
+            DepKind::Normal => self.pkg_and_deps.dependencies.push(dep),
+            DepKind::Dev => self.pkg_and_deps.dev_dependencies.push(dep),
+            DepKind::Build => self.pkg_and_deps.build_dependencies.push(dep),
+        }
+        self
+    }
+
+    pub fn build(self) -> PackageAndDeps {
+        self.pkg_and_deps
     }
 }
 
@@ -96,6 +437,10 @@ impl TomlParser<'_> {
 
         let mut package: Option<(Node<'b>, PkgInfo)> = None;
         let mut dependencies: HashMap<Node<'b>, DepsInfo> = HashMap::new();
+        let mut dev_dependencies: HashMap<Node<'b>, DepsInfo> = HashMap::new();
+        let mut build_dependencies: HashMap<Node<'b>, DepsInfo> = HashMap::new();
+        let mut target_dependencies: HashMap<Node<'b>, (String, DepsInfo)> = HashMap::new();
+        let mut has_package_table = false;
 
         children.iter().enumerate().for_each(|(i, child)| {
             debug!("\nProcessing child {} of kind: {}", i, child.kind());
@@ -113,8 +458,24 @@ impl TomlParser<'_> {
                     debug!("No dependencies found in this table");
                 }
 
+                if let Some(dev_deps_in_table) = self.find_dev_deps_in_table(*child) {
+                    debug!("Found {} dev-dependencies", dev_deps_in_table.len());
+                    dev_dependencies.extend(dev_deps_in_table);
+                }
+
+                if let Some(build_deps_in_table) = self.find_build_deps_in_table(*child) {
+                    debug!("Found {} build-dependencies", build_deps_in_table.len());
+                    build_dependencies.extend(build_deps_in_table);
+                }
+
+                if let Some(target_deps_in_table) = self.find_target_deps_in_table(*child) {
+                    debug!("Found {} target dependencies", target_deps_in_table.len());
+                    target_dependencies.extend(target_deps_in_table);
+                }
+
                 // Check for package info.
                 debug!("Looking for package info in table...");
+                has_package_table |= self.table_header(*child) == Some("package");
                 if let Some(pkg_tuple) = self.find_package_in_table(*child) {
                     package = Some(pkg_tuple);
                 } else {
@@ -125,15 +486,25 @@ impl TomlParser<'_> {
             }
         });
 
-        if let Some(pkg) = package {
-            Some(PackageAndDepsNodes {
-                package: Some(pkg),
-                dependencies,
-            })
-        } else {
+        if package.is_none() && self.grammar == TomlGrammar::Toml10 {
+            debug!("Looking for package info in dotted keys...");
+            package = self.find_dotted_package(root_node);
+        }
+
+        // A [package] inheriting its version, e.g. `version.workspace = true`, has no package
+        // info but its dependencies are still kept.
+        if package.is_none() && !has_package_table {
             debug!("No package information found in any tables, returning None");
-            None
+            return None;
         }
+
+        Some(PackageAndDepsNodes {
+            package,
+            dependencies,
+            dev_dependencies,
+            build_dependencies,
+            target_dependencies,
+        })
     }
 }
 
@@ -179,6 +550,10 @@ impl TomlParser<'_> {
                 None
             })
             .or_else(|| {
+                if self.grammar == TomlGrammar::Toml10 {
+                    debug!("Looking for package info in dotted keys...");
+                    return self.find_dotted_package(root_node).map(|(_, pkg_info)| pkg_info);
+                }
                 debug!("No package information found in any table, returning None");
                 None
             })
@@ -241,8 +616,6 @@ impl TomlParser<'_> {
 /// extract_pkg_info
 impl<'a> TomlParser<'a> {
     pub fn extract_pkg_info(&self, table_node: Node<'a>) -> Option<(Node<'a>, PkgInfo)> {
-        let strip_quotes = |s: &str| s.replace("\"", "");
-
         let mut table_cursor = table_node.walk();
         let mut name_opt: Option<String> = None;
         let mut version_opt: Option<String> = None;
@@ -268,22 +641,19 @@ impl<'a> TomlParser<'a> {
                         .trim();
 
                     // Look for the "name" and "version" keys
-                    if pair_key_text == "name" || pair_key_text == "version" {
-                        if let Some(string_node) = Self::find_child_by_kind(table_child, "string") {
-                            let text = string_node
-                                .utf8_text(self.source.as_bytes())
-                                .unwrap_or("")
-                                .trim()
-                                .to_string();
-                            if pair_key_text == "name" {
-                                name_opt = Some(strip_quotes(&text));
-                                name_pair_opt = Some(pair_text);
-                            } else if pair_key_text == "version" {
-                                version_opt = Some(strip_quotes(&text));
-                                version_pair_opt = Some(pair_text);
-                                version_node_opt = Some(string_node);
-                            }
-                        }
+                    if pair_key_text == "name"
+                        && let Some(string_node) = Self::find_child_by_kind(table_child, "string")
+                    {
+                        let text = string_node.utf8_text(self.source.as_bytes()).unwrap_or("");
+                        name_opt = Some(unquote(text));
+                        name_pair_opt = Some(pair_text);
+                    } else if pair_key_text == "version"
+                        && let Some((version, version_pair, string_node)) =
+                            Self::extract_string_pair(&self.source, table_child)
+                    {
+                        version_pair_opt = Some(version_pair);
+                        version_opt = Some(version);
+                        version_node_opt = Some(string_node);
                     }
                 }
             });
@@ -316,16 +686,54 @@ impl<'a> TomlParser<'a> {
     }
 }
 
+/// Returns the value of a TOML string without its quotes, for basic (`"1.0"`) and
+/// literal (`'1.0'`) strings alike, multi-line ones included.
+fn unquote(raw: &str) -> String {
+    let raw = raw.trim();
+    for quotes in ["\"\"\"", "'''", "\"", "'"] {
+        if let Some(value) = raw
+            .strip_prefix(quotes)
+            .and_then(|rest| rest.strip_suffix(quotes))
+        {
+            return value.to_string();
+        }
+    }
+    raw.to_string()
+}
+
+/// reformat_version_pair
+impl<'a> TomlParser<'a> {
+    /// Normalises a `key = "value"` pair to the canonical form with exactly one space
+    /// around `=`, so `version="0.4.3"` and `version  =  "0.4.3"` both become
+    /// `version = "0.4.3"`. A pair without `=` is returned trimmed.
+    pub fn reformat_version_pair(pair: &str, version: &str) -> String {
+        match pair.split_once('=') {
+            Some((key, _)) => format!("{} = \"{}\"", key.trim(), version),
+            None => {
+                debug!("Pair '{}' has no '=', leaving it as is", pair);
+                pair.trim().to_string()
+            }
+        }
+    }
+
+    /// Returns the value of the string of `pair_node`, see `unquote`, along with the pair
+    /// normalised by `reformat_version_pair` and the string node. Used for every version
+    /// pair, whether it is `version = "1.0"` in a table or `serde = '1.0'` on its own.
+    fn extract_string_pair(source: &str, pair_node: Node<'a>) -> Option<(String, String, Node<'a>)> {
+        let string_node = Self::find_child_by_kind(pair_node, "string")?;
+        let value = unquote(string_node.utf8_text(source.as_bytes()).ok()?);
+        let pair = Self::reformat_version_pair(pair_node.utf8_text(source.as_bytes()).ok()?, &value);
+        Some((value, pair, string_node))
+    }
+}
+
 /// extract_deps_info
 impl<'a> TomlParser<'a> {
     /// Helper method to extract the version info from an inline table node.
     fn extract_version_from_inline_table(
-        source: &'a str,
+        source: &str,
         inline_table_node: Node<'a>,
     ) -> Option<(String, String, Node<'a>)> {
-        // Closure to strip quotes from strings
-        let strip_quotes = |s: &str| s.replace("\"", "");
-
         inline_table_node
             .children(&mut inline_table_node.walk())
             .filter(|child| child.kind() == "pair")
@@ -336,17 +744,7 @@ impl<'a> TomlParser<'a> {
                     .map(|s| s.trim().to_string())?;
 
                 if key_text == "version" {
-                    // Find the string node corresponding to the version value
-                    Self::find_child_by_kind(pair_node, "string").and_then(|string_node| {
-                        let raw_version = string_node.utf8_text(source.as_bytes()).ok()?.trim();
-                        let version = strip_quotes(raw_version);
-                        let version_pair_text = pair_node
-                            .utf8_text(source.as_bytes())
-                            .ok()?
-                            .trim()
-                            .to_string();
-                        Some((version, version_pair_text, string_node))
-                    })
+                    Self::extract_string_pair(source, pair_node)
                 } else {
                     None
                 }
@@ -365,11 +763,14 @@ impl<'a> TomlParser<'a> {
                     .and_then(|node| node.utf8_text(self.source.as_bytes()).ok())
                     .map(|s| s.trim().to_string())?;
 
-                // Attempt to find the inline_table and extract version info via helper.
-                let version_info_opt = Self::find_child_by_kind(pair_node, "inline_table")
-                    .and_then(|inline_table_node| {
-                        Self::extract_version_from_inline_table(self.source, inline_table_node)
-                    });
+                // Attempt to find the inline_table and extract version info via helper,
+                // falling back to a version written as a plain string.
+                let version_info_opt = match Self::find_child_by_kind(pair_node, "inline_table") {
+                    Some(inline_table_node) => {
+                        Self::extract_version_from_inline_table(&self.source, inline_table_node)
+                    }
+                    None => Self::extract_string_pair(&self.source, pair_node),
+                };
 
                 version_info_opt.map(|(version, version_pair_text, version_str_node)| {
                     // Get the full pair text for the dependency.
@@ -383,6 +784,7 @@ impl<'a> TomlParser<'a> {
                         version,
                         name_pair,
                         version_pair: version_pair_text,
+                        kind: DepKind::Normal,
                     };
                     (version_str_node, deps_info)
                 })
@@ -391,10 +793,297 @@ impl<'a> TomlParser<'a> {
     }
 }
 
-/// find_deps_in_table
+/// find_dev_deps / find_build_deps
+impl TomlParser<'_> {
+    /// Finds the dependencies listed under `[dev-dependencies]`.
+    pub fn find_dev_deps<'b>(&'b self) -> Option<HashMap<Node<'b>, DepsInfo>> {
+        self.find_deps_by_table("dev-dependencies")
+    }
+
+    /// Finds the dependencies listed under `[build-dependencies]`.
+    pub fn find_build_deps<'b>(&'b self) -> Option<HashMap<Node<'b>, DepsInfo>> {
+        self.find_deps_by_table("build-dependencies")
+    }
+
+    /// Finds the dependencies of every top level table named `table_name`,
+    /// e.g. `"dependencies"` or `"dev-dependencies"`.
+    pub fn find_deps_by_table<'b>(&'b self, table_name: &str) -> Option<HashMap<Node<'b>, DepsInfo>> {
+        debug!("Starting to find dependencies in [{}]...", table_name);
+        let root_node = self.tree.root_node();
+
+        let mut cursor = root_node.walk();
+        let dependencies: HashMap<Node<'b>, DepsInfo> = root_node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "table")
+            .filter_map(|table_node| self.find_deps_in_named_table(table_node, table_name))
+            .flatten()
+            .collect();
+
+        debug!("Found {} dependencies in [{}]", dependencies.len(), table_name);
+        if dependencies.is_empty() {
+            None
+        } else {
+            Some(dependencies)
+        }
+    }
+}
+
+/// find_unversioned_deps
+impl<'a> TomlParser<'a> {
+    /// Returns the dependencies of every `[dependencies]`, `[dev-dependencies]` and
+    /// `[build-dependencies]` table, target ones included, that have a `path` or `git` but
+    /// no `version`, in file order. `find_package_and_deps` leaves them out.
+    pub fn find_unversioned_deps(&self) -> Vec<UnversionedDep> {
+        debug!("Starting to find dependencies without a version...");
+        let root_node = self.tree.root_node();
+
+        let mut cursor = root_node.walk();
+        let unversioned: Vec<UnversionedDep> = root_node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "table")
+            .filter_map(|table_node| Some((table_node, self.dep_table_kind(table_node)?)))
+            .flat_map(|(table_node, kind)| self.extract_unversioned_deps(table_node, kind))
+            .collect();
+
+        debug!("Found {} dependencies without a version", unversioned.len());
+        unversioned
+    }
+
+    /// The kind of the dependencies of a `[dependencies]`, `[dev-dependencies]` or
+    /// `[build-dependencies]` table, or of a `[target.<predicate>.*]` one. `None` for any
+    /// other table.
+    fn dep_table_kind(&self, table_node: Node<'a>) -> Option<DepKind> {
+        let key_text = self.table_header(table_node)?;
+        let section = match key_text.rsplit_once('.') {
+            Some((target, section)) => {
+                let (head, _) = target.split_once('.')?;
+                if head.trim() != "target" {
+                    return None;
+                }
+                section.trim()
+            }
+            None => key_text,
+        };
+        matches!(section, "dependencies" | "dev-dependencies" | "build-dependencies")
+            .then(|| DepKind::from_table_name(section))
+    }
+
+    fn extract_unversioned_deps(&self, table_node: Node<'a>, kind: DepKind) -> Vec<UnversionedDep> {
+        table_node
+            .children(&mut table_node.walk())
+            .filter(|pair_node| pair_node.kind() == "pair")
+            .filter_map(|pair_node| {
+                let inline_table_node = pair_node.child(pair_node.child_count().checked_sub(1)?)?;
+                if inline_table_node.kind() != "inline_table"
+                    || self.target_value_node(inline_table_node, "version").is_some()
+                {
+                    return None;
+                }
+
+                let source = match self.target_string_value(inline_table_node, "git") {
+                    Some(url) => DepSource::Git {
+                        url,
+                        reference: ["rev", "tag", "branch"]
+                            .iter()
+                            .find_map(|key| self.target_string_value(inline_table_node, key)),
+                    },
+                    None => DepSource::Path(self.target_string_value(inline_table_node, "path")?),
+                };
+                let name = pair_node
+                    .child(0)?
+                    .utf8_text(self.source.as_bytes())
+                    .ok()?
+                    .trim()
+                    .to_string();
+                let name_pair = pair_node
+                    .utf8_text(self.source.as_bytes())
+                    .unwrap_or("")
+                    .trim()
+                    .to_string();
+                debug!("Found {} without a version: {:?}", name, source);
+                Some(UnversionedDep {
+                    name,
+                    name_pair,
+                    source,
+                    kind,
+                })
+            })
+            .collect()
+    }
+}
+
+/// find_dep_section_names
+impl TomlParser<'_> {
+    /// Returns the header of every top level table whose last key segment is a dependency
+    /// section, e.g. `dependencies`, `dev-dependencies` or
+    /// `target.'cfg(unix)'.dependencies`, in file order. The entries themselves are not read.
+    pub fn find_dep_section_names(&self) -> Vec<String> {
+        debug!("Starting to find dependency section names...");
+        let root_node = self.tree.root_node();
+
+        let mut cursor = root_node.walk();
+        let names: Vec<String> = root_node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "table")
+            .filter_map(|table_node| {
+                let key_text = self.table_header(table_node)?;
+                // `[target.x.dependencies]` is a dotted key, only its last segment counts.
+                let last_segment = key_text.rsplit('.').next().unwrap_or(key_text);
+                last_segment
+                    .trim()
+                    .ends_with("dependencies")
+                    .then(|| key_text.to_string())
+            })
+            .inspect(|name| debug!("Found dependency section [{}]", name))
+            .collect();
+
+        debug!("Found {} dependency section(s)", names.len());
+        names
+    }
+}
+
+/// find_dep_version
+impl TomlParser<'_> {
+    /// Returns the version required for the dependency `dep_name`, looking in
+    /// `[dependencies]`, then `[dev-dependencies]` and `[build-dependencies]`, and stopping
+    /// at the first entry with a version. Both `dep = "1.0"` and
+    /// `dep = { version = "1.0", ... }` are read.
+    pub fn find_dep_version(&self, dep_name: &str) -> Option<String> {
+        debug!("Looking for the version of dependency '{}'", dep_name);
+        let root_node = self.tree.root_node();
+        let mut cursor = root_node.walk();
+        let tables: Vec<_> = root_node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "table")
+            .collect();
+
+        let mut version = None;
+        for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+            version = tables
+                .iter()
+                .filter(|table_node| self.table_header(**table_node) == Some(section))
+                .filter_map(|table_node| self.target_value_node(*table_node, dep_name))
+                .find_map(|value_node| match value_node.kind() {
+                    "string" => value_node
+                        .utf8_text(self.source.as_bytes())
+                        .ok()
+                        .map(unquote),
+                    // Not `Self::`, the nodes borrow `self` for less than its lifetime.
+                    "inline_table" => {
+                        TomlParser::extract_version_from_inline_table(&self.source, value_node)
+                            .map(|(version, _, _)| version)
+                    }
+                    _ => None,
+                });
+            if version.is_some() {
+                break;
+            }
+        }
+
+        debug!("Version of dependency '{}': {:?}", dep_name, version);
+        version
+    }
+}
+
+/// find_workspace
+impl TomlParser<'_> {
+    /// Returns the first top level `[workspace]` or `[workspace.*]` table, which only
+    /// exists in the Cargo.toml of a workspace root.
+    pub fn find_workspace(&self) -> Option<Node<'_>> {
+        let root_node = self.tree.root_node();
+        let mut cursor = root_node.walk();
+        let workspace = root_node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "table")
+            .find(|table_node| {
+                self.table_header(*table_node).is_some_and(|header| {
+                    header == "workspace" || header.starts_with("workspace.")
+                })
+            });
+        debug!("Workspace table found: {}", workspace.is_some());
+        workspace
+    }
+
+    /// Returns the string node of `version` in `[workspace.package]`, the version that
+    /// members declaring `version.workspace = true` inherit.
+    pub fn find_workspace_package_version(&self) -> Option<Node<'_>> {
+        let root_node = self.tree.root_node();
+        let mut cursor = root_node.walk();
+        let version_node = root_node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "table")
+            .filter(|table_node| self.table_header(*table_node) == Some("workspace.package"))
+            .find_map(|table_node| self.target_value_node(table_node, "version"))
+            .filter(|value_node| value_node.kind() == "string");
+        debug!("[workspace.package] version found: {}", version_node.is_some());
+        version_node
+    }
+}
+
+/// find_deps_in_table / find_dev_deps_in_table / find_build_deps_in_table /
+/// find_target_deps_in_table
 impl<'a> TomlParser<'a> {
     pub fn find_deps_in_table(&self, table_node: Node<'a>) -> Option<HashMap<Node<'a>, DepsInfo>> {
-        debug!("Starting to search for dependencies in table...");
+        self.find_deps_in_named_table(table_node, "dependencies")
+    }
+
+    /// Same as `find_deps_in_table`, for a `[dev-dependencies]` table.
+    pub fn find_dev_deps_in_table(
+        &self,
+        table_node: Node<'a>,
+    ) -> Option<HashMap<Node<'a>, DepsInfo>> {
+        self.find_deps_in_named_table(table_node, "dev-dependencies")
+    }
+
+    /// Same as `find_deps_in_table`, for a `[build-dependencies]` table.
+    pub fn find_build_deps_in_table(
+        &self,
+        table_node: Node<'a>,
+    ) -> Option<HashMap<Node<'a>, DepsInfo>> {
+        self.find_deps_in_named_table(table_node, "build-dependencies")
+    }
+
+    /// Finds the dependencies of a `[target.<predicate>.dependencies]` table, or of its
+    /// `dev-dependencies` and `build-dependencies` counterparts, each with the predicate
+    /// without quotes and `kind` set from the last key segment.
+    pub fn find_target_deps_in_table(
+        &self,
+        table_node: Node<'a>,
+    ) -> Option<HashMap<Node<'a>, (String, DepsInfo)>> {
+        let key_text = self.table_header(table_node)?;
+        let (target, section) = key_text.rsplit_once('.')?;
+        let (head, predicate) = target.split_once('.')?;
+        let section = section.trim();
+        if head.trim() != "target"
+            || !matches!(section, "dependencies" | "dev-dependencies" | "build-dependencies")
+        {
+            return None;
+        }
+        let kind = DepKind::from_table_name(section);
+        let predicate = predicate.trim().trim_matches(|c| c == '\'' || c == '"');
+        debug!("Found [{}] of target {}", section, predicate);
+
+        let deps_info: HashMap<Node<'a>, (String, DepsInfo)> = self
+            .extract_deps_info(table_node)
+            .into_iter()
+            .map(|(node, mut dep)| {
+                dep.kind = kind;
+                (node, (predicate.to_string(), dep))
+            })
+            .collect();
+        if deps_info.is_empty() {
+            None
+        } else {
+            Some(deps_info)
+        }
+    }
+
+    fn find_deps_in_named_table(
+        &self,
+        table_node: Node<'a>,
+        table_name: &str,
+    ) -> Option<HashMap<Node<'a>, DepsInfo>> {
+        debug!("Starting to search for [{}] in table...", table_name);
 
         // Try to find the bare_key node that might indicate this is a dependencies table
         if let Some(bare_key_node) = Self::find_child_by_kind(table_node, "bare_key") {
@@ -403,8 +1092,10 @@ impl<'a> TomlParser<'a> {
                 .unwrap_or("")
                 .trim();
 
-            if key_text == "dependencies" {
-                let deps_info = self.extract_deps_info(table_node);
+            if key_text == table_name {
+                let kind = DepKind::from_table_name(table_name);
+                let mut deps_info = self.extract_deps_info(table_node);
+                deps_info.values_mut().for_each(|dep| dep.kind = kind);
 
                 // Only return Some if we actually found dependencies
                 if !deps_info.is_empty() {
@@ -423,31 +1114,169 @@ impl<'a> TomlParser<'a> {
 impl<'a> TomlParser<'a> {
     /// Create a new TomlParser by validating and parsing the TOML source.
     /// You might want to adapt this constructor to properly initialize `pkg` and `deps`.
+    /// Malformed TOML is accepted with a warning, so that one broken table does not hide
+    /// the package and dependencies of the rest of the file; `new_strict` rejects it.
     pub fn new(source: &'a str) -> Result<Self, TomlParserError> {
+        Self::new_from_cow(Cow::Borrowed(source))
+    }
+
+    /// Same as `new`, failing with `ParseFailed` and the number of error nodes when the
+    /// TOML is malformed.
+    pub fn new_strict(source: &'a str) -> Result<Self, TomlParserError> {
+        let toml_parser = Self::new(source)?;
+        if toml_parser.has_error_nodes() {
+            return Err(TomlParserError::ParseFailed {
+                error_nodes: toml_parser.error_node_count(),
+            });
+        }
+        Ok(toml_parser)
+    }
+
+    /// Same as `new`, reading the package with `grammar`.
+    pub fn new_with_grammar(source: &'a str, grammar: TomlGrammar) -> Result<Self, TomlParserError> {
+        Ok(Self::new(source)?.with_grammar(grammar))
+    }
+
+    /// Sets the grammar the package is read with. The tree is the same for both, so
+    /// nothing is parsed again.
+    pub fn with_grammar(mut self, grammar: TomlGrammar) -> Self {
+        self.grammar = grammar;
+        self
+    }
+
+    /// Returns the TOML source the parser was created from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Same as `new`, for a source that is either borrowed or owned by the parser.
+    pub fn new_from_cow(source: Cow<'a, str>) -> Result<Self, TomlParserError> {
+        Self::from_cow_with_language(source, |parser| {
+            parser.set_language(tree_sitter_toml::language())
+        })
+    }
+
+    /// Same as `new_from_cow`, with the language set by `set_language`, so tests can make
+    /// that step fail.
+    fn from_cow_with_language<E: fmt::Debug>(
+        source: Cow<'a, str>,
+        set_language: impl FnOnce(&mut Parser) -> Result<(), E>,
+    ) -> Result<Self, TomlParserError> {
+        if source.trim().is_empty() {
+            debug!("TOML source is empty");
+            return Err(TomlParserError::EmptySource);
+        }
+
         let mut parser = Parser::new();
-        parser
-            .set_language(tree_sitter_toml::language())
-            .map_err(|_| TomlParserError::ParseError)?; // Propagate error if setting language fails.
+        set_language(&mut parser).map_err(|e| {
+            debug!("Failed to set the TOML language: {:?}", e);
+            TomlParserError::UnsupportedLanguage
+        })?;
         let tree = parser
-            .parse(source, None)
-            .ok_or_else(|| TomlParserError::ParseError)?;
+            .parse(source.as_ref(), None)
+            .ok_or(TomlParserError::ParseFailed { error_nodes: 0 })?;
+
+        let toml_parser = Self {
+            source,
+            tree,
+            grammar: TomlGrammar::default(),
+        };
+        if toml_parser.has_error_nodes() {
+            warn!(
+                "TOML source is malformed, {} error node(s) found; results may be incomplete",
+                toml_parser.error_node_count()
+            );
+        }
 
-        // Initialize with an empty HashMap for deps and pkg as None.
-        Ok(Self { source, tree })
+        Ok(toml_parser)
     }
-}
 
-/// find_child_by_kind
-impl<'a> TomlParser<'a> {
-    /// Original helper function remains available if needed.
-    fn find_child_by_kind(node: Node<'a>, kind: &str) -> Option<Node<'a>> {
-        node.children(&mut node.walk()).find_map(|child| {
-            if child.kind() == kind {
-                Some(child)
-            } else {
-                Self::find_child_by_kind(child, kind)
-            }
-        })
+    /// Returns true if tree-sitter had to insert error or missing nodes to parse the
+    /// source, i.e. the TOML is malformed and only partially understood.
+    pub fn has_error_nodes(&self) -> bool {
+        self.tree.root_node().has_error()
+    }
+
+    /// Counts the error and missing nodes of the tree, 0 for well-formed TOML.
+    pub fn error_node_count(&self) -> usize {
+        count_error_nodes(self.tree.root_node())
+    }
+}
+
+/// Counts the error and missing nodes below `node`, `node` included.
+fn count_error_nodes(node: Node) -> usize {
+    if !node.has_error() {
+        return 0;
+    }
+    let own = usize::from(node.is_error() || node.is_missing());
+    let mut cursor = node.walk();
+    let children: usize = node
+        .children(&mut cursor)
+        .map(count_error_nodes)
+        .sum();
+    own + children
+}
+
+// debug_tree
+#[cfg(debug_assertions)]
+impl TomlParser<'_> {
+    /// Returns the named nodes of the tree in pre-order, one per line, indented by their
+    /// depth, with their kind, byte range and text truncated to 40 characters, e.g.
+    /// `  pair 10..23 "name = \"pkg\""`. Only compiled in debug builds.
+    pub fn debug_tree(&self) -> String {
+        let mut lines = Vec::new();
+        self.debug_tree_node(self.tree.root_node(), 0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn debug_tree_node(&self, node: Node, depth: usize, lines: &mut Vec<String>) {
+        let text = node.utf8_text(self.source.as_bytes()).unwrap_or("");
+        let truncated: String = text.chars().take(40).collect();
+        let ellipsis = if truncated.len() < text.len() { "..." } else { "" };
+        lines.push(format!(
+            "{}{} {}..{} {:?}{}",
+            "  ".repeat(depth),
+            node.kind(),
+            node.start_byte(),
+            node.end_byte(),
+            truncated,
+            ellipsis
+        ));
+
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            self.debug_tree_node(child, depth + 1, lines);
+        }
+    }
+}
+
+/// new_from_path
+impl TomlParser<'static> {
+    /// Reads the file at `path` and parses it. The parser owns its copy of the content;
+    /// the returned string is the same content, for callers that need it next to the parser,
+    /// e.g. to compute byte offsets or edit it.
+    pub fn new_from_path(path: &Path) -> Result<(Self, String), TomlParserError> {
+        debug!("Reading TOML file: {:?}", path);
+        let content = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidData => TomlParserError::InvalidUtf8,
+            _ => TomlParserError::IoError(e),
+        })?;
+        let parser = Self::new_from_cow(Cow::Owned(content.clone()))?;
+        Ok((parser, content))
+    }
+}
+
+/// find_child_by_kind
+impl<'a> TomlParser<'a> {
+    /// Original helper function remains available if needed.
+    fn find_child_by_kind(node: Node<'a>, kind: &str) -> Option<Node<'a>> {
+        node.children(&mut node.walk()).find_map(|child| {
+            if child.kind() == kind {
+                Some(child)
+            } else {
+                Self::find_child_by_kind(child, kind)
+            }
+        })
     }
 }
 
@@ -469,23 +1298,388 @@ impl<'a> TomlParser<'a> {
     }
 }
 
+// find_dotted_package
+impl TomlParser<'_> {
+    /// Reads the package from the `package.name = "..."` and `package.version = "..."`
+    /// pairs at the top of `document`, returning the node of the version string along
+    /// with the package info.
+    fn find_dotted_package<'b>(&'b self, document: Node<'b>) -> Option<(Node<'b>, PkgInfo)> {
+        let text = |node: Node| node.utf8_text(self.source.as_bytes()).unwrap_or("").trim().to_string();
+
+        let mut name: Option<(String, String)> = None;
+        let mut version: Option<(Node<'b>, String, String)> = None;
+        let mut cursor = document.walk();
+        for pair_node in document.children(&mut cursor).filter(|child| child.kind() == "pair") {
+            let Some(dotted_key) = TomlParser::find_child_by_kind(pair_node, "dotted_key") else {
+                continue;
+            };
+            let keys: Vec<String> = dotted_key
+                .children(&mut dotted_key.walk())
+                .filter(|key| key.kind() == "bare_key")
+                .map(text)
+                .collect();
+            let Some(string_node) = TomlParser::find_child_by_kind(pair_node, "string") else {
+                continue;
+            };
+            let value = unquote(&text(string_node));
+
+            match keys.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+                ["package", "name"] => name = Some((value, text(pair_node))),
+                ["package", "version"] => {
+                    let version_pair = TomlParser::reformat_version_pair(&text(pair_node), &value);
+                    version = Some((string_node, value, version_pair));
+                }
+                _ => {}
+            }
+        }
+
+        let ((name, name_pair), (version_node, version, version_pair)) = (name?, version?);
+        debug!("Found dotted key package: {} {}", name, version);
+        Some((
+            version_node,
+            PkgInfo {
+                name,
+                version,
+                name_pair,
+                version_pair,
+            },
+        ))
+    }
+}
+
 // edit_node
 impl<'a> TomlParser<'a> {
     /// Edits the source code by replacing the part represented by `node` with `new_value`.
-    pub fn edit_node(&self, node: Node, new_value: &str) -> String {
+    /// Also returns the `(start_byte, end_byte)` range that was replaced, so callers can
+    /// batch several replacements through `source_with_edits`.
+    pub fn edit_node(&self, node: Node, new_value: &str) -> (String, (usize, usize)) {
         // Get the positions in the source code where the node is located.
         let start_byte = node.start_byte();
         let end_byte = node.end_byte();
 
-        let mut new_source_code = String::new();
-        new_source_code.push_str(&self.source[..start_byte]);
-        new_source_code.push_str(new_value);
-        new_source_code.push_str(&self.source[end_byte..]);
+        let new_source_code = self.source_with_edits(vec![(start_byte, end_byte, new_value)]);
+
+        (new_source_code, (start_byte, end_byte))
+    }
+}
+
+// source_with_edits
+impl<'a> TomlParser<'a> {
+    /// Applies all `(start_byte, end_byte, replacement)` edits to the source in one pass.
+    /// The edits are applied from the last to the first, so the byte offsets taken from the
+    /// current tree stay valid and no re-parsing is needed between edits.
+    pub fn source_with_edits(&self, mut edits: Vec<(usize, usize, &str)>) -> String {
+        edits.sort_by_key(|(start_byte, _, _)| std::cmp::Reverse(*start_byte));
+
+        let mut new_source_code = self.source.to_string();
+        for (start_byte, end_byte, replacement) in edits {
+            debug!(
+                "Replacing bytes {}..{} with {}",
+                start_byte, end_byte, replacement
+            );
+            new_source_code.replace_range(start_byte..end_byte, replacement);
+        }
 
         new_source_code
     }
 }
 
+// find_bin_targets
+impl TomlParser<'_> {
+    /// Returns every `[[bin]]` target in the order they appear in the manifest.
+    /// Entries without a `name` are skipped, since cargo rejects them anyway.
+    pub fn find_bin_targets(&self) -> Vec<BinTarget> {
+        debug!("Starting to find bin targets...");
+        let root_node = self.tree.root_node();
+
+        let mut cursor = root_node.walk();
+        let bin_targets: Vec<BinTarget> = root_node
+            .children(&mut cursor)
+            .filter(|child| {
+                child.kind() == "table_array_element" && self.table_key(*child) == Some("bin")
+            })
+            .filter_map(|bin_node| {
+                let name = self.target_string_value(bin_node, "name");
+                if name.is_none() {
+                    debug!("Skipping [[bin]] entry without a name");
+                }
+                Some(BinTarget {
+                    name: name?,
+                    path: self.target_string_value(bin_node, "path"),
+                    required_features: self.target_string_array(bin_node, "required-features"),
+                })
+            })
+            .collect();
+
+        debug!("Found {} bin target(s)", bin_targets.len());
+        bin_targets
+    }
+}
+
+// find_examples
+impl TomlParser<'_> {
+    /// Returns every `[[example]]` target in the order they appear in the manifest.
+    /// Entries without a `name` are skipped, since cargo rejects them anyway.
+    pub fn find_examples(&self) -> Vec<ExampleTarget> {
+        debug!("Starting to find example targets...");
+        let root_node = self.tree.root_node();
+
+        let mut cursor = root_node.walk();
+        let examples: Vec<ExampleTarget> = root_node
+            .children(&mut cursor)
+            .filter(|child| {
+                child.kind() == "table_array_element" && self.table_key(*child) == Some("example")
+            })
+            .filter_map(|example_node| {
+                let name = self.target_string_value(example_node, "name");
+                if name.is_none() {
+                    debug!("Skipping [[example]] entry without a name");
+                }
+                Some(ExampleTarget {
+                    name: name?,
+                    path: self.target_string_value(example_node, "path"),
+                    required_features: self.target_string_array(example_node, "required-features"),
+                })
+            })
+            .collect();
+
+        debug!("Found {} example target(s)", examples.len());
+        examples
+    }
+}
+
+// find_lib_target
+impl TomlParser<'_> {
+    /// Returns the `[lib]` target, or `None` when the manifest has no such section.
+    pub fn find_lib_target(&self) -> Option<LibTarget> {
+        debug!("Starting to find lib target...");
+        let root_node = self.tree.root_node();
+
+        let mut cursor = root_node.walk();
+        let lib_node = root_node.children(&mut cursor).find(|child| {
+            matches!(child.kind(), "table" | "table_array_element")
+                && self.table_key(*child) == Some("lib")
+        })?;
+
+        Some(LibTarget {
+            name: self.target_string_value(lib_node, "name"),
+            path: self.target_string_value(lib_node, "path"),
+        })
+    }
+}
+
+// find_profiles
+impl TomlParser<'_> {
+    /// Returns the `[profile.<name>]` sections by profile name, e.g. `release`.
+    /// Per-package overrides such as `[profile.release.package.foo]` are not included.
+    pub fn find_profiles(&self) -> HashMap<String, ProfileInfo> {
+        debug!("Starting to find profiles...");
+        let root_node = self.tree.root_node();
+
+        let mut cursor = root_node.walk();
+        let profiles: HashMap<String, ProfileInfo> = root_node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "table")
+            .filter_map(|table_node| {
+                let name = self.table_header(table_node)?.strip_prefix("profile.")?;
+                if name.contains('.') {
+                    debug!("Skipping profile override [profile.{}]", name);
+                    return None;
+                }
+
+                let debug = self.target_value_node(table_node, "debug").and_then(|node| {
+                    let text = node.utf8_text(self.source.as_bytes()).ok()?.trim();
+                    match node.kind() {
+                        "boolean" => Some(text == "true"),
+                        "integer" => Some(text != "0"),
+                        "string" => Some(text.trim_matches('"') != "none"),
+                        _ => None,
+                    }
+                });
+                let profile = ProfileInfo {
+                    opt_level: self.target_scalar_value(table_node, "opt-level"),
+                    lto: self.target_scalar_value(table_node, "lto"),
+                    debug,
+                };
+                debug!("Found profile {}: {:?}", name, profile);
+                Some((name.to_string(), profile))
+            })
+            .collect();
+
+        debug!("Found {} profile(s)", profiles.len());
+        profiles
+    }
+}
+
+// find_authors / find_categories / find_keywords
+impl TomlParser<'_> {
+    /// Returns the `authors` array of `[package]`, empty when it is missing.
+    pub fn find_authors(&self) -> Vec<String> {
+        self.package_string_array("authors")
+    }
+
+    /// Returns the `categories` array of `[package]`, empty when it is missing.
+    pub fn find_categories(&self) -> Vec<String> {
+        self.package_string_array("categories")
+    }
+
+    /// Returns the `keywords` array of `[package]`, empty when it is missing.
+    pub fn find_keywords(&self) -> Vec<String> {
+        self.package_string_array("keywords")
+    }
+
+    /// Collects the strings of the `key = [...]` array directly inside `[package]`.
+    fn package_string_array(&self, key: &str) -> Vec<String> {
+        let root_node = self.tree.root_node();
+
+        let mut cursor = root_node.walk();
+        let Some(package_node) = root_node.children(&mut cursor).find(|child| {
+            child.kind() == "table" && self.table_key(*child) == Some("package")
+        }) else {
+            debug!("No [package] table found while looking for '{}'", key);
+            return Vec::new();
+        };
+
+        let values = self.target_string_array(package_node, key);
+        debug!("Found {} value(s) for '{}'", values.len(), key);
+        values
+    }
+}
+
+// find_workspace_members
+impl TomlParser<'_> {
+    /// Returns the directories listed in `[workspace] members`, with globs such as
+    /// `"crates/*"` expanded relative to `root`. Members that are, or live under, an
+    /// entry of `[workspace] exclude` are left out, as are glob matches that are not
+    /// directories.
+    pub fn find_workspace_members(&self, root: &Path) -> Result<Vec<PathBuf>, TomlParserError> {
+        debug!("Starting to find workspace members under {:?}", root);
+        let root_node = self.tree.root_node();
+
+        let mut cursor = root_node.walk();
+        let Some(workspace_node) = root_node.children(&mut cursor).find(|child| {
+            child.kind() == "table" && self.table_key(*child) == Some("workspace")
+        }) else {
+            debug!("No [workspace] table found");
+            return Ok(Vec::new());
+        };
+
+        let excluded: Vec<PathBuf> = self
+            .target_string_array(workspace_node, "exclude")
+            .iter()
+            .map(|exclude| root.join(exclude))
+            .collect();
+        debug!("Excluded workspace paths: {:?}", excluded);
+
+        // The root is a literal path, only the members are globs.
+        let escaped_root = PathBuf::from(glob::Pattern::escape(&root.to_string_lossy()));
+        let mut members = Vec::new();
+        for member in self.target_string_array(workspace_node, "members") {
+            let pattern = escaped_root.join(&member);
+            let paths = glob::glob(&pattern.to_string_lossy())
+                .map_err(TomlParserError::PatternError)?;
+            for path in paths {
+                match path {
+                    Ok(path) if !path.is_dir() => debug!("Skipping non-directory {:?}", path),
+                    Ok(path) if excluded.iter().any(|exclude| path.starts_with(exclude)) => {
+                        debug!("Skipping excluded member {:?}", path)
+                    }
+                    Ok(path) if !members.contains(&path) => members.push(path),
+                    Ok(_) => {}
+                    Err(e) => debug!("Failed to read glob match of {:?}: {:?}", member, e),
+                }
+            }
+        }
+
+        debug!("Found {} workspace member(s)", members.len());
+        Ok(members)
+    }
+}
+
+// target helpers
+impl<'a> TomlParser<'a> {
+    /// Returns the header key of a `[table]` or `[[table]]` node.
+    fn table_key(&self, table_node: Node<'a>) -> Option<&str> {
+        let mut cursor = table_node.walk();
+        let key_node = table_node
+            .children(&mut cursor)
+            .find(|child| child.kind() == "bare_key")?;
+        key_node
+            .utf8_text(self.source.as_bytes())
+            .ok()
+            .map(str::trim)
+    }
+
+    /// Returns the full header of a `[table]` node, dotted keys included,
+    /// e.g. `workspace.package` or `target.'cfg(unix)'.dependencies`.
+    fn table_header(&self, table_node: Node<'a>) -> Option<&str> {
+        let mut cursor = table_node.walk();
+        let key_node = table_node
+            .children(&mut cursor)
+            .find(|child| matches!(child.kind(), "bare_key" | "dotted_key" | "quoted_key"))?;
+        key_node
+            .utf8_text(self.source.as_bytes())
+            .ok()
+            .map(str::trim)
+    }
+
+    /// Returns the value node of the `key = ...` pair directly inside the table.
+    fn target_value_node(&self, table_node: Node<'a>, key: &str) -> Option<Node<'a>> {
+        let mut cursor = table_node.walk();
+        table_node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "pair")
+            .find_map(|pair_node| {
+                let key_node = pair_node.child(0)?;
+                let key_text = key_node.utf8_text(self.source.as_bytes()).ok()?.trim();
+                if key_text == key {
+                    pair_node.child(pair_node.child_count().checked_sub(1)?)
+                } else {
+                    None
+                }
+            })
+    }
+
+    fn target_string_value(&self, table_node: Node<'a>, key: &str) -> Option<String> {
+        let value_node = self.target_value_node(table_node, key)?;
+        if value_node.kind() != "string" {
+            return None;
+        }
+        value_node
+            .utf8_text(self.source.as_bytes())
+            .ok()
+            .map(unquote)
+    }
+
+    /// Returns the text of a string, integer or boolean value, strings without their quotes.
+    fn target_scalar_value(&self, table_node: Node<'a>, key: &str) -> Option<String> {
+        let value_node = self
+            .target_value_node(table_node, key)
+            .filter(|node| matches!(node.kind(), "string" | "integer" | "boolean"))?;
+        value_node
+            .utf8_text(self.source.as_bytes())
+            .ok()
+            .map(unquote)
+    }
+
+    fn target_string_array(&self, table_node: Node<'a>, key: &str) -> Vec<String> {
+        let Some(array_node) = self
+            .target_value_node(table_node, key)
+            .filter(|node| node.kind() == "array")
+        else {
+            return Vec::new();
+        };
+
+        let mut cursor = array_node.walk();
+        array_node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "string")
+            .filter_map(|string_node| string_node.utf8_text(self.source.as_bytes()).ok())
+            .map(unquote)
+            .collect()
+    }
+}
+
 impl<'a> From<PackageAndDepsNodes<'a>> for PackageAndDeps {
     fn from(nodes: PackageAndDepsNodes<'a>) -> Self {
         // Debug output before conversion
@@ -495,19 +1689,26 @@ impl<'a> From<PackageAndDepsNodes<'a>> for PackageAndDeps {
         for (node, dep) in &nodes.dependencies {
             debug!("  Node: {:?}, Dependency: {}", node, dep);
         }
+        debug!("Dev-dependencies: {}", nodes.dev_dependencies.len());
+        debug!("Build-dependencies: {}", nodes.build_dependencies.len());
+        debug!("Target dependencies: {}", nodes.target_dependencies.len());
 
         // Perform the conversion
         let package = nodes.package.map(|(_, pkg_info)| pkg_info);
-        let dependencies = nodes
-            .dependencies
-            .into_iter()
-            .map(|(_node, deps_info)| deps_info)
-            .collect();
+        let dependencies = nodes.dependencies.into_values().collect();
+        let dev_dependencies = nodes.dev_dependencies.into_values().collect();
+        let build_dependencies = nodes.build_dependencies.into_values().collect();
+        let target_dependencies = nodes.target_dependencies.into_values().collect();
+        let unversioned_dependencies = Vec::new();
 
         // Create the result
         let result = PackageAndDeps {
             package,
             dependencies,
+            dev_dependencies,
+            build_dependencies,
+            target_dependencies,
+            unversioned_dependencies,
         };
 
         // Debug output after conversion
@@ -525,12 +1726,33 @@ impl<'a> From<PackageAndDepsNodes<'a>> for PackageAndDeps {
 impl fmt::Display for TomlParserError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            TomlParserError::ParseError => write!(f, "TOML parse error"),
+            TomlParserError::UnsupportedLanguage => write!(f, "TOML language is not supported by tree-sitter"),
+            TomlParserError::EmptySource => write!(f, "TOML source is empty"),
+            TomlParserError::ParseFailed { error_nodes: 0 } => write!(f, "TOML parse error"),
+            TomlParserError::ParseFailed { error_nodes } => {
+                write!(f, "TOML parse error, {} error nodes", error_nodes)
+            }
+            TomlParserError::InvalidUtf8 => write!(f, "TOML file is not valid UTF-8"),
+            TomlParserError::IoError(err) => write!(f, "failed to read TOML file: {}", err),
+            TomlParserError::PatternError(err) => {
+                write!(f, "invalid workspace member pattern: {}", err)
+            }
         }
     }
 }
 
-impl std::error::Error for TomlParserError {}
+impl std::error::Error for TomlParserError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TomlParserError::UnsupportedLanguage
+            | TomlParserError::EmptySource
+            | TomlParserError::ParseFailed { .. }
+            | TomlParserError::InvalidUtf8 => None,
+            TomlParserError::IoError(err) => Some(err),
+            TomlParserError::PatternError(err) => Some(err),
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -675,6 +1897,16 @@ package_test2 = { version = "0.4.3", path = "package_test2" }
         assert!(result.is_none(), "Should return None if package info is missing");
     }
 
+    #[test]
+    fn test_find_package_and_deps_inherited_package_version() {
+        let toml_source = "[package]\nname = \"root\"\nversion.workspace = true\n\n[dependencies]\ncrate_a = { version = \"0.4.1\", path = \"crate_a\" }\n";
+
+        let parser = TomlParser::new(toml_source).expect("Parsing should succeed");
+        let result = parser.find_package_and_deps().expect("dependencies should be kept");
+        assert!(result.package.is_none());
+        assert_eq!(result.dependencies.len(), 1);
+    }
+
     /// Test that a TOML file with no table nodes returns None.
     #[test]
     fn test_find_package_and_deps_no_tables() {
@@ -807,12 +2039,13 @@ package_test = "0.4.3"
 
         let deps_info = parser.extract_deps_info(deps_table_node);
 
-        // Expect no dependency info extracted because the format is incorrect (missing inline table)
-        assert_eq!(
-            deps_info.len(),
-            0,
-            "No dependency should be extracted if an inline table is not present"
-        );
+        // The plain string is the version, and the whole pair is the version pair.
+        assert_eq!(deps_info.len(), 1);
+        let (node, dep) = deps_info.into_iter().next().unwrap();
+        assert_eq!(dep.name, "package_test");
+        assert_eq!(dep.version, "0.4.3");
+        assert_eq!(dep.version_pair, r#"package_test = "0.4.3""#);
+        assert_eq!(node.utf8_text(toml_source.as_bytes()).unwrap(), r#""0.4.3""#);
     }
 
     #[test]
@@ -878,27 +2111,51 @@ package_test = { version = "0.4.3", path = "package_test" }
         }
     }
 
-    /// Test case where the dependency is defined without an inline table.
+    /// Test case where the dependency is defined without an inline table, with a
+    /// version requirement.
     ///
-    /// Here, we expect that no dependency info will be extracted since the inline table (and thus
-    /// the logic to extract the version) is missing.
+    /// Here, we expect the requirement to be extracted as it is written.
     #[test]
     fn test_extract_deps_info_no_inline_table1() {
         let toml_source = r#"
 [dependencies]
-package_test = "0.4.3"
+package_test = "^1.0"
 "#;
         let parser = TomlParser::new(toml_source).expect("Parsing should succeed");
 
         let deps_table_node = find_dependencies_table_node(&parser, toml_source)
             .expect("The TOML should contain a [dependencies] table");
 
-        // Since the dependency is not defined with an inline table,
-        // the extraction should yield an empty map.
         let deps_info = parser.extract_deps_info(deps_table_node);
-        assert!(
-            deps_info.is_empty(),
-            "No dependencies should be extracted when inline table is missing"
+        let versions: Vec<&str> = deps_info.values().map(|dep| dep.version.as_str()).collect();
+        assert_eq!(versions, vec!["^1.0"]);
+    }
+
+    /// Test case mixing plain string versions, inline tables and a path-only entry
+    /// in the same table.
+    #[test]
+    fn test_extract_deps_info_mixed_table() {
+        let toml_source = r#"
+[dependencies]
+serde = "1.0"
+package_test = { version = "0.4.3", path = "../package_test" }
+log = "0.4.3"
+local = { path = "../local" }
+"#;
+        let parser = TomlParser::new(toml_source).expect("Parsing should succeed");
+
+        let deps_table_node = find_dependencies_table_node(&parser, toml_source)
+            .expect("The TOML should contain a [dependencies] table");
+
+        let deps_info = parser.extract_deps_info(deps_table_node);
+        let mut deps: Vec<(&str, &str)> = deps_info
+            .values()
+            .map(|dep| (dep.name.as_str(), dep.version.as_str()))
+            .collect();
+        deps.sort();
+        assert_eq!(
+            deps,
+            vec![("log", "0.4.3"), ("package_test", "0.4.3"), ("serde", "1.0")]
         );
     }
 
@@ -970,4 +2227,1093 @@ version = "0.1.0"
             "There should be no [dependencies] table in this TOML"
         );
     }
+
+    #[test]
+    fn test_edit_node_returns_replaced_range() {
+        let toml_source = r#"
+[package]
+name = "package_test"
+version = "0.4.3"
+"#;
+        let parser = TomlParser::new(toml_source).expect("Parsing should succeed");
+        let (version_node, _) = parser.find_package_and_deps().unwrap().package.unwrap();
+
+        let (new_source, (start_byte, end_byte)) = parser.edit_node(version_node, "\"0.4.4\"");
+        assert_eq!(&toml_source[start_byte..end_byte], "\"0.4.3\"");
+        assert!(new_source.contains("version = \"0.4.4\""));
+    }
+
+    #[test]
+    fn test_source_with_edits_applies_all_edits() {
+        let toml_source = r#"
+[package]
+name = "package_test1"
+version = "0.4.3"
+
+[dependencies]
+package_test2 = { version = "0.4.3", path = "package_test2" }
+package_test3 = { version = "0.4.3", path = "package_test3" }
+"#;
+        let parser = TomlParser::new(toml_source).expect("Parsing should succeed");
+        let pkg_and_deps = parser.find_package_and_deps().unwrap();
+
+        // Collect the edits in ascending order to check they are re-sorted.
+        let (pkg_node, _) = pkg_and_deps.package.unwrap();
+        let mut edits = vec![(pkg_node.start_byte(), pkg_node.end_byte(), "\"0.5.0\"")];
+        edits.extend(
+            pkg_and_deps
+                .dependencies
+                .keys()
+                .map(|node| (node.start_byte(), node.end_byte(), "\"0.5.0\"")),
+        );
+
+        let new_source = parser.source_with_edits(edits);
+        assert!(!new_source.contains("0.4.3"));
+        assert_eq!(new_source.matches("\"0.5.0\"").count(), 3);
+        assert_eq!(new_source, toml_source.replace("0.4.3", "0.5.0"));
+    }
+
+    #[test]
+    fn test_source_with_edits_no_edits() {
+        let toml_source = "[package]\nname = \"a\"\n";
+        let parser = TomlParser::new(toml_source).expect("Parsing should succeed");
+        assert_eq!(parser.source_with_edits(Vec::new()), toml_source);
+    }
+
+    fn deps_info(name: &str, version: &str) -> DepsInfo {
+        DepsInfo {
+            name: name.to_string(),
+            version: version.to_string(),
+            name_pair: format!("{} = {{ version = \"{}\" }}", name, version),
+            version_pair: format!("version = \"{}\"", version),
+            kind: DepKind::Normal,
+        }
+    }
+
+    fn pkg_info(name: &str, version: &str) -> PkgInfo {
+        PkgInfo {
+            name: name.to_string(),
+            version: version.to_string(),
+            name_pair: format!("name = \"{}\"", name),
+            version_pair: format!("version = \"{}\"", version),
+        }
+    }
+
+    #[test]
+    fn test_all_versions_no_package() {
+        let pkg_and_deps = PackageAndDeps {
+            package: None,
+            dependencies: vec![deps_info("dep_a", "1.0.0")],
+            dev_dependencies: Vec::new(),
+            build_dependencies: Vec::new(),
+            target_dependencies: Vec::new(),
+            unversioned_dependencies: Vec::new(),
+        };
+        assert_eq!(pkg_and_deps.all_versions(), vec!["1.0.0"]);
+        assert!(pkg_and_deps.has_version("1.0.0"));
+        assert!(!pkg_and_deps.has_version("0.1.0"));
+    }
+
+    #[test]
+    fn test_all_versions_package_without_deps() {
+        let pkg_and_deps = PackageAndDeps {
+            package: Some(pkg_info("pkg", "0.1.0")),
+            dependencies: Vec::new(),
+            dev_dependencies: Vec::new(),
+            build_dependencies: Vec::new(),
+            target_dependencies: Vec::new(),
+            unversioned_dependencies: Vec::new(),
+        };
+        assert_eq!(pkg_and_deps.all_versions(), vec!["0.1.0"]);
+        assert!(pkg_and_deps.has_version("0.1.0"));
+    }
+
+    #[test]
+    fn test_all_versions_mixed_dep_versions() {
+        let pkg_and_deps = PackageAndDeps {
+            package: Some(pkg_info("pkg", "0.1.0")),
+            dependencies: vec![deps_info("dep_a", "1.0.0"), deps_info("dep_b", "2.0.0")],
+            dev_dependencies: Vec::new(),
+            build_dependencies: Vec::new(),
+            target_dependencies: Vec::new(),
+            unversioned_dependencies: Vec::new(),
+        };
+        assert_eq!(pkg_and_deps.all_versions(), vec!["0.1.0", "1.0.0", "2.0.0"]);
+        assert!(pkg_and_deps.has_version("2.0.0"));
+        assert!(!pkg_and_deps.has_version("2.0"));
+    }
+
+    #[test]
+    fn test_find_bin_targets_none() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n";
+        let parser = TomlParser::new(source).unwrap();
+        assert!(parser.find_bin_targets().is_empty());
+    }
+
+    #[test]
+    fn test_find_bin_targets_single() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[[bin]]
+name = "tool"
+path = "src/bin/tool.rs"
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        assert_eq!(
+            parser.find_bin_targets(),
+            vec![BinTarget {
+                name: "tool".to_string(),
+                path: Some("src/bin/tool.rs".to_string()),
+                required_features: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_bin_targets_multiple() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[[bin]]
+name = "first"
+
+[dependencies]
+dep_a = { version = "1.0.0" }
+
+[[bin]]
+name = "second"
+path = "src/second.rs"
+required-features = ["cli", "color"]
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        assert_eq!(
+            parser.find_bin_targets(),
+            vec![
+                BinTarget {
+                    name: "first".to_string(),
+                    path: None,
+                    required_features: Vec::new(),
+                },
+                BinTarget {
+                    name: "second".to_string(),
+                    path: Some("src/second.rs".to_string()),
+                    required_features: vec!["cli".to_string(), "color".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_toml_str_finds_package_and_deps() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = { version = \"1.0\" }\n";
+
+        let pkg_and_deps = PackageAndDeps::from_toml_str(source).unwrap();
+
+        assert_eq!(
+            pkg_and_deps,
+            Some(PackageAndDepBuilder::new().package("pkg", "0.1.0").dep("serde", "1.0").build())
+        );
+    }
+
+    #[test]
+    fn test_from_toml_str_reads_dev_dependencies() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = { version = \"1.0\" }\n\n[dev-dependencies]\nmy-helper = { version = \"0.3.0\" }\n";
+
+        let pkg_and_deps = PackageAndDeps::from_toml_str(source).unwrap().unwrap();
+
+        assert_eq!(pkg_and_deps.dependencies.len(), 1);
+        assert_eq!(pkg_and_deps.dev_dependencies.len(), 1);
+        assert_eq!(pkg_and_deps.dev_dependencies[0].name, "my-helper");
+        assert_eq!(pkg_and_deps.dev_dependencies[0].version, "0.3.0");
+        assert_eq!(pkg_and_deps.count(), 3);
+        assert!(pkg_and_deps.has_version("0.3.0"));
+    }
+
+    #[test]
+    fn test_from_toml_str_tags_dependency_kinds() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = { version = \"1.0\" }\n\n[dev-dependencies]\nmy-helper = { version = \"0.3.0\" }\n\n[build-dependencies]\ncc = { version = \"1.2\" }\n";
+
+        let pkg_and_deps = PackageAndDeps::from_toml_str(source).unwrap().unwrap();
+
+        let kinds: Vec<(&str, DepKind)> = pkg_and_deps
+            .all_dependencies()
+            .map(|dep| (dep.name.as_str(), dep.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [("serde", DepKind::Normal), ("my-helper", DepKind::Dev), ("cc", DepKind::Build)]
+        );
+        assert_eq!(pkg_and_deps.build_dependencies.len(), 1);
+        assert_eq!(pkg_and_deps.count(), 4);
+        assert_eq!(DepKind::Build.to_string(), "build-dependencies");
+    }
+
+    #[test]
+    fn test_from_toml_str_reads_target_dependencies() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[target.'cfg(unix)'.dependencies]
+nix = { version = "0.29" }
+
+[target."cfg(windows)".build-dependencies]
+winres = { version = "0.1" }
+
+[target.x86_64-pc-windows-gnu.dev-dependencies]
+helper = { version = "0.3.0" }
+
+[target.'cfg(unix)'.features]
+extra = { version = "9.9" }
+"#;
+
+        let pkg_and_deps = PackageAndDeps::from_toml_str(source).unwrap().unwrap();
+
+        let mut targets: Vec<(&str, &str, DepKind)> = pkg_and_deps
+            .target_dependencies
+            .iter()
+            .map(|(predicate, dep)| (predicate.as_str(), dep.name.as_str(), dep.kind))
+            .collect();
+        targets.sort_by_key(|(predicate, _, _)| *predicate);
+        assert_eq!(
+            targets,
+            [
+                ("cfg(unix)", "nix", DepKind::Normal),
+                ("cfg(windows)", "winres", DepKind::Build),
+                ("x86_64-pc-windows-gnu", "helper", DepKind::Dev),
+            ]
+        );
+        assert!(pkg_and_deps.dependencies.is_empty());
+        assert_eq!(pkg_and_deps.count(), 4);
+        assert!(pkg_and_deps.has_version("0.29"));
+    }
+
+    #[test]
+    fn test_from_toml_str_keeps_unversioned_dependencies() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[dependencies]
+serde = "1.0"
+local = { path = "../local" }
+remote = { git = "https://github.com/owner/remote", rev = "abc123" }
+both = { version = "0.2.0", path = "../both" }
+shared = { workspace = true }
+
+[dev-dependencies]
+helper = { git = "https://github.com/owner/helper" }
+"#;
+
+        let pkg_and_deps = PackageAndDeps::from_toml_str(source).unwrap().unwrap();
+
+        assert_eq!(
+            pkg_and_deps.unversioned_dependencies,
+            vec![
+                UnversionedDep {
+                    name: "local".to_string(),
+                    name_pair: r#"local = { path = "../local" }"#.to_string(),
+                    source: DepSource::Path("../local".to_string()),
+                    kind: DepKind::Normal,
+                },
+                UnversionedDep {
+                    name: "remote".to_string(),
+                    name_pair: r#"remote = { git = "https://github.com/owner/remote", rev = "abc123" }"#.to_string(),
+                    source: DepSource::Git {
+                        url: "https://github.com/owner/remote".to_string(),
+                        reference: Some("abc123".to_string()),
+                    },
+                    kind: DepKind::Normal,
+                },
+                UnversionedDep {
+                    name: "helper".to_string(),
+                    name_pair: r#"helper = { git = "https://github.com/owner/helper" }"#.to_string(),
+                    source: DepSource::Git {
+                        url: "https://github.com/owner/helper".to_string(),
+                        reference: None,
+                    },
+                    kind: DepKind::Dev,
+                },
+            ]
+        );
+        // Only the versioned dependencies count.
+        assert_eq!(pkg_and_deps.count(), 3);
+    }
+
+    #[test]
+    fn test_from_toml_str_empty_source_fails() {
+        assert!(matches!(
+            PackageAndDeps::from_toml_str("  \n"),
+            Err(TomlParserError::EmptySource)
+        ));
+    }
+
+    #[test]
+    fn test_from_toml_str_without_package() {
+        let source = "[dependencies]\nserde = { version = \"1.0\" }\n";
+        assert_eq!(PackageAndDeps::from_toml_str(source).unwrap(), None);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_debug_tree_lists_nodes() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = { version = \"1.0\", features = [\"derive\", \"rc\", \"alloc\"] }\n";
+        let parser = TomlParser::new(source).unwrap();
+
+        let tree = parser.debug_tree();
+        let lines: Vec<&str> = tree.lines().collect();
+
+        assert!(lines[0].starts_with(&format!("document 0..{}", source.len())));
+        assert!(tree.contains("package"));
+        assert!(tree.contains("dependencies"));
+        assert!(tree.contains("\n  table 0..42"));
+        assert!(tree.contains("\n    pair 10..22 \"name = \\\"pkg\\\"\""));
+        assert!(tree.contains("\n      bare_key 10..14 \"name\""));
+        // The serde pair is longer than 40 characters.
+        assert!(tree.contains(r#""serde = { version = \"1.0\", features = [\""..."#));
+    }
+
+    #[test]
+    fn test_find_examples_none() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n\n[[bin]]\nname = \"tool\"\n";
+        let parser = TomlParser::new(source).unwrap();
+        assert!(parser.find_examples().is_empty());
+    }
+
+    #[test]
+    fn test_find_examples_single() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[[example]]
+name = "demo"
+path = "examples/demo/main.rs"
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        assert_eq!(
+            parser.find_examples(),
+            vec![ExampleTarget {
+                name: "demo".to_string(),
+                path: Some("examples/demo/main.rs".to_string()),
+                required_features: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_examples_multiple() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[[example]]
+name = "first"
+
+[[bin]]
+name = "tool"
+
+[[example]]
+name = "second"
+required-features = ["serde"]
+
+[dev-dependencies]
+dep_a = { version = "1.0.0" }
+
+[[example]]
+name = "third"
+path = "examples/third.rs"
+required-features = ["cli", "color"]
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        assert_eq!(
+            parser.find_examples(),
+            vec![
+                ExampleTarget {
+                    name: "first".to_string(),
+                    path: None,
+                    required_features: Vec::new(),
+                },
+                ExampleTarget {
+                    name: "second".to_string(),
+                    path: None,
+                    required_features: vec!["serde".to_string()],
+                },
+                ExampleTarget {
+                    name: "third".to_string(),
+                    path: Some("examples/third.rs".to_string()),
+                    required_features: vec!["cli".to_string(), "color".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_lib_target() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[lib]
+path = "src/pkg.rs"
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        assert_eq!(
+            parser.find_lib_target(),
+            Some(LibTarget {
+                name: None,
+                path: Some("src/pkg.rs".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_find_lib_target_missing() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n\n[[bin]]\nname = \"tool\"\n";
+        let parser = TomlParser::new(source).unwrap();
+        assert_eq!(parser.find_lib_target(), None);
+    }
+
+    #[test]
+    fn test_find_dev_and_build_deps() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[dependencies]
+dep_a = { version = "1.0.0" }
+
+[dev-dependencies]
+dev_a = { version = "2.0.0" }
+
+[build-dependencies]
+build_a = { version = "3.0.0" }
+"#;
+        let parser = TomlParser::new(source).unwrap();
+
+        let dev_names: Vec<String> = parser
+            .find_dev_deps()
+            .unwrap()
+            .into_values()
+            .map(|dep| dep.name)
+            .collect();
+        assert_eq!(dev_names, vec!["dev_a"]);
+
+        let build_names: Vec<String> = parser
+            .find_build_deps()
+            .unwrap()
+            .into_values()
+            .map(|dep| dep.name)
+            .collect();
+        assert_eq!(build_names, vec!["build_a"]);
+    }
+
+    #[test]
+    fn test_find_dev_deps_missing_table() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n";
+        let parser = TomlParser::new(source).unwrap();
+        assert!(parser.find_dev_deps().is_none());
+        assert!(parser.find_build_deps().is_none());
+    }
+
+    #[test]
+    fn test_to_display_string_is_plain_text() {
+        let built = PackageAndDepBuilder::new()
+            .package("cratup", "0.4.1")
+            .dep("serde", "1.0")
+            .build();
+
+        let pkg = built.package.unwrap().to_display_string();
+        let dep = built.dependencies[0].to_display_string();
+
+        assert_eq!(pkg, "cratup v0.4.1");
+        assert_eq!(dep, "serde = \"1.0\"");
+        assert!(!pkg.contains('\x1b') && !dep.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_builder_matches_parsed_manifest() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[dependencies]
+dep_a = { version = "1.0.0" }
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        let parsed: PackageAndDeps = parser.find_package_and_deps().unwrap().into();
+
+        let built = PackageAndDepBuilder::new()
+            .package("pkg", "0.1.0")
+            .dep("dep_a", "1.0.0")
+            .build();
+        assert_eq!(built, parsed);
+    }
+
+    #[test]
+    fn test_builder_collects_all_dependency_kinds() {
+        let built = PackageAndDepBuilder::new()
+            .dep("dep_a", "1.0.0")
+            .dev_dep("dev_a", "2.0.0")
+            .build_dep("build_a", "3.0.0")
+            .build();
+        assert!(built.package.is_none());
+        assert_eq!(built.all_versions(), vec!["1.0.0", "2.0.0", "3.0.0"]);
+        assert_eq!(built.dependencies[0].name, "dep_a");
+        assert_eq!(built.dev_dependencies[0].name, "dev_a");
+        assert_eq!(built.dev_dependencies[0].kind, DepKind::Dev);
+        assert_eq!(built.build_dependencies[0].name, "build_a");
+        assert_eq!(built.build_dependencies[0].kind, DepKind::Build);
+    }
+
+    /// Parsing and building work the same without the optional `serde` feature.
+    #[cfg(not(feature = "serde"))]
+    #[test]
+    fn test_builds_without_serde_feature() {
+        let parser = TomlParser::new("[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n").unwrap();
+        let parsed: PackageAndDeps = parser.find_package_and_deps().unwrap().into();
+        assert_eq!(parsed, PackageAndDepBuilder::new().package("pkg", "0.1.0").build());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let built = PackageAndDepBuilder::new()
+            .package("pkg", "0.1.0")
+            .dep("dep_a", "1.0.0")
+            .build();
+
+        let serialized = toml::to_string(&built).unwrap();
+        let deserialized: PackageAndDeps = toml::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, built);
+        assert_eq!(deserialized.dependencies[0].name_pair, built.dependencies[0].name_pair);
+    }
+
+    #[test]
+    fn test_new_from_path_matches_new() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n\n[dependencies]\ndep_a = { version = \"1.0.0\" }\n";
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("Cargo.toml");
+        std::fs::write(&path, source).unwrap();
+
+        let (from_path, content) = TomlParser::new_from_path(&path).unwrap();
+        let from_str = TomlParser::new(source).unwrap();
+
+        assert_eq!(content, source);
+        assert_eq!(
+            from_path.tree.root_node().to_sexp(),
+            from_str.tree.root_node().to_sexp()
+        );
+        let from_path_pkg: PackageAndDeps = from_path.find_package_and_deps().unwrap().into();
+        let from_str_pkg: PackageAndDeps = from_str.find_package_and_deps().unwrap().into();
+        assert_eq!(from_path_pkg, from_str_pkg);
+    }
+
+    #[test]
+    fn test_new_from_cow_matches_new() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n";
+        let borrowed = TomlParser::new_from_cow(Cow::Borrowed(source)).unwrap();
+        let owned = TomlParser::new_from_cow(Cow::Owned(source.to_string())).unwrap();
+        let from_str = TomlParser::new(source).unwrap();
+
+        assert_eq!(borrowed.source(), source);
+        assert_eq!(owned.source(), source);
+        assert_eq!(
+            owned.tree.root_node().to_sexp(),
+            from_str.tree.root_node().to_sexp()
+        );
+    }
+
+    #[test]
+    fn test_new_unsupported_language() {
+        let result = TomlParser::from_cow_with_language(Cow::Borrowed("[package]\n"), |_| {
+            Err("incompatible language version")
+        });
+        assert!(matches!(result, Err(TomlParserError::UnsupportedLanguage)));
+    }
+
+    #[test]
+    fn test_new_without_language_fails_to_parse() {
+        let result =
+            TomlParser::from_cow_with_language(Cow::Borrowed("[package]\n"), |_| Ok::<(), ()>(()));
+        assert!(matches!(result, Err(TomlParserError::ParseFailed { error_nodes: 0 })));
+    }
+
+    #[test]
+    fn test_new_empty_source() {
+        assert!(matches!(TomlParser::new(""), Err(TomlParserError::EmptySource)));
+        assert!(matches!(TomlParser::new(" \n\t\n"), Err(TomlParserError::EmptySource)));
+    }
+
+    #[test]
+    fn test_new_accepts_and_counts_error_nodes() {
+        let parser = TomlParser::new("[package]\nname = \"pkg\"\n= \"0.1.0\"\n").unwrap();
+        assert!(parser.has_error_nodes());
+        assert!(parser.error_node_count() > 0);
+    }
+
+    #[test]
+    fn test_new_strict_reports_error_nodes() {
+        let source = "[package]\nname = \"pkg\"\n= \"0.1.0\"\n";
+        let error_nodes = TomlParser::new(source).unwrap().error_node_count();
+
+        let result = TomlParser::new_strict(source);
+        assert!(
+            matches!(result, Err(TomlParserError::ParseFailed { error_nodes: n }) if n == error_nodes && n > 0)
+        );
+        assert!(TomlParser::new_strict("[package]\nname = \"pkg\"\n").is_ok());
+    }
+
+    #[test]
+    fn test_truncated_manifest_has_error_nodes() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = { version = \"1.0\", features = [\"der";
+        let parser = TomlParser::new(source).unwrap();
+        assert!(parser.has_error_nodes());
+        assert!(parser.error_node_count() > 0);
+
+        let parser = TomlParser::new("[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n").unwrap();
+        assert!(!parser.has_error_nodes());
+        assert_eq!(parser.error_node_count(), 0);
+    }
+
+    const DOTTED_KEY_MANIFEST: &str = "package.name = \"foo\"\npackage.version = \"0.1.0\"\n\n[dependencies]\nserde = { version = \"1.0\" }\n";
+
+    #[test]
+    fn test_toml05_ignores_dotted_key_package() {
+        let parser = TomlParser::new_with_grammar(DOTTED_KEY_MANIFEST, TomlGrammar::Toml05).unwrap();
+
+        // tree-sitter-toml parses dotted keys, the package is just not looked for there.
+        assert!(!parser.has_error_nodes());
+        assert!(parser.find_package_and_deps().is_none());
+        assert_eq!(TomlParser::new(DOTTED_KEY_MANIFEST).unwrap().grammar, TomlGrammar::Toml05);
+    }
+
+    #[test]
+    fn test_toml10_reads_dotted_key_package() {
+        let parser = TomlParser::new_with_grammar(DOTTED_KEY_MANIFEST, TomlGrammar::Toml10).unwrap();
+
+        let pkg_and_deps = parser.find_package_and_deps().expect("package should be found");
+        let (version_node, pkg) = pkg_and_deps.package.unwrap();
+        assert_eq!(pkg.name, "foo");
+        assert_eq!(pkg.version, "0.1.0");
+        assert_eq!(pkg.name_pair, "package.name = \"foo\"");
+        assert_eq!(pkg.version_pair, "package.version = \"0.1.0\"");
+        assert_eq!(version_node.utf8_text(parser.source().as_bytes()).unwrap(), "\"0.1.0\"");
+        assert_eq!(pkg_and_deps.dependencies.len(), 1);
+
+        let pkg = parser.find_package().unwrap();
+        assert_eq!((pkg.name.as_str(), pkg.version.as_str()), ("foo", "0.1.0"));
+        assert!(TomlParser::new(DOTTED_KEY_MANIFEST).unwrap().find_package().is_none());
+
+        // A [package] table is still read the same way.
+        let table_source = "[package]\nname = \"bar\"\nversion = \"0.2.0\"\n";
+        let parser = TomlParser::new_with_grammar(table_source, TomlGrammar::Toml10).unwrap();
+        let (_, pkg) = parser.find_package_and_deps().unwrap().package.unwrap();
+        assert_eq!(pkg.name, "bar");
+    }
+
+    #[test]
+    fn test_new_from_path_binary_data() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("Cargo.toml");
+        std::fs::write(&path, [0xff, 0xfe, 0x00, 0x9f]).unwrap();
+
+        let result = TomlParser::new_from_path(&path);
+        assert!(matches!(result, Err(TomlParserError::InvalidUtf8)));
+    }
+
+    #[test]
+    fn test_new_from_path_missing_file() {
+        let result = TomlParser::new_from_path(Path::new("/nonexistent/cratup/Cargo.toml"));
+        assert!(matches!(result, Err(TomlParserError::IoError(_))));
+    }
+
+    #[test]
+    fn test_find_workspace_members_expands_globs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for dir in ["crates/core", "crates/cli", "crates/old", "tools/gen"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("crates/README.md"), "not a member").unwrap();
+
+        let source = r#"
+[workspace]
+members = ["crates/*", "tools/gen"]
+exclude = ["crates/old"]
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        let members = parser.find_workspace_members(root).unwrap();
+
+        assert_eq!(
+            members,
+            vec![
+                root.join("crates/cli"),
+                root.join("crates/core"),
+                root.join("tools/gen"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_workspace_members_escapes_the_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("ws [1]*?");
+        for dir in ["ws [1]*?/crates/core", "ws 1/crates/other"] {
+            std::fs::create_dir_all(tmp.path().join(dir)).unwrap();
+        }
+
+        let parser = TomlParser::new("[workspace]\nmembers = [\"crates/*\"]\n").unwrap();
+        let members = parser.find_workspace_members(&root).unwrap();
+
+        assert_eq!(members, vec![root.join("crates/core")]);
+    }
+
+    #[test]
+    fn test_find_workspace_members_without_workspace() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n";
+        let parser = TomlParser::new(source).unwrap();
+        assert!(parser.find_workspace_members(Path::new(".")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reformat_version_pair_whitespace_variants() {
+        for pair in [
+            r#"version="0.4.3""#,
+            r#"version = "0.4.3""#,
+            r#"version  =  "0.4.3""#,
+            "\tversion =\t\"0.4.3\" ",
+        ] {
+            assert_eq!(
+                TomlParser::reformat_version_pair(pair, "0.4.3"),
+                r#"version = "0.4.3""#,
+                "unexpected normalisation of {:?}",
+                pair
+            );
+        }
+    }
+
+    #[test]
+    fn test_extracted_version_pairs_are_canonical() {
+        let source = r#"
+[package]
+name = "pkg"
+version="0.4.3"
+
+[dependencies]
+dep_a = { version  =  "0.4.3", path = "../dep_a" }
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        let parsed: PackageAndDeps = parser.find_package_and_deps().unwrap().into();
+
+        assert_eq!(parsed.package.unwrap().version_pair, r#"version = "0.4.3""#);
+        assert_eq!(parsed.dependencies[0].version_pair, r#"version = "0.4.3""#);
+    }
+
+    #[test]
+    fn test_literal_string_versions_are_unquoted() {
+        let source = r#"
+[package]
+name = 'pkg'
+version = '0.4.3'
+
+[dependencies]
+dep_a = '1.0'
+dep_b={ version='2.0' }
+dep_c  =  "3.0"
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        let parsed: PackageAndDeps = parser.find_package_and_deps().unwrap().into();
+
+        let package = parsed.package.unwrap();
+        assert_eq!((package.name.as_str(), package.version.as_str()), ("pkg", "0.4.3"));
+        assert_eq!(package.version_pair, r#"version = "0.4.3""#);
+        let mut deps: Vec<(&str, &str, &str)> = parsed
+            .dependencies
+            .iter()
+            .map(|dep| (dep.name.as_str(), dep.version.as_str(), dep.version_pair.as_str()))
+            .collect();
+        deps.sort();
+        assert_eq!(
+            deps,
+            vec![
+                ("dep_a", "1.0", r#"dep_a = "1.0""#),
+                ("dep_b", "2.0", r#"version = "2.0""#),
+                ("dep_c", "3.0", r#"dep_c = "3.0""#),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_dedups_overlapping_dependencies() {
+        let mut merged = PackageAndDepBuilder::new()
+            .package("pkg", "0.1.0")
+            .dep("dep_a", "1.0.0")
+            .dep("dep_b", "2.0.0")
+            .build();
+        let other = PackageAndDepBuilder::new()
+            .dep("dep_b", "2.0.0")
+            .dep("dep_b", "2.1.0")
+            .dev_dep("dep_a", "1.0.0")
+            .dep("dep_c", "3.0.0")
+            .build();
+
+        merged.merge(other);
+
+        let deps: Vec<(&str, &str)> = merged
+            .dependencies
+            .iter()
+            .map(|dep| (dep.name.as_str(), dep.version.as_str()))
+            .collect();
+        assert_eq!(
+            deps,
+            vec![
+                ("dep_a", "1.0.0"),
+                ("dep_b", "2.0.0"),
+                ("dep_b", "2.1.0"),
+                ("dep_c", "3.0.0"),
+            ]
+        );
+        assert_eq!(merged.package.unwrap().name, "pkg");
+    }
+
+    #[test]
+    fn test_deps_info_identity_ignores_pairs() {
+        let spaced = DepsInfo {
+            name: "dep_a".to_string(),
+            version: "1.0.0".to_string(),
+            name_pair: "dep_a = { version  =  \"1.0.0\" }".to_string(),
+            version_pair: "version  =  \"1.0.0\"".to_string(),
+            kind: DepKind::Normal,
+        };
+        let compact = DepsInfo {
+            name_pair: "dep_a={version=\"1.0.0\"}".to_string(),
+            version_pair: "version=\"1.0.0\"".to_string(),
+            ..spaced.clone()
+        };
+
+        assert_eq!(spaced, compact);
+        let set: HashSet<DepsInfo> = [spaced, compact].into_iter().collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_find_package_metadata_arrays() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+authors = ["Jane Doe <jane@example.com>", "John Roe"]
+categories = ["command-line-utilities", "development-tools"]
+keywords = ["cargo", "version"]
+
+[dependencies]
+dep_a = { version = "1.0.0", features = ["std"] }
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        assert_eq!(
+            parser.find_authors(),
+            vec!["Jane Doe <jane@example.com>", "John Roe"]
+        );
+        assert_eq!(
+            parser.find_categories(),
+            vec!["command-line-utilities", "development-tools"]
+        );
+        assert_eq!(parser.find_keywords(), vec!["cargo", "version"]);
+    }
+
+    const DEP_VERSION_SOURCE: &str = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[dependencies]
+serde = "1.0"
+tokio = { version = "1.38", features = ["full"] }
+local = { path = "../local" }
+
+[dev-dependencies]
+tempfile = "3"
+local = { version = "0.2.0", path = "../local" }
+"#;
+
+    #[test]
+    fn test_find_dep_version_in_dependencies() {
+        let parser = TomlParser::new(DEP_VERSION_SOURCE).unwrap();
+        assert_eq!(parser.find_dep_version("serde"), Some("1.0".to_string()));
+    }
+
+    #[test]
+    fn test_find_dep_version_in_dev_dependencies() {
+        let parser = TomlParser::new(DEP_VERSION_SOURCE).unwrap();
+        assert_eq!(parser.find_dep_version("tempfile"), Some("3".to_string()));
+        // The path-only entry of [dependencies] has no version to report.
+        assert_eq!(parser.find_dep_version("local"), Some("0.2.0".to_string()));
+    }
+
+    #[test]
+    fn test_find_dep_version_not_found() {
+        let parser = TomlParser::new(DEP_VERSION_SOURCE).unwrap();
+        assert_eq!(parser.find_dep_version("rand"), None);
+        assert_eq!(parser.find_dep_version("pkg"), None);
+    }
+
+    #[test]
+    fn test_find_dep_version_inline_table() {
+        let parser = TomlParser::new(DEP_VERSION_SOURCE).unwrap();
+        assert_eq!(parser.find_dep_version("tokio"), Some("1.38".to_string()));
+    }
+
+    #[test]
+    fn test_find_dep_section_names_without_dependencies() {
+        let parser = TomlParser::new("[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n").unwrap();
+        assert!(parser.find_dep_section_names().is_empty());
+
+        let source = "[package]\nname = \"pkg\"\n\n[dependencies]\nserde = \"1.0\"\n";
+        let parser = TomlParser::new(source).unwrap();
+        assert_eq!(parser.find_dep_section_names(), vec!["dependencies"]);
+    }
+
+    #[test]
+    fn test_find_dep_section_names_all_sections() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[dependencies]
+serde = "1.0"
+
+[dev-dependencies]
+tempfile = "3"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[features]
+default = []
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        assert_eq!(
+            parser.find_dep_section_names(),
+            vec![
+                "dependencies",
+                "dev-dependencies",
+                "target.'cfg(unix)'.dependencies"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_workspace() {
+        let source = r#"
+[workspace]
+members = ["crates/*"]
+
+[workspace.package]
+version = "0.4.1"
+"#;
+        let parser = TomlParser::new(source).unwrap();
+        assert!(parser.find_workspace().is_some());
+        let version_node = parser.find_workspace_package_version().unwrap();
+        assert_eq!(version_node.utf8_text(source.as_bytes()).unwrap(), "\"0.4.1\"");
+
+        let parser = TomlParser::new("[package]\nname = \"pkg\"\nversion.workspace = true\n").unwrap();
+        assert!(parser.find_workspace().is_none());
+        assert!(parser.find_workspace_package_version().is_none());
+    }
+
+    #[test]
+    fn test_find_profiles_release_only() {
+        let source = "[package]\nname = \"pkg\"\n\n[profile.release]\nopt-level = 3\nlto = \"thin\"\ndebug = false\n";
+        let profiles = TomlParser::new(source).unwrap().find_profiles();
+
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(
+            profiles["release"],
+            ProfileInfo {
+                opt_level: Some("3".to_string()),
+                lto: Some("thin".to_string()),
+                debug: Some(false),
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_profiles_dev_only() {
+        let source = "[profile.dev]\nopt-level = \"s\"\ndebug = 2\n";
+        let profiles = TomlParser::new(source).unwrap().find_profiles();
+
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(
+            profiles["dev"],
+            ProfileInfo {
+                opt_level: Some("s".to_string()),
+                lto: None,
+                debug: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_profiles_dev_and_release() {
+        let source = r#"
+[package]
+name = "pkg"
+version = "0.1.0"
+
+[profile.dev]
+debug = "none"
+
+[profile.release]
+lto = true
+
+[profile.release.package.foo]
+opt-level = 1
+"#;
+        let profiles = TomlParser::new(source).unwrap().find_profiles();
+
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles["dev"].debug, Some(false));
+        assert_eq!(profiles["dev"].opt_level, None);
+        assert_eq!(profiles["release"].lto.as_deref(), Some("true"));
+        assert_eq!(profiles["release"].opt_level, None);
+    }
+
+    #[test]
+    fn test_find_profiles_none() {
+        let source = "[package]\nname = \"pkg\"\n\n[dependencies]\nserde = \"1.0\"\n";
+        assert!(TomlParser::new(source).unwrap().find_profiles().is_empty());
+    }
+
+    #[test]
+    fn test_find_package_metadata_arrays_missing() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\n";
+        let parser = TomlParser::new(source).unwrap();
+        assert!(parser.find_authors().is_empty());
+        assert!(parser.find_categories().is_empty());
+        assert!(parser.find_keywords().is_empty());
+
+        let parser = TomlParser::new("[workspace]\nmembers = []\n").unwrap();
+        assert!(parser.find_authors().is_empty());
+    }
+
+    #[test]
+    fn test_find_authors_empty_array() {
+        let source = "[package]\nname = \"pkg\"\nversion = \"0.1.0\"\nauthors = []\n";
+        let parser = TomlParser::new(source).unwrap();
+        assert!(parser.find_authors().is_empty());
+    }
 }
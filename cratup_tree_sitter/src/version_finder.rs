@@ -1,54 +1,295 @@
-// use semver::Version;
 use log::debug;
+use semver::{Version, VersionReq};
+use std::path::{Path, PathBuf};
 
-// use thiserror::Error;
+use thiserror::Error;
+use tree_sitter::Node;
 
-use crate::tree_traversal::{PackageAndDeps, PackageAndDepsNodes, TomlParser};
+use crate::tree_traversal::{
+    DepsInfo, PackageAndDeps, PackageAndDepsNodes, TomlGrammar, TomlParser, TomlParserError,
+};
+
+/// Errors produced while preparing or applying a version update.
+#[derive(Debug, Error)]
+pub enum VersionUpdateError {
+    #[error("failed to parse: {0}")]
+    ParseError(String),
+    #[error("the new version ({proposed}) is lower than the current version ({current})")]
+    VersionDowngrade { current: String, proposed: String },
+    #[error("the new version is the same as the current version ({0})")]
+    VersionIdentical(String),
+    #[error("no package or dependency matches the requested version")]
+    NoMatchFound,
+    #[error("failed to access {path:?}: {source}")]
+    IoError { path: PathBuf, source: std::io::Error },
+}
+
+impl VersionUpdateError {
+    /// Returns a closure wrapping an I/O error on `path`, for use with `map_err`.
+    pub fn io(path: &Path) -> impl FnOnce(std::io::Error) -> Self + '_ {
+        move |source| VersionUpdateError::IoError { path: path.to_path_buf(), source }
+    }
+}
+
+impl From<TomlParserError> for VersionUpdateError {
+    fn from(err: TomlParserError) -> Self {
+        VersionUpdateError::ParseError(err.to_string())
+    }
+}
 
 #[derive(Debug)]
 pub struct VersionUpdate<'a> {
     pub package_name: Option<&'a str>,
     pub current_version: &'a str,
     pub new_version: &'a str,
+    /// Keep the `+meta` suffix of the old version when writing the new one.
+    pub build_metadata_preserved: bool,
+    /// When set, versions satisfying this requirement are replaced instead of those
+    /// equal to `current_version`.
+    pub current_version_req: Option<VersionReq>,
+    /// TOML version the package of each Cargo.toml is read with.
+    pub grammar: TomlGrammar,
+}
+
+// with_build_metadata_preserved
+impl<'a> VersionUpdate<'a> {
+    /// Parses `source`, reading its package with `grammar`.
+    fn parser<'s>(&self, source: &'s str) -> Result<TomlParser<'s>, TomlParserError> {
+        TomlParser::new_with_grammar(source, self.grammar)
+    }
+
+    /// When enabled, build metadata such as `+build.1` on the version being replaced is
+    /// appended to `new_version`, unless `new_version` carries build metadata of its own.
+    pub fn with_build_metadata_preserved(mut self, preserved: bool) -> Self {
+        self.build_metadata_preserved = preserved;
+        self
+    }
+}
+
+// with_current_version_req
+impl VersionUpdate<'_> {
+    /// Matches versions against `current_version_req` instead of comparing them to
+    /// `current_version`, so `"^0.4"` replaces both `0.4.0` and `0.4.3`.
+    pub fn with_current_version_req(mut self, current_version_req: Option<VersionReq>) -> Self {
+        self.current_version_req = current_version_req;
+        self
+    }
+
+    /// Returns true if `version` is one this update replaces. With a requirement,
+    /// versions that do not parse as semver never match.
+    pub fn version_matches(&self, version: &str) -> bool {
+        match &self.current_version_req {
+            Some(version_req) => Version::parse(version).is_ok_and(|v| version_req.matches(&v)),
+            None => version == self.current_version,
+        }
+    }
+}
+
+// strip_prerelease_for_stable
+impl VersionUpdate<'_> {
+    /// Returns the stable release of `current_version`, i.e. its version core without
+    /// pre-release or build metadata: `"1.0.0-rc.2"` becomes `"1.0.0"`.
+    /// A version that does not parse as semver is returned unchanged.
+    pub fn strip_prerelease_for_stable(&self) -> String {
+        match Version::parse(self.current_version) {
+            Ok(version) => Version::new(version.major, version.minor, version.patch).to_string(),
+            Err(e) => {
+                debug!("Could not parse '{}' as semver: {}", self.current_version, e);
+                self.current_version.to_string()
+            }
+        }
+    }
+}
+
+// new_version_quoted
+impl<'a> VersionUpdate<'a> {
+    /// Returns the replacement for `old_version`, wrapped in double quotes.
+    fn new_version_quoted(&self, old_version: &str) -> String {
+        if self.build_metadata_preserved && !self.new_version.contains('+') {
+            match Version::parse(old_version) {
+                Ok(old) if !old.build.is_empty() => {
+                    debug!("Preserving build metadata '+{}' of {}", old.build, old_version);
+                    return format!("\"{}+{}\"", self.new_version, old.build);
+                }
+                Ok(_) => {}
+                Err(e) => debug!("Could not parse '{}' for build metadata: {}", old_version, e),
+            }
+        }
+        format!("\"{}\"", self.new_version)
+    }
+}
+
+/// Reported by `update_all_with_callback` for every version that gets replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateProgress {
+    /// Number of the replacement, starting at 1.
+    pub iteration: usize,
+    /// Name of the package or dependency whose version is replaced.
+    pub name_updated: String,
+    /// The version that is replaced.
+    pub version_old: String,
+}
+
+/// Whether a `VersionChangePosition` is the `[package]` version or a dependency version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionKind {
+    Package,
+    Dependency,
+}
+
+/// A version string, quotes included, that the update replaces, as byte offsets into the
+/// original source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionChangePosition {
+    pub start_byte: usize,
+    pub end_byte: usize,
+    /// Name of the package or dependency.
+    pub entity_name: String,
+    pub kind: PositionKind,
 }
 
 //update_all_pkg_and_deps
 impl<'a> VersionUpdate<'a> {
+    /// Updates the package and every matching dependency in a single parse, collecting
+    /// all replacements first and applying them through `TomlParser::source_with_edits`.
     pub fn update_all_pkg_and_deps(&self, source: &str) -> String {
+        self.update_all_with_callback(source, |_| {})
+    }
+}
+
+//update_all_with_callback
+impl<'a> VersionUpdate<'a> {
+    /// Same as `update_all_pkg_and_deps`, calling `cb` once for every replacement,
+    /// the package first and then the dependencies of every kind in the order they appear
+    /// in the file.
+    pub fn update_all_with_callback<F: Fn(UpdateProgress)>(&self, source: &str, cb: F) -> String {
         debug!(
             "Starting version update from '{}' to '{}'",
             self.current_version, self.new_version
         );
-        let mut updated_source = source.to_owned();
-        let mut iteration = 1;
-
-        loop {
-            debug!("\n--- Iteration {} ---", iteration);
-            iteration += 1;
-
-            // Run one update pass. If an update was applied, use the new source; otherwise, break.
-            match self.update_pass(&updated_source) {
-                Some(new_source) => updated_source = new_source,
-                None => {
-                    debug!("No more versions to update in this iteration");
-                    break;
-                }
+
+        let version_finder = match self.parser(source) {
+            Ok(vf) => vf,
+            Err(e) => {
+                debug!("Error initializing TomlParser: {:?}", e);
+                return source.to_owned();
             }
+        };
+
+        let Some(pkg_and_deps) = version_finder.find_package_and_deps() else {
+            debug!("No package/dependency information found");
+            return source.to_owned();
+        };
+        let filtered = self.filter_package_and_deps(pkg_and_deps);
+
+        let report = |name: &str, version: &str, replacements: &[(usize, usize, String)]| {
+            cb(UpdateProgress {
+                iteration: replacements.len(),
+                name_updated: name.to_string(),
+                version_old: version.to_string(),
+            })
+        };
+
+        // Collect the new version strings, wrapped in double quotes, for every matching node.
+        let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+        if let Some((pkg_node, pkg_info)) = filtered.package {
+            let new_version_quoted = self.new_version_quoted(&pkg_info.version);
+            debug!(
+                "Updating package {} from version {} to {}",
+                pkg_info.name, pkg_info.version, new_version_quoted
+            );
+            replacements.push((pkg_node.start_byte(), pkg_node.end_byte(), new_version_quoted));
+            report(&pkg_info.name, &pkg_info.version, &replacements);
+        }
+        let mut dependencies: Vec<_> = filtered
+            .dependencies
+            .iter()
+            .chain(filtered.dev_dependencies.iter())
+            .chain(filtered.build_dependencies.iter())
+            .chain(filtered.target_dependencies.iter().map(|(node, (_, dep))| (node, dep)))
+            .collect();
+        dependencies.sort_by_key(|(dep_node, _)| dep_node.start_byte());
+        for (dep_node, dep_info) in dependencies {
+            let new_version_quoted = self.new_version_quoted(&dep_info.version);
+            debug!(
+                "Updating dependency '{}' from version '{}' to '{}'",
+                dep_info.name, dep_info.version, new_version_quoted
+            );
+            replacements.push((dep_node.start_byte(), dep_node.end_byte(), new_version_quoted));
+            report(&dep_info.name, &dep_info.version, &replacements);
         }
 
+        debug!("Applying {} edits", replacements.len());
+        let edits = replacements
+            .iter()
+            .map(|(start_byte, end_byte, replacement)| (*start_byte, *end_byte, replacement.as_str()))
+            .collect();
+        let updated_source = version_finder.source_with_edits(edits);
+
         debug!("\nVersion update completed");
         updated_source
     }
 }
 
+//changed_positions
+impl VersionUpdate<'_> {
+    /// Returns where in `source` the update replaces a version, in file order, without
+    /// changing anything. Versions already equal to the replacement are left out, like
+    /// `update_pass` does.
+    pub fn changed_positions(&self, source: &str) -> Vec<VersionChangePosition> {
+        let version_finder = match self.parser(source) {
+            Ok(vf) => vf,
+            Err(e) => {
+                debug!("Error initializing TomlParser: {:?}", e);
+                return Vec::new();
+            }
+        };
+        let Some(pkg_and_deps) = version_finder.find_package_and_deps() else {
+            debug!("No package/dependency information found");
+            return Vec::new();
+        };
+        let filtered = self.filter_package_and_deps(pkg_and_deps);
+
+        let package = filtered
+            .package
+            .iter()
+            .map(|(node, pkg)| (*node, &pkg.name, &pkg.version, PositionKind::Package));
+        let dependencies = filtered
+            .dependencies
+            .iter()
+            .chain(filtered.dev_dependencies.iter())
+            .chain(filtered.build_dependencies.iter())
+            .chain(filtered.target_dependencies.iter().map(|(node, (_, dep))| (node, dep)))
+            .map(|(node, dep)| (*node, &dep.name, &dep.version, PositionKind::Dependency));
+
+        let mut positions: Vec<VersionChangePosition> = package
+            .chain(dependencies)
+            .filter(|(node, _, version, _)| {
+                let node_text = node.utf8_text(source.as_bytes()).unwrap_or("").trim();
+                node_text != self.new_version_quoted(version)
+            })
+            .map(|(node, name, _, kind)| VersionChangePosition {
+                start_byte: node.start_byte(),
+                end_byte: node.end_byte(),
+                entity_name: name.clone(),
+                kind,
+            })
+            .collect();
+        positions.sort_by_key(|position| position.start_byte);
+
+        debug!("Found {} position(s) to change", positions.len());
+        positions
+    }
+}
+
 //update_pass
 impl<'a> VersionUpdate<'a> {
     /// Performs one update pass over the package and its dependencies.
     /// Returns Some(updated_source) if an update was applied, otherwise None.
+    /// A matching version node whose text already equals the replacement is not an
+    /// update, so calling this in a loop until it returns None always terminates.
     pub fn update_pass(&self, source: &str) -> Option<String> {
-        let mut updated_source = source.to_owned();
-
-        let version_finder = match TomlParser::new(&updated_source) {
+        let version_finder = match self.parser(source) {
             Ok(vf) => {
                 debug!("Successfully initialized TomlParser");
                 vf
@@ -59,40 +300,98 @@ impl<'a> VersionUpdate<'a> {
             }
         };
 
-        // Prepare the new version string wrapped in double quotes.
-        let new_version_quoted = format!("\"{}\"", self.new_version);
-
         // Find the package and dependencies.
-        if let Some(pkg_and_deps) = version_finder.find_package_and_deps() {
-            // Filter the package and dependency info using the update criteria.
-            let filtered = self.filter_package_and_deps(pkg_and_deps);
+        let Some(pkg_and_deps) = version_finder.find_package_and_deps() else {
+            debug!("No package/dependency information found");
+            return None;
+        };
+
+        // Filter the package and dependency info using the update criteria.
+        let filtered = self.filter_package_and_deps(pkg_and_deps);
 
-            // Update the package if available.
-            if let Some((pkg_node, pkg_info)) = filtered.package {
-                debug!(
-                    "Updating package {} from version {} to {}",
-                    pkg_info.name, pkg_info.version, new_version_quoted
-                );
-                updated_source = version_finder.edit_node(pkg_node, &new_version_quoted);
-                return Some(updated_source);
+        // Replaces the node unless it already holds the new version.
+        let edit = |node: Node, name: &str, version: &str| {
+            let new_version_quoted = self.new_version_quoted(version);
+            let node_text = node.utf8_text(source.as_bytes()).unwrap_or("").trim();
+            if node_text == new_version_quoted {
+                debug!("'{}' is already at {}, nothing to edit", name, new_version_quoted);
+                return None;
             }
+            debug!(
+                "Updating '{}' from version '{}' to '{}'",
+                name, version, new_version_quoted
+            );
+            let (updated_source, _) = version_finder.edit_node(node, &new_version_quoted);
+            Some(updated_source)
+        };
+
+        // Update the package if available, otherwise the first matching dependency of any
+        // kind.
+        if let Some(updated_source) = filtered
+            .package
+            .as_ref()
+            .and_then(|(pkg_node, pkg_info)| edit(*pkg_node, &pkg_info.name, &pkg_info.version))
+        {
+            return Some(updated_source);
+        }
 
-            // Otherwise, update the first matching dependency.
-            debug!("Checking {} dependencies...", filtered.dependencies.len());
-            if let Some((dep_node, dep_info)) = filtered.dependencies.iter().next() {
-                debug!(
-                    "Updating dependency '{}' from version '{}' to '{}'",
-                    dep_info.name, dep_info.version, new_version_quoted
-                );
-                updated_source = version_finder.edit_node(*dep_node, &new_version_quoted);
-                return Some(updated_source);
+        debug!(
+            "Checking {} dependencies, {} dev-dependencies, {} build-dependencies and {} target dependencies...",
+            filtered.dependencies.len(),
+            filtered.dev_dependencies.len(),
+            filtered.build_dependencies.len(),
+            filtered.target_dependencies.len()
+        );
+        let mut dependencies: Vec<_> = filtered
+            .dependencies
+            .iter()
+            .chain(filtered.dev_dependencies.iter())
+            .chain(filtered.build_dependencies.iter())
+            .chain(filtered.target_dependencies.iter().map(|(node, (_, dep))| (node, dep)))
+            .collect();
+        dependencies.sort_by_key(|(dep_node, _)| dep_node.start_byte());
+        dependencies
+            .into_iter()
+            .find_map(|(dep_node, dep_info)| edit(*dep_node, &dep_info.name, &dep_info.version))
+    }
+}
+
+// update_workspace_version
+impl VersionUpdate<'_> {
+    /// Updates `version` in `[workspace.package]`, which members declaring
+    /// `version.workspace = true` inherit. Returns None when there is no such version,
+    /// it does not match the update or it is already at the new version.
+    pub fn update_workspace_version(&self, source: &str) -> Option<String> {
+        let version_finder = match self.parser(source) {
+            Ok(vf) => vf,
+            Err(e) => {
+                debug!("Error initializing TomlParser: {:?}", e);
+                return None;
             }
-        } else {
-            debug!("No package/dependency information found");
+        };
+
+        let Some(version_node) = version_finder.find_workspace_package_version() else {
+            debug!("No [workspace.package] version found");
+            return None;
+        };
+        let node_text = version_node.utf8_text(source.as_bytes()).ok()?.trim();
+        let version = node_text.trim_matches('"');
+        if !self.version_matches(version) {
+            debug!("Workspace version {} does not match the update", version);
+            return None;
         }
 
-        // No update was made in this pass.
-        None
+        let new_version_quoted = self.new_version_quoted(version);
+        if node_text == new_version_quoted {
+            debug!("Workspace is already at {}, nothing to edit", new_version_quoted);
+            return None;
+        }
+        debug!(
+            "Updating workspace version from '{}' to '{}'",
+            version, new_version_quoted
+        );
+        let (updated_source, _) = version_finder.edit_node(version_node, &new_version_quoted);
+        Some(updated_source)
     }
 }
 
@@ -102,7 +401,7 @@ impl<'a> VersionUpdate<'a> {
         debug!("Starting filtered_pkg_and_deps update: {:?}", self);
 
         // Initialize the TomlParser with the provided source.
-        let version_finder = match TomlParser::new(source) {
+        let version_finder = match self.parser(source) {
             Ok(vf) => {
                 debug!("Successfully initialized TomlParser");
                 vf
@@ -132,7 +431,7 @@ impl<'a> VersionUpdate<'a> {
 
 /// This function filters the given `PackageAndDepsNodes` according to the package name in `update`.
 /// - If the package info's name does not match, it sets `package` to `None`.
-/// - The dependencies HashMap is filtered so that only those whose `name` matches `package_name` are kept.
+/// - The dependencies, dev-dependencies, build-dependencies and target dependencies HashMaps are filtered so that only those whose `name` matches `package_name` are kept.
 // filter_package_and_deps
 impl<'a> VersionUpdate<'a> {
     pub fn filter_package_and_deps(
@@ -142,11 +441,11 @@ impl<'a> VersionUpdate<'a> {
         // Filter package: Only include if a package name is provided and both name and version match.
         let filtered_package = match (pkg_and_deps.package, self.package_name) {
             (Some((pkg_node, pkg_info)), Some(pkg_name))
-                if pkg_info.name == pkg_name && pkg_info.version == self.current_version =>
+                if pkg_info.name == pkg_name && self.version_matches(&pkg_info.version) =>
             {
                 Some((pkg_node, pkg_info))
             }
-            (Some((pkg_node, pkg_info)), None) if pkg_info.version == self.current_version => {
+            (Some((pkg_node, pkg_info)), None) if self.version_matches(&pkg_info.version) => {
                 Some((pkg_node, pkg_info))
             }
             _ => None,
@@ -155,20 +454,431 @@ impl<'a> VersionUpdate<'a> {
         // Filter dependencies:
         // If package name is provided: both name and version must match.
         // If not provided: only the version must match.
+        let dep_matches = |dep_info: &DepsInfo| match self.package_name {
+            Some(pkg_name) => dep_info.name == pkg_name && self.version_matches(&dep_info.version),
+            None => self.version_matches(&dep_info.version),
+        };
         let filtered_dependencies = pkg_and_deps
             .dependencies
             .into_iter()
-            .filter(|(_, dep_info)| match self.package_name {
-                Some(pkg_name) => {
-                    dep_info.name == pkg_name && dep_info.version == self.current_version
-                }
-                None => dep_info.version == self.current_version,
-            })
+            .filter(|(_, dep_info)| dep_matches(dep_info))
+            .collect();
+        let filtered_dev_dependencies = pkg_and_deps
+            .dev_dependencies
+            .into_iter()
+            .filter(|(_, dep_info)| dep_matches(dep_info))
+            .collect();
+        let filtered_build_dependencies = pkg_and_deps
+            .build_dependencies
+            .into_iter()
+            .filter(|(_, dep_info)| dep_matches(dep_info))
+            .collect();
+        let filtered_target_dependencies = pkg_and_deps
+            .target_dependencies
+            .into_iter()
+            .filter(|(_, (_, dep_info))| dep_matches(dep_info))
             .collect();
 
         PackageAndDepsNodes {
             package: filtered_package,
             dependencies: filtered_dependencies,
+            dev_dependencies: filtered_dev_dependencies,
+            build_dependencies: filtered_build_dependencies,
+            target_dependencies: filtered_target_dependencies,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+[package]
+name = "package_test1"
+version = "0.4.3"
+
+[dependencies]
+package_test2 = { version = "0.4.3", path = "package_test2" }
+package_test3 = { version = "0.4.3", path = "package_test3" }
+other = { version = "1.0.0" }
+"#;
+
+    #[test]
+    fn test_update_all_pkg_and_deps() {
+        let update = VersionUpdate {
+            package_name: None,
+            current_version: "0.4.3",
+            new_version: "0.4.4",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        };
+        let updated = update.update_all_pkg_and_deps(SOURCE);
+        assert_eq!(updated, SOURCE.replace("0.4.3", "0.4.4"));
+    }
+
+    #[test]
+    fn test_update_dotted_key_package_with_toml10() {
+        let source = "package.name = \"foo\"\npackage.version = \"0.4.3\"\n";
+        let update = |grammar| VersionUpdate {
+            package_name: None,
+            current_version: "0.4.3",
+            new_version: "0.4.4",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar,
+        };
+
+        assert_eq!(update(TomlGrammar::Toml05).update_all_pkg_and_deps(source), source);
+        assert_eq!(
+            update(TomlGrammar::Toml10).update_all_pkg_and_deps(source),
+            source.replace("0.4.3", "0.4.4")
+        );
+    }
+
+    #[test]
+    fn test_update_all_pkg_and_deps_by_name() {
+        let update = VersionUpdate {
+            package_name: Some("package_test2"),
+            current_version: "0.4.3",
+            new_version: "0.4.4",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        };
+        let updated = update.update_all_pkg_and_deps(SOURCE);
+        assert!(updated.contains(r#"package_test2 = { version = "0.4.4""#));
+        assert!(updated.contains(r#"package_test3 = { version = "0.4.3""#));
+        assert!(updated.contains(r#"version = "0.4.3"
+"#));
+    }
+
+    #[test]
+    fn test_update_all_pkg_and_deps_updates_every_dependency_table() {
+        let source = r#"
+[package]
+name = "package_test1"
+version = "0.1.0"
+
+[dependencies]
+my-helper = { version = "0.3.0", optional = true }
+
+[dev-dependencies]
+my-helper = { version = "0.3.0" }
+
+[build-dependencies]
+my-helper = { version = "0.3.0" }
+
+[target.'cfg(unix)'.dependencies]
+my-helper = { version = "0.3.0" }
+"#;
+        let update = VersionUpdate {
+            package_name: Some("my-helper"),
+            current_version: "0.3.0",
+            new_version: "0.4.0",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        };
+        let updated = update.update_all_pkg_and_deps(source);
+        assert_eq!(updated, source.replace("0.3.0", "0.4.0"));
+        assert_eq!(update.changed_positions(source).len(), 4);
+    }
+
+    const BUILD_METADATA_SOURCE: &str = r#"
+[package]
+name = "package_meta"
+version = "1.0.0+build.1"
+"#;
+
+    #[test]
+    fn test_update_build_metadata_preserved() {
+        let update = VersionUpdate {
+            package_name: None,
+            current_version: "1.0.0+build.1",
+            new_version: "2.0.0",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        }
+        .with_build_metadata_preserved(true);
+        let updated = update.update_all_pkg_and_deps(BUILD_METADATA_SOURCE);
+        assert!(updated.contains(r#"version = "2.0.0+build.1""#));
+    }
+
+    #[test]
+    fn test_update_build_metadata_dropped_by_default() {
+        let update = VersionUpdate {
+            package_name: None,
+            current_version: "1.0.0+build.1",
+            new_version: "2.0.0",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        };
+        let updated = update.update_all_pkg_and_deps(BUILD_METADATA_SOURCE);
+        assert!(updated.contains(r#"version = "2.0.0"
+"#));
+    }
+
+    fn constraint_update(req: &str, new_version: &'static str) -> VersionUpdate<'static> {
+        VersionUpdate {
+            package_name: None,
+            current_version: "",
+            new_version,
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        }
+        .with_current_version_req(Some(VersionReq::parse(req).unwrap()))
+    }
+
+    #[test]
+    fn test_update_by_constraint_single_version() {
+        let updated = constraint_update("=0.4.3", "0.5.0").update_all_pkg_and_deps(SOURCE);
+        assert_eq!(updated, SOURCE.replace("0.4.3", "0.5.0"));
+    }
+
+    #[test]
+    fn test_update_by_constraint_range() {
+        let updated = constraint_update("^0.4", "0.5.0").update_all_pkg_and_deps(SOURCE);
+        assert_eq!(updated, SOURCE.replace("0.4.3", "0.5.0"));
+
+        let updated = constraint_update(">=1.0.0, <2.0.0", "1.0.200").update_all_pkg_and_deps(SOURCE);
+        assert_eq!(updated, SOURCE.replace("1.0.0", "1.0.200"));
+    }
+
+    #[test]
+    fn test_update_by_constraint_mixed_versions() {
+        let source = r#"
+[package]
+name = "package_mixed"
+version = "1.2.0"
+
+[dependencies]
+serde = { version = "1.0.150" }
+serde_json = { version = "1.9.9" }
+toml = { version = "2.0.0" }
+odd = { version = "1.5" }
+"#;
+        let updated =
+            constraint_update(">=1.0.0, <2.0.0", "1.0.200").update_all_pkg_and_deps(source);
+        let expected = source
+            .replace("\"1.2.0\"", "\"1.0.200\"")
+            .replace("\"1.0.150\"", "\"1.0.200\"")
+            .replace("\"1.9.9\"", "\"1.0.200\"");
+        assert_eq!(updated, expected);
+
+        let by_name = VersionUpdate {
+            package_name: Some("serde"),
+            ..constraint_update(">=1.0.0, <2.0.0", "1.0.200")
+        };
+        let updated = by_name.update_all_pkg_and_deps(source);
+        assert_eq!(updated, source.replace("\"1.0.150\"", "\"1.0.200\""));
+    }
+
+    /// Applies `update_pass` until it reports nothing left to do.
+    fn update_until_done(update: &VersionUpdate, source: &str) -> (String, usize) {
+        let mut current = source.to_string();
+        let mut passes = 0;
+        while let Some(updated) = update.update_pass(&current) {
+            current = updated;
+            passes += 1;
+            assert!(passes <= 10, "update_pass did not terminate");
+        }
+        (current, passes)
+    }
+
+    #[test]
+    fn test_update_pass_beta_to_stable() {
+        let source = r#"
+[package]
+name = "package_beta"
+version = "1.0.0-beta.1"
+
+[dependencies]
+package_a = { version = "1.0.0-beta.1", path = "../package_a" }
+package_b = { version = "1.0.0" }
+"#;
+        let update = VersionUpdate {
+            package_name: None,
+            current_version: "1.0.0-beta.1",
+            new_version: "1.0.0",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        };
+
+        let (updated, passes) = update_until_done(&update, source);
+        assert_eq!(passes, 2);
+        assert_eq!(updated, source.replace("1.0.0-beta.1", "1.0.0"));
+    }
+
+    #[test]
+    fn test_update_pass_plain_string_dependencies() {
+        let source = r#"
+[package]
+name = "package_plain"
+version = "0.1.0"
+
+[dependencies]
+package_a = "1.0.0"
+package_b = { version = "1.0.0", path = "../package_b" }
+package_c = "^1.0"
+"#;
+        let update = VersionUpdate {
+            package_name: None,
+            current_version: "1.0.0",
+            new_version: "1.1.0",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        };
+
+        let (updated, passes) = update_until_done(&update, source);
+        assert_eq!(passes, 2);
+        assert!(updated.contains(r#"package_a = "1.1.0""#));
+        assert!(updated.contains(r#"package_b = { version = "1.1.0", path = "../package_b" }"#));
+        // A requirement is only updated when it is exactly the current version.
+        assert!(updated.contains(r#"package_c = "^1.0""#));
+
+        let caret = VersionUpdate {
+            current_version: "^1.0",
+            new_version: "^1.1",
+            ..update
+        };
+        assert_eq!(caret.update_all_pkg_and_deps(source), source.replace("^1.0", "^1.1"));
+    }
+
+    #[test]
+    fn test_update_pass_skips_nodes_already_at_new_version() {
+        // The requirement also matches 1.0.0, which is already the new version.
+        let update = VersionUpdate {
+            package_name: None,
+            current_version: "",
+            new_version: "1.0.0",
+            build_metadata_preserved: false,
+            current_version_req: Some(VersionReq::parse("^1").unwrap()),
+            grammar: TomlGrammar::Toml05,
+        };
+        let source = "[package]\nname = \"pkg\"\nversion = \"1.0.0\"\n\n[dependencies]\ndep = { version = \"1.2.0\" }\n";
+
+        let (updated, passes) = update_until_done(&update, source);
+        assert_eq!(passes, 1);
+        assert_eq!(updated, source.replace("1.2.0", "1.0.0"));
+    }
+
+    #[test]
+    fn test_update_workspace_version() {
+        let update = VersionUpdate {
+            package_name: None,
+            current_version: "0.4.1",
+            new_version: "0.4.2",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        };
+        let source = "[workspace]\nmembers = [\"a\"]\n\n[workspace.package]\nversion = \"0.4.1\"\nedition = \"2024\"\n";
+
+        let updated = update.update_workspace_version(source).unwrap();
+        assert_eq!(updated, source.replace("0.4.1", "0.4.2"));
+        assert_eq!(update.update_workspace_version(&updated), None);
+
+        let member = "[package]\nname = \"a\"\nversion.workspace = true\n";
+        assert_eq!(update.update_workspace_version(member), None);
+    }
+
+    #[test]
+    fn test_changed_positions_round_trip() {
+        let update = VersionUpdate {
+            package_name: None,
+            current_version: "0.4.3",
+            new_version: "0.4.4",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        };
+
+        let positions = update.changed_positions(SOURCE);
+
+        let found: Vec<(&str, &str, PositionKind)> = positions
+            .iter()
+            .map(|position| {
+                (
+                    &SOURCE[position.start_byte..position.end_byte],
+                    position.entity_name.as_str(),
+                    position.kind,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("\"0.4.3\"", "package_test1", PositionKind::Package),
+                ("\"0.4.3\"", "package_test2", PositionKind::Dependency),
+                ("\"0.4.3\"", "package_test3", PositionKind::Dependency),
+            ]
+        );
+        assert!(positions.windows(2).all(|w| w[0].end_byte <= w[1].start_byte));
+
+        // Replacing every range gives the same result as the update itself.
+        let mut edited = SOURCE.to_string();
+        for position in positions.iter().rev() {
+            edited.replace_range(position.start_byte..position.end_byte, "\"0.4.4\"");
+        }
+        assert_eq!(edited, update.update_all_pkg_and_deps(SOURCE));
+    }
+
+    #[test]
+    fn test_strip_prerelease_for_stable() {
+        for (current, stable) in [
+            ("1.0.0-alpha.1", "1.0.0"),
+            ("2.1.0-rc.2", "2.1.0"),
+            ("1.0.0-beta.3+build.7", "1.0.0"),
+            ("0.4.3", "0.4.3"),
+            ("not.a.version", "not.a.version"),
+        ] {
+            let update = VersionUpdate {
+                package_name: None,
+                current_version: current,
+                new_version: "",
+                build_metadata_preserved: false,
+                current_version_req: None,
+                grammar: TomlGrammar::Toml05,
+            };
+            assert_eq!(update.strip_prerelease_for_stable(), stable);
         }
     }
+
+    #[test]
+    fn test_update_all_with_callback_called_per_replacement() {
+        let update = VersionUpdate {
+            package_name: None,
+            current_version: "0.4.3",
+            new_version: "0.4.4",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        };
+        let progress = std::cell::RefCell::new(Vec::new());
+
+        let updated =
+            update.update_all_with_callback(SOURCE, |p| progress.borrow_mut().push(p));
+
+        assert_eq!(updated, update.update_all_pkg_and_deps(SOURCE));
+        let progress = progress.into_inner();
+        assert_eq!(progress.len(), 3);
+        assert_eq!(
+            progress
+                .iter()
+                .map(|p| (p.iteration, p.name_updated.as_str(), p.version_old.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, "package_test1", "0.4.3"),
+                (2, "package_test2", "0.4.3"),
+                (3, "package_test3", "0.4.3"),
+            ]
+        );
+    }
 }
@@ -0,0 +1,250 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Fills in the `{from}` and `{to}` placeholders of a commit message template.
+pub fn render_commit_message(template: &str, from: &str, to: &str) -> String {
+    template.replace("{from}", from).replace("{to}", to)
+}
+
+/// Stages `modified_files` and commits only them with `message`, using the `git` found in
+/// `PATH`. Changes the user had already staged are left staged and out of the commit.
+pub fn git_commit_changes(modified_files: &[PathBuf], message: &str) -> Result<()> {
+    git_commit_changes_with("git", Path::new("."), modified_files, message)
+}
+
+/// Same as `git_commit_changes`, running `program` instead of `git` in `repo_dir`.
+fn git_commit_changes_with(
+    program: &str,
+    repo_dir: &Path,
+    modified_files: &[PathBuf],
+    message: &str,
+) -> Result<()> {
+    debug!("Staging {} file(s) with {}", modified_files.len(), program);
+    run_git(
+        Command::new(program)
+            .current_dir(repo_dir)
+            .arg("add")
+            .arg("--")
+            .args(modified_files),
+        "add",
+    )?;
+
+    // The pathspec limits the commit to `modified_files`.
+    debug!("Committing with message: {}", message);
+    run_git(
+        Command::new(program)
+            .current_dir(repo_dir)
+            .args(["commit", "-m", message, "--"])
+            .args(modified_files),
+        "commit",
+    )
+}
+
+/// Creates the tag `<prefix><version>` on the current commit, e.g. `v0.4.2`. Annotated
+/// tags are created with `-a` and use `message`, or the tag name when there is none.
+pub fn git_tag_version(
+    prefix: &str,
+    version: &str,
+    annotated: bool,
+    message: Option<&str>,
+) -> Result<()> {
+    git_tag_version_with("git", prefix, version, annotated, message)
+}
+
+/// Same as `git_tag_version`, running `program` instead of `git`.
+fn git_tag_version_with(
+    program: &str,
+    prefix: &str,
+    version: &str,
+    annotated: bool,
+    message: Option<&str>,
+) -> Result<()> {
+    let tag = format!("{}{}", prefix, version);
+    debug!("Creating {} tag {}", if annotated { "annotated" } else { "lightweight" }, tag);
+
+    let mut cmd = Command::new(program);
+    cmd.arg("tag");
+    if annotated {
+        cmd.args(["-a", &tag, "-m", message.unwrap_or(&tag)]);
+    } else {
+        cmd.arg(&tag);
+    }
+    run_git(&mut cmd, "tag")
+}
+
+/// Whether `manifest` lies in a git repository nested below `root`, such as a submodule,
+/// i.e. whether a directory between the two has a `.git` entry. `root` itself is not
+/// checked, and paths outside `root` are never nested.
+pub fn is_in_nested_repo(manifest: &Path, root: &Path) -> bool {
+    let Ok(relative) = manifest.strip_prefix(root) else {
+        return false;
+    };
+    relative
+        .ancestors()
+        .skip(1)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .any(|dir| root.join(dir).join(".git").exists())
+}
+
+/// Runs a git command and turns a failed exit status into an error.
+fn run_git(cmd: &mut Command, subcommand: &str) -> Result<()> {
+    debug!("Running: {:?}", cmd);
+    let status = cmd
+        .status()
+        .with_context(|| format!("Failed to run git {}", subcommand))?;
+
+    if !status.success() {
+        anyhow::bail!("git {} failed with status {:?}", subcommand, status.code());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Writes a fake `git` that appends its arguments, one line per call, to `log`.
+    #[cfg(unix)]
+    fn fake_git(dir: &Path, log: &Path, exit_code: i32) -> PathBuf {
+        let body = format!("echo \"$*\" >> '{}'\nexit {}\n", log.display(), exit_code);
+        crate::test_support::fake_program(dir, "git", &body)
+    }
+
+    #[test]
+    fn test_render_commit_message() {
+        assert_eq!(
+            render_commit_message("chore: bump version {from} → {to}", "0.4.1", "0.4.2"),
+            "chore: bump version 0.4.1 → 0.4.2"
+        );
+    }
+
+    #[test]
+    fn test_is_in_nested_repo() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("crates/a")).unwrap();
+        fs::create_dir_all(root.join("vendor/lib/crates/b")).unwrap();
+        // Submodules have a `.git` file pointing into the parent repository.
+        fs::write(root.join("vendor/lib/.git"), "gitdir: ../../.git/modules/lib\n").unwrap();
+
+        assert!(!is_in_nested_repo(&root.join("Cargo.toml"), root));
+        assert!(!is_in_nested_repo(&root.join("crates/a/Cargo.toml"), root));
+        assert!(is_in_nested_repo(&root.join("vendor/lib/Cargo.toml"), root));
+        assert!(is_in_nested_repo(&root.join("vendor/lib/crates/b/Cargo.toml"), root));
+        assert!(!is_in_nested_repo(Path::new("/elsewhere/Cargo.toml"), root));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_git_commit_changes_arguments() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log = tmp.path().join("git.log");
+        let git = fake_git(tmp.path(), &log, 0);
+        let files = vec![
+            PathBuf::from("crate_a/Cargo.toml"),
+            PathBuf::from("crate_b/Cargo.toml"),
+        ];
+
+        git_commit_changes_with(
+            git.to_str().unwrap(),
+            tmp.path(),
+            &files,
+            "chore: bump version 0.4.1 → 0.4.2",
+        )
+        .unwrap();
+
+        let calls = fs::read_to_string(&log).unwrap();
+        assert_eq!(
+            calls.lines().collect::<Vec<_>>(),
+            vec![
+                "add -- crate_a/Cargo.toml crate_b/Cargo.toml",
+                "commit -m chore: bump version 0.4.1 → 0.4.2 -- crate_a/Cargo.toml crate_b/Cargo.toml",
+            ]
+        );
+    }
+
+    /// Runs `git` with `args` in `repo_dir` and returns its standard output.
+    fn git_output(repo_dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .current_dir(repo_dir)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_git_commit_changes_leaves_staged_files_out() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = tmp.path();
+        git_output(repo, &["init", "-q"]);
+        git_output(repo, &["config", "user.name", "cratup"]);
+        git_output(repo, &["config", "user.email", "cratup@example.com"]);
+        fs::write(repo.join("Cargo.toml"), "[package]\nversion = \"0.4.1\"\n").unwrap();
+        fs::write(repo.join("notes.txt"), "first\n").unwrap();
+        git_output(repo, &["add", "."]);
+        git_output(repo, &["commit", "-q", "-m", "initial"]);
+
+        // An unrelated change the user staged before running incv.
+        fs::write(repo.join("notes.txt"), "second\n").unwrap();
+        git_output(repo, &["add", "notes.txt"]);
+        fs::write(repo.join("Cargo.toml"), "[package]\nversion = \"0.4.2\"\n").unwrap();
+
+        git_commit_changes_with("git", repo, &[PathBuf::from("Cargo.toml")], "bump").unwrap();
+
+        let committed = git_output(repo, &["show", "--name-only", "--format=%s", "HEAD"]);
+        assert_eq!(
+            committed.lines().filter(|line| !line.is_empty()).collect::<Vec<_>>(),
+            vec!["bump", "Cargo.toml"]
+        );
+        let staged = git_output(repo, &["diff", "--cached", "--name-only"]);
+        assert_eq!(staged.trim(), "notes.txt");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_git_commit_changes_stops_when_add_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log = tmp.path().join("git.log");
+        let git = fake_git(tmp.path(), &log, 1);
+
+        let err = git_commit_changes_with(
+            git.to_str().unwrap(),
+            tmp.path(),
+            &[PathBuf::from("Cargo.toml")],
+            "message",
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("git add"), "{}", err);
+        assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_git_tag_version_arguments() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log = tmp.path().join("git.log");
+        let git = fake_git(tmp.path(), &log, 0);
+        let git = git.to_str().unwrap();
+
+        git_tag_version_with(git, "v", "0.4.2", false, None).unwrap();
+        git_tag_version_with(git, "v", "0.4.2", true, Some("Release 0.4.2")).unwrap();
+        git_tag_version_with(git, "release-", "0.4.2", true, None).unwrap();
+
+        let calls = fs::read_to_string(&log).unwrap();
+        assert_eq!(
+            calls.lines().collect::<Vec<_>>(),
+            vec![
+                "tag v0.4.2",
+                "tag -a v0.4.2 -m Release 0.4.2",
+                "tag -a release-0.4.2 -m release-0.4.2",
+            ]
+        );
+    }
+}
@@ -2,15 +2,67 @@ use anyhow::Context;
 use anyhow::Result;
 use colored::ColoredString;
 use colored::Colorize;
-use log::debug;
+use glob::Pattern;
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use log::{debug, warn};
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
-use semver::Version;
-use std::collections::HashSet;
+use semver::{BuildMetadata, Prerelease, Version, VersionReq};
+use std::cell::{OnceCell, RefCell};
+use std::collections::{BTreeMap, HashSet};
 
-use cratup_search::{VersionMatch, get_colored_dir_path_and_matches, get_colored_pkg_deps};
-use cratup_tree_sitter::{PackageAndDeps, VersionUpdate};
+use cratup_init::Config;
+use cratup_search::{
+    MatchThresholds, VersionMatch, find_manifests, get_colored_dir_path_and_matches,
+    get_colored_pkg_deps,
+};
+use cratup_tree_sitter::{
+    PackageAndDeps, TomlGrammar, TomlParser, UpdateProgress, VersionUpdate,
+    VersionUpdateError,
+};
+
+/// Custom predicate deciding whether a Cargo.toml, given its path and filtered
+/// package/dependency info, takes part in the update.
+type FileFilter = Box<dyn Fn(&Path, &PackageAndDeps) -> bool>;
+
+/// Returns the match counts set in `config` at which the per-file match count changes
+/// color.
+pub fn match_thresholds(config: &Config) -> MatchThresholds {
+    MatchThresholds {
+        warn: config.match_warn_threshold,
+        error: config.match_error_threshold,
+    }
+}
+
+/// One version replacement that `update_dirs_and_packages_streaming` would make.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionChange {
+    pub file: PathBuf,
+    pub package: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// The content of one Cargo.toml before and after `update_and_diff` or `diff`. Displayed
+/// as a unified diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub path: PathBuf,
+    pub old: String,
+    pub new: String,
+}
+
+/// Lines of context kept around each change of a unified diff.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// A package or dependency of the update whose version differs between the Cargo.toml
+/// files mentioning it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionInconsistency {
+    pub package: String,
+    /// Every Cargo.toml mentioning the package with the version found there, sorted by path.
+    pub versions: Vec<(PathBuf, String)>,
+}
 
 /// The Increaser struct now includes the current directory along with version update info.
 pub struct Increaser {
@@ -19,79 +71,200 @@ pub struct Increaser {
     next_version: String,
     package_name: Option<String>,
     package_dirs: Vec<(PathBuf, PackageAndDeps)>,
+    /// Every Cargo.toml found under `dir_path` when the increaser is created. Each one is
+    /// read again when needed, one file at a time. `package_dirs` and `inconsistencies`
+    /// are derived from them.
+    manifests: Vec<PathBuf>,
+    backup: bool,
+    /// The `Cargo.toml.bak` files written by this increaser, restored by `rollback` and
+    /// deleted by `remove_backups`.
+    backups: RefCell<Vec<PathBuf>>,
+    filter_fn: Option<FileFilter>,
+    match_thresholds: MatchThresholds,
+    /// Set by `update_with_semver_constraint`, replaces the exact `current_version` match.
+    version_req: Option<VersionReq>,
+    /// Found by `check_consistency` the first time `inconsistencies` is called.
+    inconsistencies: OnceCell<Vec<VersionInconsistency>>,
+    /// Cargo.toml files left untouched, matched against their path relative to `dir_path`.
+    skip_files: Vec<Pattern>,
+    /// Set by `with_quiet`, the version matches are returned without being printed.
+    quiet: bool,
+    /// TOML version the package of each Cargo.toml is read with.
+    grammar: TomlGrammar,
 }
 
-//update_dirs_and_packages
+//update_dirs_and_packages_streaming
 impl Increaser {
-    /// Walks through the current directory (dir_path), finds all Cargo.toml files,
-    /// updates their content by applying the version change, writes the updated content back,
-    /// and returns a vector containing each file's path along with its package/dependency info.
-    pub fn update_dirs_and_packages(&self) -> Result<Vec<(PathBuf, PackageAndDeps)>> {
-        // Create the VersionUpdate using Increaser's version info.
-        let version_update = VersionUpdate {
-            package_name: self.package_name.as_deref(),
-            current_version: &self.current_version,
-            new_version: &self.next_version,
+    /// Goes through the Cargo.toml files found in the current directory (dir_path) one at a
+    /// time as the returned iterator is advanced: reads each file, applies the version change,
+    /// writes the updated content back and yields its path along with the package/dependency
+    /// info that was updated in it. Only the file being processed is held in memory, and
+    /// nothing is written until the iterator is consumed. Files the update does not change
+    /// are neither written nor yielded.
+    pub fn update_dirs_and_packages_streaming(
+        &self,
+    ) -> impl Iterator<Item = Result<(PathBuf, PackageAndDeps), VersionUpdateError>> + '_ {
+        // Cleared when the iterator is dropped, so the spinner never stays on screen,
+        // whatever the outcome.
+        let pb = ProgressBar::new_spinner().with_finish(ProgressFinish::AndClear);
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} {msg}")
+                .expect("Failed to set progress bar template"),
+        );
+
+        self.manifests.iter().filter_map(move |file_path| {
+            let on_progress = |progress: UpdateProgress| {
+                pb.set_message(format!(
+                    "Updated {} ({} -> {})",
+                    progress.name_updated, progress.version_old, self.next_version
+                ));
+                pb.tick();
+            };
+            self.update_file(file_path, &on_progress).transpose()
+        })
+    }
+
+    /// Updates a single Cargo.toml. Returns `None` when the file is rejected by the
+    /// filter or has nothing to update, in which case it is not written.
+    fn update_file(
+        &self,
+        file_path: &Path,
+        on_progress: &dyn Fn(UpdateProgress),
+    ) -> Result<Option<(PathBuf, PackageAndDeps)>, VersionUpdateError> {
+        let Some((_, updated_source, filtered)) = self.updated_source(file_path, on_progress)?
+        else {
+            return Ok(None);
         };
+        self.write_update(file_path, &updated_source)?;
 
-        let results = Vec::new();
-        for entry in WalkDir::new(&self.dir_path)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_file() && entry.file_name() == "Cargo.toml" {
-                let file_path = entry.path().to_path_buf();
-                // Read the file contents.
-                let content = fs::read_to_string(&file_path)
-                    .with_context(|| format!("Failed to read file {:?}", file_path))?;
-
-                // Destructure to capture both values.
-                let updated_source = version_update.update_all_pkg_and_deps(&content);
-
-                // Write the updated content back to the file.
-                fs::write(&file_path, updated_source)
-                    .with_context(|| format!("Failed to write file {:?}", file_path))?;
+        // A virtual workspace root has no package or dependency info, but was still updated.
+        Ok(Some((file_path.to_path_buf(), filtered.unwrap_or_default())))
+    }
+
+    /// Returns the original and updated content of a single Cargo.toml along with its
+    /// filtered package/dependency info, or `None` when the file is rejected by the filter
+    /// or has nothing to update.
+    fn updated_source(
+        &self,
+        file_path: &Path,
+        on_progress: &dyn Fn(UpdateProgress),
+    ) -> Result<Option<(String, String, Option<PackageAndDeps>)>, VersionUpdateError> {
+        let version_update = self.version_update();
+
+        // Read the file contents.
+        debug!("Updating file {:?}", file_path);
+        let content = fs::read_to_string(file_path).map_err(VersionUpdateError::io(file_path))?;
+
+        let filtered = version_update.filtered_pkg_and_deps(&content);
+
+        // Skip files rejected by the custom filter.
+        if let Some(ref filter_fn) = self.filter_fn {
+            let included = filtered
+                .as_ref()
+                .is_some_and(|pkg_and_deps| filter_fn(file_path, pkg_and_deps));
+            if !included {
+                debug!("Skipping {:?}: rejected by filter", file_path);
+                return Ok(None);
             }
         }
 
-        Ok(results)
+        // A workspace root's [workspace.package] version is inherited by its members. It is
+        // bumped first, then the root's own package and dependencies like any other file.
+        let workspace_update = TomlParser::new_with_grammar(&content, self.grammar)
+            .is_ok_and(|parser| parser.find_workspace().is_some())
+            .then(|| version_update.update_workspace_version(&content))
+            .flatten();
+        let workspace_source = match workspace_update {
+            Some(workspace_source) => {
+                debug!("Updated the [workspace.package] version of {:?}", file_path);
+                on_progress(UpdateProgress {
+                    iteration: 1,
+                    name_updated: "workspace.package".to_string(),
+                    version_old: self.current_version.clone(),
+                });
+                workspace_source
+            }
+            None => content.clone(),
+        };
+        let updated_source = version_update.update_all_with_callback(&workspace_source, on_progress);
+        if updated_source == content {
+            debug!("Skipping {:?}: nothing to update", file_path);
+            return Ok(None);
+        }
+
+        Ok(Some((content, updated_source, filtered)))
+    }
+
+    /// Writes `updated_source` to `file_path`, backing the file up first when enabled.
+    fn write_update(&self, file_path: &Path, updated_source: &str) -> Result<(), VersionUpdateError> {
+        // Keep a copy of the original file so a failed run can be rolled back.
+        if self.backup {
+            let backup_path = file_path.with_file_name("Cargo.toml.bak");
+            fs::copy(file_path, &backup_path).map_err(VersionUpdateError::io(&backup_path))?;
+            self.backups.borrow_mut().push(backup_path);
+        }
+
+        // Write the updated content back to the file.
+        fs::write(file_path, updated_source).map_err(VersionUpdateError::io(file_path))?;
+        Ok(())
+    }
+
+    /// Restores every `Cargo.toml.bak` written by this increaser back to its `Cargo.toml`,
+    /// deletes the backup and returns the number of files restored. Backups left by
+    /// earlier runs are not touched.
+    pub fn rollback(&self) -> Result<usize> {
+        let backups = self.backups.take();
+        for backup_path in &backups {
+            let file_path = backup_path.with_file_name("Cargo.toml");
+            debug!("Restoring {:?} from {:?}", file_path, backup_path);
+
+            fs::copy(backup_path, &file_path)
+                .with_context(|| format!("Failed to restore file {:?}", file_path))?;
+            fs::remove_file(backup_path)
+                .with_context(|| format!("Failed to remove backup {:?}", backup_path))?;
+        }
+
+        Ok(backups.len())
+    }
+
+    /// Deletes every `Cargo.toml.bak` written by this increaser, once the update is known
+    /// to have succeeded, and returns the number of files deleted.
+    pub fn remove_backups(&self) -> Result<usize> {
+        let backups = self.backups.take();
+        for backup_path in &backups {
+            debug!("Removing backup {:?}", backup_path);
+            fs::remove_file(backup_path)
+                .with_context(|| format!("Failed to remove backup {:?}", backup_path))?;
+        }
+
+        Ok(backups.len())
     }
 
     /// Print version matches using the red color for current version matches.
     pub fn print_current_version_matches(&self) -> Result<Vec<VersionMatch>> {
-        // Construct the VersionUpdate for current versions.
-        let version_update = VersionUpdate {
-            package_name: self.package_name.as_deref(),
-            current_version: &self.current_version,
-            // Here both current and new versions are the same, since we are highlighting the current version.
-            new_version: &self.current_version,
-        };
-
-        self.print_version_matches(&version_update, |s| s.red())
+        self.print_version_matches(&self.package_dirs, |s| s.red())
     }
 
     /// Print version matches using the green color for next version matches.
     pub fn print_next_version_matches(&self) -> Result<Vec<VersionMatch>> {
-        // build a new Increaser for the “next” version
-        let mut updated = Increaser::new(
-            self.dir_path.clone(),
-            self.next_version.clone(),
-            "0.0.0".to_string(),
-            self.package_name.clone(),
-        )?;
-
-        // now filter updated.package_dirs so it only contains what existed in `self`
-        updated.package_dirs = self.filter_existing_packages(updated.package_dirs);
-
-        // build the VersionUpdate as you did before
+        // Load the entries that are now at the "next" version.
         let version_update = VersionUpdate {
-            package_name: updated.package_name.as_deref(),
-            current_version: &updated.next_version,
-            new_version: &updated.next_version,
+            package_name: self.package_name.as_deref(),
+            current_version: &self.next_version,
+            new_version: &self.next_version,
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: self.grammar,
         };
+        let next_dirs =
+            load_dirs_and_packages(&self.manifests, &version_update, self.filter_fn.as_ref())?;
+
+        // now filter them so they only contain what existed in `self`
+        let next_dirs = self.filter_existing_packages(next_dirs);
 
         // finally, print only those filtered matches in green
-        updated.print_version_matches(&version_update, |s| s.green())
+        self.print_version_matches(&next_dirs, |s| s.green())
     }
 }
 
@@ -108,7 +281,7 @@ impl Increaser {
             if let Some(pkg) = &pkg_and_deps.package {
                 existing_names.insert(pkg.name.clone());
             }
-            for dep in &pkg_and_deps.dependencies {
+            for dep in pkg_and_deps.all_dependencies() {
                 existing_names.insert(dep.name.clone());
             }
         }
@@ -121,12 +294,11 @@ impl Increaser {
                 let pkg_match = pkg_and_deps
                     .package
                     .as_ref()
-                    .map_or(false, |pkg| existing_names.contains(&pkg.name));
+                    .is_some_and(|pkg| existing_names.contains(&pkg.name));
 
                 // do any of the updated deps exist in the original?
                 let deps_match = pkg_and_deps
-                    .dependencies
-                    .iter()
+                    .all_dependencies()
                     .any(|dep| existing_names.contains(&dep.name));
 
                 // if neither the pkg nor any deps match, drop the whole entry
@@ -135,9 +307,7 @@ impl Increaser {
                 }
 
                 // prune out deps that weren’t in the original
-                pkg_and_deps
-                    .dependencies
-                    .retain(|dep| existing_names.contains(&dep.name));
+                pkg_and_deps.retain_dependencies(|dep| existing_names.contains(&dep.name));
 
                 // if the package itself didn't match, drop it too
                 if !pkg_match {
@@ -151,110 +321,849 @@ impl Increaser {
 }
 
 impl Increaser {
+    /// Loads the Cargo.toml files of `dir_path` matching `current_version`, skipping every
+    /// directory named in `excluded_dirs` and reading their packages with `grammar`. Fails
+    /// when the versions are invalid, when `next_version` is not above `current_version` or
+    /// when nothing matches.
     pub fn new(
         dir_path: PathBuf,
         current_version: String,
         next_version: String,
         package_name: Option<String>,
-    ) -> Result<Self> {
-        // Parse versions using semver.
-        let current_ver = Version::parse(&current_version)
-            .expect("Failed to parse the current version as a valid semver string");
-        let new_ver = Version::parse(&next_version)
-            .expect("Failed to parse the new version as a valid semver string");
-
-        // Check for equality.
-        if current_ver == new_ver {
-            eprintln!(
-                "Error: the new version ({}) is the same as the current version ({}). Exiting.",
-                next_version, current_version
-            );
-            std::process::exit(1);
-        }
+        excluded_dirs: &[String],
+        grammar: TomlGrammar,
+    ) -> Result<Self, VersionUpdateError> {
+        check_increase(&current_version, &next_version)?;
+        Self::load(
+            dir_path,
+            current_version,
+            next_version,
+            package_name,
+            None,
+            excluded_dirs,
+            grammar,
+        )
+    }
 
-        let version_update = VersionUpdate {
-            package_name: package_name.as_deref(),
-            current_version: &current_version,
-            new_version: &next_version,
+    /// Same as `new`, without a next version yet: it stays `current_version` until one is
+    /// set with `with_next_version`, e.g. one derived from `bump_prerelease`.
+    pub fn new_matching(
+        dir_path: PathBuf,
+        current_version: String,
+        package_name: Option<String>,
+        excluded_dirs: &[String],
+        grammar: TomlGrammar,
+    ) -> Result<Self, VersionUpdateError> {
+        parse_version("current", &current_version)?;
+        let next_version = current_version.clone();
+        Self::load(
+            dir_path,
+            current_version,
+            next_version,
+            package_name,
+            None,
+            excluded_dirs,
+            grammar,
+        )
+    }
+
+    /// Same as `new`, but bumps every package and dependency whose version satisfies the
+    /// semver requirement `constraint` (e.g. `"^0.4"`) instead of one exact version.
+    /// Matching versions above `next_version` are not rejected, so pick a constraint that
+    /// only covers older versions.
+    pub fn update_with_semver_constraint(
+        dir_path: PathBuf,
+        constraint: &str,
+        next_version: &str,
+        package_name: Option<String>,
+        excluded_dirs: &[String],
+        grammar: TomlGrammar,
+    ) -> Result<Self, VersionUpdateError> {
+        let version_req = VersionReq::parse(constraint).map_err(|e| {
+            VersionUpdateError::ParseError(format!("version constraint '{}': {}", constraint, e))
+        })?;
+        parse_version("next", next_version)?;
+
+        Self::load(
+            dir_path,
+            constraint.to_string(),
+            next_version.to_string(),
+            package_name,
+            Some(version_req),
+            excluded_dirs,
+            grammar,
+        )
+    }
+
+    /// Reads the Cargo.toml files of `dir_path`, outside of `excluded_dirs` and the
+    /// directories skipped by `find_manifests`, and builds the increaser, failing with
+    /// `NoMatchFound` when nothing matches.
+    fn load(
+        dir_path: PathBuf,
+        current_version: String,
+        next_version: String,
+        package_name: Option<String>,
+        version_req: Option<VersionReq>,
+        excluded_dirs: &[String],
+        grammar: TomlGrammar,
+    ) -> Result<Self, VersionUpdateError> {
+        let manifests = find_manifests(&dir_path, excluded_dirs);
+
+        let mut increaser = Self {
+            dir_path,
+            current_version,
+            next_version,
+            package_name,
+            package_dirs: Vec::new(),
+            manifests,
+            backup: false,
+            backups: RefCell::new(Vec::new()),
+            filter_fn: None,
+            match_thresholds: match_thresholds(&Config::default()),
+            version_req,
+            inconsistencies: OnceCell::new(),
+            skip_files: Vec::new(),
+            quiet: false,
+            grammar,
         };
+        increaser.load_packages()?;
+        Ok(increaser)
+    }
+
+    /// Derives `package_dirs` from `manifests`, failing with `NoMatchFound` when nothing
+    /// matches.
+    fn load_packages(&mut self) -> Result<(), VersionUpdateError> {
+        let version_update = self.version_update();
 
         // Load directories and their package/dependency information.
-        let package_dirs = load_dirs_and_packages(&dir_path, &version_update)?;
+        let package_dirs =
+            load_dirs_and_packages(&self.manifests, &version_update, self.filter_fn.as_ref())?;
 
         // Count total package/dependency elements across all directories.
         let total_count: usize = package_dirs.iter()
             .map(|(_, pkg_and_deps)| pkg_and_deps.count())
             .sum();
 
-        if total_count == 0 {
-            std::process::exit(1);
+        // Virtual manifests have no package info, their [workspace.package] version is
+        // looked up separately.
+        let workspace_roots = find_workspace_roots(&self.manifests, &version_update)?;
+        debug!("Found {} matching workspace root(s): {:?}", workspace_roots.len(), workspace_roots);
+
+        if total_count == 0 && workspace_roots.is_empty() {
+            return Err(VersionUpdateError::NoMatchFound);
         }
 
-        Ok(Self {
-            dir_path,
-            current_version,
-            next_version,
-            package_name,
-            package_dirs,
-        })
+        self.package_dirs = package_dirs;
+        self.inconsistencies = OnceCell::new();
+        Ok(())
+    }
+
+    /// The version change of the update, replacing `current_version`, or the versions
+    /// matching `version_req`, with `next_version`.
+    fn version_update(&self) -> VersionUpdate<'_> {
+        VersionUpdate {
+            package_name: self.package_name.as_deref(),
+            current_version: &self.current_version,
+            new_version: &self.next_version,
+            build_metadata_preserved: false,
+            current_version_req: self.version_req.clone(),
+            grammar: self.grammar,
+        }
+    }
+
+    /// Replaces the next version, failing like `new` when it is invalid or not above
+    /// `current_version`.
+    pub fn with_next_version(mut self, next_version: String) -> Result<Self, VersionUpdateError> {
+        check_increase(&self.current_version, &next_version)?;
+        self.next_version = next_version;
+        Ok(self)
+    }
+
+    /// The version being replaced, or the semver constraint matching the replaced ones.
+    pub fn current_version(&self) -> &str {
+        &self.current_version
+    }
+
+    /// The version written by the update.
+    pub fn next_version(&self) -> &str {
+        &self.next_version
+    }
+
+    /// Enables writing a `Cargo.toml.bak` copy of each file before it is updated.
+    pub fn with_backup(mut self, backup: bool) -> Self {
+        self.backup = backup;
+        self
+    }
+
+    /// Stops `print_current_version_matches` and `print_next_version_matches` from
+    /// printing, e.g. when the result is reported as JSON. They still return the matches.
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Sets the match counts at which the per-file match count changes color.
+    pub fn with_match_thresholds(mut self, match_thresholds: MatchThresholds) -> Self {
+        self.match_thresholds = match_thresholds;
+        self
+    }
+
+    /// Returns `current_version` with its pre-release set to `<label>.<number>`, keeping
+    /// the version core and dropping build metadata: `bump_prerelease("alpha", 2)` on
+    /// `"1.0.0-alpha.1"` gives `"1.0.0-alpha.2"`.
+    pub fn bump_prerelease(&self, label: &str, number: u32) -> Result<String> {
+        let mut version = Version::parse(&self.current_version)
+            .with_context(|| format!("Invalid current version '{}'", self.current_version))?;
+        version.pre = Prerelease::new(&format!("{}.{}", label, number))
+            .with_context(|| format!("Invalid pre-release label '{}'", label))?;
+        version.build = BuildMetadata::EMPTY;
+
+        debug!("Bumped pre-release of {} to {}", self.current_version, version);
+        Ok(version.to_string())
+    }
+
+    /// Sets `next_version` to `f(current_version)` instead of the version given to `new`,
+    /// for version policies that do not fit a fixed next version, e.g. a date stamp.
+    /// The result is written as it is, without being checked as semver.
+    pub fn with_version_transformer<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> String + 'static,
+    {
+        self.next_version = f(&self.current_version);
+        debug!(
+            "Version transformer maps {} to {}",
+            self.current_version, self.next_version
+        );
+        self
+    }
+
+    /// Only includes the Cargo.toml files for which `f` returns true. The predicate
+    /// receives the file path and its package/dependency info after version filtering.
+    pub fn with_filter_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Path, &PackageAndDeps) -> bool + 'static,
+    {
+        self.package_dirs
+            .retain(|(file_path, pkg_and_deps)| f(file_path, pkg_and_deps));
+        self.filter_fn = Some(Box::new(f));
+        self
     }
 }
 
-/// Walks through the given directory, finds all Cargo.toml files,
-/// reads their content, and returns a vector of tuples containing the file's path and its package/dependency info.
-fn load_dirs_and_packages(
-    dir_path: &Path,
-    version_update: &VersionUpdate,
-) -> Result<Vec<(PathBuf, PackageAndDeps)>> {
-    let entries = WalkDir::new(dir_path)
-        .into_iter()
-        // Only keep successful directory entries.
-        .filter_map(Result::ok)
-        // Filter for files named "Cargo.toml".
-        .filter(|entry| {
-            entry.file_type().is_file() && entry.file_name() == "Cargo.toml"
+//skip_files
+impl Increaser {
+    /// Leaves alone every Cargo.toml whose path relative to `dir_path` matches one of the
+    /// glob `patterns`, e.g. `templates/*/Cargo.toml`. The skipped files are dropped from
+    /// the loaded ones, so they neither count as matches nor take part in `inconsistencies`.
+    /// Fails on an invalid pattern, or with `NoMatchFound` when only skipped files matched.
+    pub fn with_skip_files(mut self, patterns: Vec<String>) -> Result<Self> {
+        self.skip_files = patterns
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern)
+                    .with_context(|| format!("Invalid skip file pattern '{}'", pattern))
+            })
+            .collect::<Result<_>>()?;
+        debug!("Skipping files matching {:?}", self.skip_files);
+
+        let manifests = std::mem::take(&mut self.manifests);
+        self.manifests = manifests
+            .into_iter()
+            .filter(|file_path| {
+                let skipped = self.is_skipped(file_path);
+                if skipped {
+                    debug!("Skipping {:?}: matches a skipped file pattern", file_path);
+                }
+                !skipped
+            })
+            .collect();
+        self.load_packages()?;
+        Ok(self)
+    }
+
+    /// Only updates the Cargo.toml files listed in `file_paths`, e.g. the results of a
+    /// search. Like `with_skip_files`, the other files neither count as matches nor take
+    /// part in `inconsistencies`. Fails with `NoMatchFound` when none of them matched.
+    pub fn with_only_files(mut self, file_paths: &[PathBuf]) -> Result<Self, VersionUpdateError> {
+        let manifests = std::mem::take(&mut self.manifests);
+        self.manifests = manifests
+            .into_iter()
+            .filter(|file_path| file_paths.contains(file_path))
+            .collect();
+        debug!("Restricted the update to {} file(s)", self.manifests.len());
+        self.load_packages()?;
+        Ok(self)
+    }
+
+    /// Returns true if `file_path` matches one of the `skip_files` patterns.
+    fn is_skipped(&self, file_path: &Path) -> bool {
+        let relative_path = file_path.strip_prefix(&self.dir_path).unwrap_or(file_path);
+        self.skip_files
+            .iter()
+            .any(|pattern| pattern.matches_path(relative_path))
+    }
+}
+
+//preview
+impl Increaser {
+    /// Lists every replacement the update would make without touching any file,
+    /// sorted by file path and then by package name.
+    pub fn preview(&self) -> Result<Vec<VersionChange>> {
+        let mut changes: Vec<VersionChange> = self
+            .package_dirs
+            .iter()
+            .flat_map(|(file_path, pkg_and_deps)| {
+                let package_names = pkg_and_deps.package.iter().map(|pkg| (&pkg.name, &pkg.version));
+                let dep_names = pkg_and_deps
+                    .all_dependencies()
+                    .map(|dep| (&dep.name, &dep.version));
+                package_names
+                    .chain(dep_names)
+                    .map(|(name, version)| VersionChange {
+                        file: file_path.clone(),
+                        package: name.clone(),
+                        from: version.clone(),
+                        to: self.next_version.clone(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        changes.sort_by(|a, b| a.file.cmp(&b.file).then_with(|| a.package.cmp(&b.package)));
+
+        debug!("Preview contains {} change(s)", changes.len());
+        Ok(changes)
+    }
+
+    /// Formats `preview` as a plain table with the columns `File`, `Package`, `From` and `To`,
+    /// each padded to its widest value. File paths are shown relative to `dir_path`.
+    pub fn preview_as_table(&self) -> Result<String> {
+        let header = ["File", "Package", "From", "To"];
+        let rows: Vec<[String; 4]> = self
+            .preview()?
+            .into_iter()
+            .map(|change| {
+                let file = change
+                    .file
+                    .strip_prefix(&self.dir_path)
+                    .unwrap_or(&change.file)
+                    .display()
+                    .to_string();
+                [file, change.package, change.from, change.to]
+            })
+            .collect();
+
+        let mut widths = header.map(str::len);
+        for row in &rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.len());
+            }
+        }
+
+        let format_row = |values: [&str; 4]| {
+            format!(
+                "{:<w0$}  {:<w1$}  {:<w2$}  {}",
+                values[0],
+                values[1],
+                values[2],
+                values[3],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2]
+            )
+        };
+
+        let mut lines = vec![format_row(header)];
+        lines.extend(
+            rows.iter()
+                .map(|row| format_row([&row[0], &row[1], &row[2], &row[3]])),
+        );
+
+        Ok(lines.join("\n"))
+    }
+}
+
+//update_and_diff
+impl Increaser {
+    /// Same as `update_dirs_and_packages_streaming`, but returns for each updated Cargo.toml
+    /// its content before and after the update. Sorted by file path.
+    pub fn update_and_diff(&self) -> Result<Vec<FileDiff>, VersionUpdateError> {
+        let diffs = self.file_diffs()?;
+        self.write_diffs(&diffs)?;
+        Ok(diffs)
+    }
+
+    /// Computes what `update_dirs_and_packages_streaming` would write, without touching any
+    /// file. Sorted by file path.
+    pub fn diff(&self) -> Result<Vec<FileDiff>> {
+        Ok(self.file_diffs()?)
+    }
+
+    /// Writes the updated content of each of `diffs`, as computed by `diff`, backing the
+    /// files up first when enabled. The files are not read again, so what is written is
+    /// exactly what the diffs show.
+    pub fn write_diffs(&self, diffs: &[FileDiff]) -> Result<(), VersionUpdateError> {
+        for diff in diffs {
+            self.write_update(&diff.path, &diff.new)?;
+        }
+        Ok(())
+    }
+
+    /// Updates every loaded Cargo.toml in memory and returns the changed ones.
+    fn file_diffs(&self) -> Result<Vec<FileDiff>, VersionUpdateError> {
+        let mut diffs = Vec::new();
+        for file_path in &self.manifests {
+            let Some((content, updated_source, _)) = self.updated_source(file_path, &|_| {})?
+            else {
+                continue;
+            };
+
+            let diff = FileDiff {
+                path: file_path.clone(),
+                old: content,
+                new: updated_source,
+            };
+            debug!("{:?}: changed lines {:?}", file_path, diff.changed_line_numbers());
+            diffs.push(diff);
+        }
+
+        diffs.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(diffs)
+    }
+}
+
+/// FileDiff
+impl FileDiff {
+    /// 1-based numbers of the lines that differ between `old` and `new`, compared line by
+    /// line. A line only present in one of them counts as changed.
+    pub fn changed_line_numbers(&self) -> Vec<usize> {
+        let old_lines: Vec<&str> = self.old.lines().collect();
+        let new_lines: Vec<&str> = self.new.lines().collect();
+        (0..old_lines.len().max(new_lines.len()))
+            .filter(|&i| old_lines.get(i) != new_lines.get(i))
+            .map(|i| i + 1)
+            .collect()
+    }
+
+    /// Same as `to_string`, with removed lines in red, added lines in green and hunk
+    /// headers in cyan.
+    pub fn to_colored_string(&self) -> String {
+        self.to_string()
+            .lines()
+            .map(|line| match line {
+                _ if line.starts_with("---") || line.starts_with("+++") => line.bold(),
+                _ if line.starts_with("@@") => line.cyan(),
+                _ if line.starts_with('-') => line.red(),
+                _ if line.starts_with('+') => line.green(),
+                _ => line.normal(),
+            }
+            .to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl std::fmt::Display for FileDiff {
+    /// Writes a unified diff with `a/` and `b/` headers, or nothing when `old` and `new`
+    /// have the same lines.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let old_lines: Vec<&str> = self.old.lines().collect();
+        let new_lines: Vec<&str> = self.new.lines().collect();
+        let changes = line_changes(&old_lines, &new_lines);
+        let hunks = hunk_ranges(&changes);
+        if hunks.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f, "--- a/{}", self.path.display())?;
+        writeln!(f, "+++ b/{}", self.path.display())?;
+        for (start, end) in hunks {
+            // Line numbers of the hunk's first line in the old and new content.
+            let old_before = changes[..start]
+                .iter()
+                .filter(|change| !matches!(change, LineChange::Added(_)))
+                .count();
+            let new_before = changes[..start]
+                .iter()
+                .filter(|change| !matches!(change, LineChange::Removed(_)))
+                .count();
+            let hunk = &changes[start..end];
+            let old_len = hunk
+                .iter()
+                .filter(|change| !matches!(change, LineChange::Added(_)))
+                .count();
+            let new_len = hunk
+                .iter()
+                .filter(|change| !matches!(change, LineChange::Removed(_)))
+                .count();
+
+            // An empty range starts at the line before it.
+            let old_start = if old_len == 0 { old_before } else { old_before + 1 };
+            let new_start = if new_len == 0 { new_before } else { new_before + 1 };
+            writeln!(f, "@@ -{},{} +{},{} @@", old_start, old_len, new_start, new_len)?;
+            for change in hunk {
+                match change {
+                    LineChange::Same(line) => writeln!(f, " {}", line)?,
+                    LineChange::Removed(line) => writeln!(f, "-{}", line)?,
+                    LineChange::Added(line) => writeln!(f, "+{}", line)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One line of a diff between two files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineChange<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Diffs `old` against `new` along their longest common subsequence of lines, listing the
+/// removed lines of a change before the added ones.
+fn line_changes<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<LineChange<'a>> {
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            changes.push(LineChange::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            changes.push(LineChange::Removed(old[i]));
+            i += 1;
+        } else {
+            changes.push(LineChange::Added(new[j]));
+            j += 1;
+        }
+    }
+    changes.extend(old[i..].iter().map(|line| LineChange::Removed(line)));
+    changes.extend(new[j..].iter().map(|line| LineChange::Added(line)));
+    changes
+}
+
+/// Groups the changed lines of `changes` into `(start, end)` ranges of `changes`, each
+/// surrounded by up to `DIFF_CONTEXT_LINES` unchanged lines. Ranges that would overlap or
+/// touch are merged.
+fn hunk_ranges(changes: &[LineChange]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (index, _) in changes
+        .iter()
+        .enumerate()
+        .filter(|(_, change)| !matches!(change, LineChange::Same(_)))
+    {
+        let start = index.saturating_sub(DIFF_CONTEXT_LINES);
+        let end = (index + 1 + DIFF_CONTEXT_LINES).min(changes.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
+//update_lock_file
+impl Increaser {
+    /// Replaces the version of the workspace packages of the update in the `Cargo.lock` at
+    /// `lock_path` with `next_version`, writes the file back and returns the number of
+    /// replaced entries. Entries with a `source` come from a registry or git and are kept.
+    pub fn update_lock_file(&self, lock_path: &Path) -> Result<usize> {
+        let names: HashSet<&str> = self
+            .package_dirs
+            .iter()
+            .filter_map(|(_, pkg_and_deps)| pkg_and_deps.package.as_ref())
+            .map(|pkg| pkg.name.as_str())
+            .collect();
+        debug!("Updating {:?} for packages {:?}", lock_path, names);
+
+        let version_update = self.version_update();
+        let content = fs::read_to_string(lock_path)
+            .with_context(|| format!("Failed to read lock file {:?}", lock_path))?;
+        let quoted_value = |line: &str, key: &str| -> Option<String> {
+            let (line_key, value) = line.split_once('=')?;
+            (line_key.trim() == key).then(|| value.trim().trim_matches('"').to_string())
+        };
+
+        // Cargo writes every [[package]] entry as `name`, `version`, then `source` if any.
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let mut updated = String::with_capacity(content.len());
+        let mut replaced = 0;
+        for (i, line) in lines.iter().enumerate() {
+            let previous_name = i
+                .checked_sub(1)
+                .and_then(|previous| quoted_value(lines[previous], "name"));
+            let version = quoted_value(line, "version");
+            let has_source = lines
+                .get(i + 1)
+                .is_some_and(|next| quoted_value(next, "source").is_some());
+
+            match (previous_name, version) {
+                (Some(name), Some(version))
+                    if names.contains(name.as_str())
+                        && !has_source
+                        && version_update.version_matches(&version) =>
+                {
+                    debug!("Updating {} {} in {:?}", name, version, lock_path);
+                    let line_ending = &line[line.trim_end().len()..];
+                    updated.push_str(&format!("version = \"{}\"{}", self.next_version, line_ending));
+                    replaced += 1;
+                }
+                _ => updated.push_str(line),
+            }
+        }
+
+        if replaced > 0 {
+            fs::write(lock_path, updated)
+                .with_context(|| format!("Failed to write lock file {:?}", lock_path))?;
+        }
+        debug!("Replaced {} version(s) in {:?}", replaced, lock_path);
+        Ok(replaced)
+    }
+}
+
+//inconsistencies
+impl Increaser {
+    /// Packages and dependencies of the update that do not have the same version in every
+    /// Cargo.toml under `dir_path`. Updating only the matching ones would leave the others
+    /// behind. Computed on the first call.
+    pub fn inconsistencies(&self) -> &[VersionInconsistency] {
+        self.inconsistencies.get_or_init(|| {
+            let inconsistencies =
+                check_consistency(&self.manifests, &self.package_dirs, self.grammar);
+            debug!("Found {} version inconsistencies", inconsistencies.len());
+            inconsistencies
         })
-        // Map each entry to a Result containing an Option.
-        .map(|entry| {
-            let file_path = entry.path().to_path_buf();
-            fs::read_to_string(&file_path)
-                .with_context(|| format!("Failed to read file {:?}", file_path))
-                .map(|content| {
-                    version_update
-                        .filtered_pkg_and_deps(&content)
-                        .map(|pkg_deps| (file_path, pkg_deps))
-                })
+    }
+
+    /// Formats `inconsistencies` as a plain table with the columns `Package`, `File` and
+    /// `Version`, grouped by package name. File paths are shown relative to `dir_path`.
+    pub fn inconsistencies_as_table(&self) -> String {
+        let header = ["Package", "File", "Version"];
+        let rows: Vec<[String; 3]> = self
+            .inconsistencies()
+            .iter()
+            .flat_map(|inconsistency| {
+                inconsistency
+                    .versions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (file, version))| {
+                        // Only the first row of a group names the package.
+                        let package = if i == 0 {
+                            inconsistency.package.clone()
+                        } else {
+                            String::new()
+                        };
+                        let file = file
+                            .strip_prefix(&self.dir_path)
+                            .unwrap_or(file)
+                            .display()
+                            .to_string();
+                        [package, file, version.clone()]
+                    })
+            })
+            .collect();
+
+        let mut widths = header.map(str::len);
+        for row in &rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.len());
+            }
+        }
+
+        let format_row = |values: [&str; 3]| {
+            format!(
+                "{:<w0$}  {:<w1$}  {}",
+                values[0],
+                values[1],
+                values[2],
+                w0 = widths[0],
+                w1 = widths[1]
+            )
+        };
+
+        let mut lines = vec![format_row(header)];
+        lines.extend(rows.iter().map(|row| format_row([&row[0], &row[1], &row[2]])));
+        lines.join("\n")
+    }
+}
+
+/// Returns the names of `package_dirs`, packages and dependencies alike, that appear with
+/// more than one version in `manifests`, read with `grammar`, sorted by name. Files that
+/// cannot be read or parsed are left out with a warning.
+fn check_consistency(
+    manifests: &[PathBuf],
+    package_dirs: &[(PathBuf, PackageAndDeps)],
+    grammar: TomlGrammar,
+) -> Vec<VersionInconsistency> {
+    let names: HashSet<&str> = package_dirs
+        .iter()
+        .flat_map(|(_, pkg_and_deps)| {
+            let package_name = pkg_and_deps.package.iter().map(|pkg| pkg.name.as_str());
+            let dep_names = pkg_and_deps.all_dependencies().map(|dep| dep.name.as_str());
+            package_name.chain(dep_names)
         })
-        // Collect into a Result containing a vector of Option values.
-        .collect::<Result<Vec<Option<(PathBuf, PackageAndDeps)>>, _>>()?
-        // Filter out `None` values.
+        .collect();
+
+    let mut versions: BTreeMap<String, Vec<(PathBuf, String)>> = BTreeMap::new();
+    for manifest in read_manifests(manifests) {
+        let (file_path, content) = match manifest {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                warn!("Skipping a file in the consistency check: {}", e);
+                continue;
+            }
+        };
+        let parser = match TomlParser::new_with_grammar(&content, grammar) {
+            Ok(parser) => parser,
+            Err(e) => {
+                warn!("Skipping {:?} in the consistency check: {}", file_path, e);
+                continue;
+            }
+        };
+        let Some(pkg_and_deps) = parser.find_package_and_deps() else {
+            continue;
+        };
+        let package = pkg_and_deps.package.map(|(_, pkg)| (pkg.name, pkg.version));
+        let deps = pkg_and_deps
+            .dependencies
+            .into_values()
+            .chain(pkg_and_deps.dev_dependencies.into_values())
+            .chain(pkg_and_deps.build_dependencies.into_values())
+            .chain(pkg_and_deps.target_dependencies.into_values().map(|(_, dep)| dep))
+            .map(|dep| (dep.name, dep.version));
+        for (name, version) in package.into_iter().chain(deps) {
+            if names.contains(name.as_str()) {
+                versions
+                    .entry(name)
+                    .or_default()
+                    .push((file_path.to_path_buf(), version));
+            }
+        }
+    }
+
+    versions
         .into_iter()
-        .flatten()
-        // Now filter out any paths that contain "target/release" or "target/debug".
-        .filter(|(file_path, _)| {
-            let path_str = file_path.to_string_lossy();
-            !(path_str.contains("target/release") || path_str.contains("target/debug"))
+        .filter(|(_, found)| {
+            let distinct: HashSet<&String> = found.iter().map(|(_, version)| version).collect();
+            distinct.len() > 1
         })
-        .collect();
+        .map(|(package, mut found)| {
+            found.sort();
+            debug!("Inconsistent versions of {}: {:?}", package, found);
+            VersionInconsistency {
+                package,
+                versions: found,
+            }
+        })
+        .collect()
+}
 
-    Ok(entries)
+/// Reads the content of each Cargo.toml of `file_paths`, one file at a time as the
+/// returned iterator is advanced.
+fn read_manifests(
+    file_paths: &[PathBuf],
+) -> impl Iterator<Item = Result<(&Path, String), VersionUpdateError>> {
+    file_paths.iter().map(|file_path| {
+        let content = fs::read_to_string(file_path).map_err(VersionUpdateError::io(file_path))?;
+        Ok((file_path.as_path(), content))
+    })
+}
+
+/// Parses `version` as semver, naming it `which` (e.g. "next") in the error.
+fn parse_version(which: &str, version: &str) -> Result<Version, VersionUpdateError> {
+    Version::parse(version)
+        .map_err(|e| VersionUpdateError::ParseError(format!("{} version '{}': {}", which, version, e)))
+}
+
+/// Fails when either version is invalid, or when `next_version` is not above
+/// `current_version`.
+fn check_increase(current_version: &str, next_version: &str) -> Result<(), VersionUpdateError> {
+    let current_ver = parse_version("current", current_version)?;
+    let new_ver = parse_version("next", next_version)?;
+
+    // Check for equality and downgrades.
+    if current_ver == new_ver {
+        return Err(VersionUpdateError::VersionIdentical(current_version.to_string()));
+    }
+    if new_ver < current_ver {
+        return Err(VersionUpdateError::VersionDowngrade {
+            current: current_version.to_string(),
+            proposed: next_version.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns the path and package/dependency info of every Cargo.toml of `manifests` that
+/// `version_update` would change. When `filter_fn` is given, only entries it accepts are
+/// returned. Only the package/dependency info of each file is kept.
+fn load_dirs_and_packages(
+    manifests: &[PathBuf],
+    version_update: &VersionUpdate,
+    filter_fn: Option<&FileFilter>,
+) -> Result<Vec<(PathBuf, PackageAndDeps)>, VersionUpdateError> {
+    let mut package_dirs = Vec::new();
+    for manifest in read_manifests(manifests) {
+        let (file_path, content) = manifest?;
+        let Some(pkg_and_deps) = version_update.filtered_pkg_and_deps(&content) else {
+            continue;
+        };
+        // Apply the custom filter, if any.
+        if filter_fn.is_none_or(|filter_fn| filter_fn(file_path, &pkg_and_deps)) {
+            package_dirs.push((file_path.to_path_buf(), pkg_and_deps));
+        }
+    }
+    Ok(package_dirs)
+}
+
+/// Returns the Cargo.toml files of `manifests` whose `[workspace.package]` version would
+/// be changed by `version_update`.
+fn find_workspace_roots(
+    manifests: &[PathBuf],
+    version_update: &VersionUpdate,
+) -> Result<Vec<PathBuf>, VersionUpdateError> {
+    let mut workspace_roots = Vec::new();
+    for manifest in read_manifests(manifests) {
+        let (file_path, content) = manifest?;
+        if TomlParser::new_with_grammar(&content, version_update.grammar)
+            .is_ok_and(|parser| parser.find_workspace().is_some())
+            && version_update.update_workspace_version(&content).is_some()
+        {
+            workspace_roots.push(file_path.to_path_buf());
+        }
+    }
+    Ok(workspace_roots)
 }
 
 //print_version_matches
 impl Increaser {
-    /// Generic method to print version matches with a specified color function
-    /// and a provided version update.
+    /// Generic method to print version matches of the given package directories
+    /// with a specified color function.
     fn print_version_matches<F>(
         &self,
-        _version_update: &VersionUpdate,
+        package_dirs: &[(PathBuf, PackageAndDeps)],
         color_version: F,
     ) -> Result<Vec<VersionMatch>>
     where
         F: Fn(&str) -> ColoredString + Copy,
     {
         // Using iterator combinators to process package_dirs.
-        let version_matches: Vec<VersionMatch> = self.package_dirs.iter()
-            .filter_map(|(file_path, pkg_deps)| {
+        let version_matches: Vec<VersionMatch> = package_dirs.iter()
+            .map(|(file_path, pkg_deps)| {
                 debug!("Found package info in file {:?}:", file_path);
                 if let Some(ref pkg) = pkg_deps.package {
                     debug!("{:?}\n", pkg);
@@ -267,24 +1176,912 @@ impl Increaser {
                 }
 
                 // Create a new VersionMatch using the constructor.
-                let version_match = VersionMatch::new(file_path.clone(), pkg_deps.clone());
-
-                // Skip printing and adding if there are no matches.
-                if version_match.matches == 0 {
-                    return None;
-                }
-
-                // Use the provided color function to colorize output.
-                let colored_dir_path = get_colored_dir_path_and_matches(&version_match, &self.dir_path);
-                let colored_pkg_deps = get_colored_pkg_deps(&version_match.pkg_deps, color_version);
-                println!("{}", colored_dir_path);
-                println!("{}", colored_pkg_deps);
-
-                Some(version_match)
+                VersionMatch::new(file_path.clone(), pkg_deps.clone(), &self.dir_path)
             })
+            // Skip printing and adding if there are no matches.
+            .filter(|version_match| version_match.matches > 0)
             .collect();
 
+        // A file listed more than once is shown, and counted, only once.
+        let version_matches = VersionMatch::merge_matches(version_matches);
+        if self.quiet {
+            return Ok(version_matches);
+        }
+        for version_match in &version_matches {
+            // Use the provided color function to colorize output.
+            let colored_dir_path =
+                get_colored_dir_path_and_matches(version_match, &self.match_thresholds);
+            let colored_pkg_deps = get_colored_pkg_deps(&version_match.pkg_deps, color_version);
+            println!("{}", colored_dir_path);
+            println!("{}", colored_pkg_deps);
+        }
+
         Ok(version_matches)
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRATE_A: &str = r#"[package]
+name = "crate_a"
+version = "0.4.1"
+"#;
+
+    const CRATE_B: &str = r#"[package]
+name = "crate_b"
+version = "0.1.0"
+
+[dependencies]
+crate_a = { version = "0.4.1", path = "../crate_a" }
+"#;
+
+    fn write_fixture(root: &Path) {
+        for (dir, content) in [("crate_a", CRATE_A), ("crate_b", CRATE_B)] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("Cargo.toml"), content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rollback_after_mid_run_failure() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        // A directory in place of the backup file makes creating that backup fail.
+        fs::create_dir(tmp.path().join("crate_b").join("Cargo.toml.bak")).unwrap();
+
+        let increaser = Increaser::new(
+            tmp.path().to_path_buf(),
+            "0.4.1".to_string(),
+            "0.4.2".to_string(),
+            None,
+            &[],
+            TomlGrammar::Toml05,
+        )
+        .unwrap()
+        .with_backup(true);
+
+        assert!(update_all(&increaser).is_err());
+        increaser.rollback().unwrap();
+
+        let read = |dir: &str| fs::read_to_string(tmp.path().join(dir).join("Cargo.toml")).unwrap();
+        assert_eq!(read("crate_a"), CRATE_A);
+        assert_eq!(read("crate_b"), CRATE_B);
+        assert!(!tmp.path().join("crate_a").join("Cargo.toml.bak").exists());
+    }
+
+    #[test]
+    fn test_rollback_restores_and_counts_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+
+        let increaser = Increaser::new(
+            tmp.path().to_path_buf(),
+            "0.4.1".to_string(),
+            "0.4.2".to_string(),
+            None,
+            &[],
+            TomlGrammar::Toml05,
+        )
+        .unwrap()
+        .with_backup(true);
+
+        update_all(&increaser).unwrap();
+        let updated = fs::read_to_string(tmp.path().join("crate_b").join("Cargo.toml")).unwrap();
+        assert!(updated.contains("\"0.4.2\""));
+
+        assert_eq!(increaser.rollback().unwrap(), 2);
+        let restored = fs::read_to_string(tmp.path().join("crate_b").join("Cargo.toml")).unwrap();
+        assert_eq!(restored, CRATE_B);
+        assert_eq!(increaser.rollback().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_remove_backups_keeps_earlier_backups() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        fs::create_dir_all(tmp.path().join("crate_c")).unwrap();
+        fs::write(tmp.path().join("crate_c").join("Cargo.toml.bak"), "earlier").unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap().with_backup(true);
+        update_all(&increaser).unwrap();
+
+        assert_eq!(increaser.remove_backups().unwrap(), 2);
+        assert!(!tmp.path().join("crate_a").join("Cargo.toml.bak").exists());
+        assert!(!tmp.path().join("crate_b").join("Cargo.toml.bak").exists());
+        assert_eq!(increaser.rollback().unwrap(), 0);
+        let earlier = fs::read_to_string(tmp.path().join("crate_c").join("Cargo.toml.bak")).unwrap();
+        assert_eq!(earlier, "earlier");
+    }
+
+    fn new_increaser(root: &Path, current: &str, next: &str) -> Result<Increaser, VersionUpdateError> {
+        Increaser::new(
+            root.to_path_buf(),
+            current.to_string(),
+            next.to_string(),
+            None,
+            &[],
+            TomlGrammar::Toml05,
+        )
+    }
+
+    /// Consumes the whole update stream, stopping at the first error.
+    fn update_all(increaser: &Increaser) -> Result<Vec<(PathBuf, PackageAndDeps)>, VersionUpdateError> {
+        increaser.update_dirs_and_packages_streaming().collect()
+    }
+
+    #[test]
+    fn test_new_skips_empty_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        fs::create_dir_all(tmp.path().join("empty")).unwrap();
+        fs::write(tmp.path().join("empty").join("Cargo.toml"), "").unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap();
+        assert_eq!(update_all(&increaser).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let err = new_increaser(tmp.path(), "not.a.version", "0.4.2").err().unwrap();
+        assert!(matches!(err, VersionUpdateError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_new_rejects_identical_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let err = new_increaser(tmp.path(), "0.4.1", "0.4.1").err().unwrap();
+        assert!(matches!(err, VersionUpdateError::VersionIdentical(ref v) if v == "0.4.1"));
+    }
+
+    #[test]
+    fn test_new_rejects_downgrade() {
+        let tmp = tempfile::tempdir().unwrap();
+        let err = new_increaser(tmp.path(), "0.4.1", "0.3.0").err().unwrap();
+        assert!(matches!(
+            err,
+            VersionUpdateError::VersionDowngrade { ref current, ref proposed }
+                if current == "0.4.1" && proposed == "0.3.0"
+        ));
+    }
+
+    #[test]
+    fn test_new_reports_no_match() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        let err = new_increaser(tmp.path(), "9.9.9", "10.0.0").err().unwrap();
+        assert!(matches!(err, VersionUpdateError::NoMatchFound));
+    }
+
+    #[test]
+    fn test_update_reports_io_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        fs::create_dir(tmp.path().join("crate_a").join("Cargo.toml.bak")).unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap().with_backup(true);
+        let err = update_all(&increaser).err().unwrap();
+        assert!(matches!(
+            err,
+            VersionUpdateError::IoError { ref path, .. } if path.ends_with("Cargo.toml.bak")
+        ));
+    }
+
+    const PRERELEASE_CRATE: &str = r#"[package]
+name = "crate_pre"
+version = "1.0.0-alpha.1"
+"#;
+
+    fn prerelease_increaser(root: &Path, next: &str) -> Increaser {
+        fs::create_dir_all(root.join("crate_pre")).unwrap();
+        fs::write(root.join("crate_pre").join("Cargo.toml"), PRERELEASE_CRATE).unwrap();
+        new_increaser(root, "1.0.0-alpha.1", next).unwrap()
+    }
+
+    #[test]
+    fn test_bump_prerelease_alpha_to_alpha() {
+        let tmp = tempfile::tempdir().unwrap();
+        let increaser = prerelease_increaser(tmp.path(), "1.0.0-alpha.2");
+
+        assert_eq!(increaser.bump_prerelease("alpha", 2).unwrap(), "1.0.0-alpha.2");
+        assert_eq!(increaser.bump_prerelease("rc", 1).unwrap(), "1.0.0-rc.1");
+        assert!(increaser.bump_prerelease("not valid", 1).is_err());
+
+        update_all(&increaser).unwrap();
+        let updated = fs::read_to_string(tmp.path().join("crate_pre").join("Cargo.toml")).unwrap();
+        assert!(updated.contains(r#"version = "1.0.0-alpha.2""#));
+    }
+
+    #[test]
+    fn test_new_matching_with_next_version_from_prerelease() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("crate_pre")).unwrap();
+        fs::write(tmp.path().join("crate_pre").join("Cargo.toml"), PRERELEASE_CRATE).unwrap();
+        let increaser =
+            Increaser::new_matching(
+                tmp.path().to_path_buf(),
+                "1.0.0-alpha.1".to_string(),
+                None,
+                &[],
+                TomlGrammar::Toml05,
+            )
+                .unwrap();
+        assert_eq!(increaser.next_version(), "1.0.0-alpha.1");
+
+        let downgrade = increaser.bump_prerelease("alpha", 0).unwrap();
+        assert!(matches!(
+            Increaser::new_matching(
+                tmp.path().to_path_buf(),
+                "1.0.0-alpha.1".to_string(),
+                None,
+                &[],
+                TomlGrammar::Toml05,
+            )
+                .unwrap()
+                .with_next_version(downgrade),
+            Err(VersionUpdateError::VersionDowngrade { .. })
+        ));
+
+        let next_version = increaser.bump_prerelease("beta", 1).unwrap();
+        let increaser = increaser.with_next_version(next_version).unwrap();
+        assert_eq!(increaser.next_version(), "1.0.0-beta.1");
+        update_all(&increaser).unwrap();
+        let updated = fs::read_to_string(tmp.path().join("crate_pre").join("Cargo.toml")).unwrap();
+        assert!(updated.contains(r#"version = "1.0.0-beta.1""#));
+    }
+
+    #[test]
+    fn test_prerelease_to_stable() {
+        let tmp = tempfile::tempdir().unwrap();
+        let increaser = prerelease_increaser(tmp.path(), "1.0.0");
+
+        update_all(&increaser).unwrap();
+        let updated = fs::read_to_string(tmp.path().join("crate_pre").join("Cargo.toml")).unwrap();
+        assert!(updated.contains(r#"version = "1.0.0"
+"#));
+
+        let rc = VersionUpdate {
+            package_name: None,
+            current_version: "1.0.0-rc.2",
+            new_version: "1.0.0",
+            build_metadata_preserved: false,
+            current_version_req: None,
+            grammar: TomlGrammar::Toml05,
+        };
+        assert_eq!(rc.strip_prerelease_for_stable(), "1.0.0");
+    }
+
+    #[test]
+    fn test_new_with_toml10_updates_dotted_key_package() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = tmp.path().join("dotted").join("Cargo.toml");
+        fs::create_dir_all(manifest.parent().unwrap()).unwrap();
+        fs::write(&manifest, "package.name = \"dotted\"\npackage.version = \"0.4.1\"\n").unwrap();
+        let new = |grammar| {
+            Increaser::new(
+                tmp.path().to_path_buf(),
+                "0.4.1".to_string(),
+                "0.4.2".to_string(),
+                None,
+                &[],
+                grammar,
+            )
+        };
+
+        assert!(matches!(new(TomlGrammar::Toml05), Err(VersionUpdateError::NoMatchFound)));
+
+        let increaser = new(TomlGrammar::Toml10).unwrap();
+        assert_eq!(update_all(&increaser).unwrap().len(), 1);
+        assert_eq!(
+            fs::read_to_string(&manifest).unwrap(),
+            "package.name = \"dotted\"\npackage.version = \"0.4.2\"\n"
+        );
+    }
+
+    #[test]
+    fn test_update_with_semver_constraint() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = r#"[package]
+name = "crate_c"
+version = "0.4.0"
+
+[dependencies]
+dep_a = { version = "0.4.3" }
+dep_b = { version = "0.5.0" }
+"#;
+        for dir in ["crate_c", "vendored/crate_c"] {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+            fs::write(tmp.path().join(dir).join("Cargo.toml"), manifest).unwrap();
+        }
+
+        let increaser = Increaser::update_with_semver_constraint(
+            tmp.path().to_path_buf(),
+            "^0.4",
+            "0.6.0",
+            None,
+            &["vendored".to_string()],
+            TomlGrammar::Toml05,
+        )
+        .unwrap();
+        update_all(&increaser).unwrap();
+
+        let updated = fs::read_to_string(tmp.path().join("crate_c").join("Cargo.toml")).unwrap();
+        assert_eq!(
+            updated,
+            manifest
+                .replace("\"0.4.0\"", "\"0.6.0\"")
+                .replace("\"0.4.3\"", "\"0.6.0\"")
+        );
+        let excluded =
+            fs::read_to_string(tmp.path().join("vendored/crate_c").join("Cargo.toml")).unwrap();
+        assert_eq!(excluded, manifest);
+    }
+
+    #[test]
+    fn test_update_with_semver_constraint_rejects_invalid_constraint() {
+        let tmp = tempfile::tempdir().unwrap();
+        let err = Increaser::update_with_semver_constraint(
+            tmp.path().to_path_buf(),
+            "not a constraint",
+            "0.6.0",
+            None,
+            &[],
+            TomlGrammar::Toml05,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(err, VersionUpdateError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_with_filter_fn_rejects_large_crates() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        fs::create_dir_all(tmp.path().join("crate_c")).unwrap();
+        fs::write(
+            tmp.path().join("crate_c").join("Cargo.toml"),
+            r#"[package]
+name = "crate_c"
+version = "0.1.0"
+
+[dependencies]
+dep_1 = { version = "0.4.1" }
+dep_2 = { version = "0.4.1" }
+dep_3 = { version = "0.4.1" }
+"#,
+        )
+        .unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2")
+            .unwrap()
+            .with_filter_fn(|_, pkg_and_deps| pkg_and_deps.dependencies.len() <= 2);
+
+        let mut names: Vec<String> = increaser
+            .package_dirs
+            .iter()
+            .filter_map(|(path, _)| path.parent()?.file_name()?.to_str().map(str::to_string))
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["crate_a", "crate_b"]);
+
+        update_all(&increaser).unwrap();
+        let crate_c = fs::read_to_string(tmp.path().join("crate_c").join("Cargo.toml")).unwrap();
+        assert!(!crate_c.contains("0.4.2"));
+        let crate_b = fs::read_to_string(tmp.path().join("crate_b").join("Cargo.toml")).unwrap();
+        assert!(crate_b.contains("0.4.2"));
+    }
+
+    #[test]
+    fn test_preview_as_table_alignment() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        let nested = tmp.path().join("nested").join("deeper").join("crate_d");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(
+            nested.join("Cargo.toml"),
+            "[package]\nname = \"crate_d\"\nversion = \"0.4.1\"\n",
+        )
+        .unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.10.0").unwrap();
+
+        let table = increaser.preview_as_table().unwrap();
+        let expected = "\
+File                              Package  From   To
+crate_a/Cargo.toml                crate_a  0.4.1  0.10.0
+crate_b/Cargo.toml                crate_a  0.4.1  0.10.0
+nested/deeper/crate_d/Cargo.toml  crate_d  0.4.1  0.10.0";
+        assert_eq!(table, expected);
+
+        // Nothing was written.
+        let crate_a = fs::read_to_string(tmp.path().join("crate_a").join("Cargo.toml")).unwrap();
+        assert_eq!(crate_a, CRATE_A);
+    }
+
+    #[test]
+    fn test_with_version_transformer_sets_next_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2")
+            .unwrap()
+            .with_version_transformer(|_| "SNAPSHOT".to_string());
+        let updated = update_all(&increaser).unwrap();
+
+        assert_eq!(updated.len(), 2);
+        let read = |dir: &str| fs::read_to_string(tmp.path().join(dir).join("Cargo.toml")).unwrap();
+        assert_eq!(read("crate_a"), CRATE_A.replace("0.4.1", "SNAPSHOT"));
+        assert_eq!(read("crate_b"), CRATE_B.replace("0.4.1", "SNAPSHOT"));
+    }
+
+    #[test]
+    fn test_update_and_diff_reports_only_changed_lines() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        let crate_c = r#"[package]
+name = "crate_c"
+version = "0.2.0"
+edition = "2021"
+
+[dependencies]
+serde = "1.0"
+crate_a = { version = "0.4.1", path = "../crate_a" }
+log = "0.4.1"
+
+[features]
+default = []
+"#;
+        fs::create_dir_all(tmp.path().join("crate_c")).unwrap();
+        fs::write(tmp.path().join("crate_c").join("Cargo.toml"), crate_c).unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap();
+        let diffs = increaser.update_and_diff().unwrap();
+
+        let paths: Vec<PathBuf> = diffs.iter().map(|diff| diff.path.clone()).collect();
+        let expected_paths: Vec<PathBuf> = ["crate_a", "crate_b", "crate_c"]
+            .iter()
+            .map(|dir| tmp.path().join(dir).join("Cargo.toml"))
+            .collect();
+        assert_eq!(paths, expected_paths);
+
+        let diff_c = &diffs[2];
+        assert_eq!(diff_c.changed_line_numbers(), vec![8, 9]);
+        let old_lines: Vec<&str> = diff_c.old.lines().collect();
+        let new_lines: Vec<&str> = diff_c.new.lines().collect();
+        assert_eq!(old_lines[7], r#"crate_a = { version = "0.4.1", path = "../crate_a" }"#);
+        assert_eq!(new_lines[7], r#"crate_a = { version = "0.4.2", path = "../crate_a" }"#);
+        assert_eq!(new_lines[6], r#"serde = "1.0""#);
+        assert_eq!(new_lines[8], r#"log = "0.4.2""#);
+        assert_eq!(diffs[0].changed_line_numbers(), vec![3]);
+
+        let written = fs::read_to_string(tmp.path().join("crate_c").join("Cargo.toml")).unwrap();
+        assert_eq!(written, diff_c.new);
+    }
+
+    #[test]
+    fn test_write_diffs_writes_the_computed_content() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap().with_backup(true);
+        let diffs = increaser.diff().unwrap();
+        // Changed after the diff was shown, the file still gets the content of the diff.
+        fs::write(tmp.path().join("crate_a").join("Cargo.toml"), "changed").unwrap();
+        increaser.write_diffs(&diffs).unwrap();
+
+        for diff in &diffs {
+            assert_eq!(fs::read_to_string(&diff.path).unwrap(), diff.new);
+        }
+        let backup = fs::read_to_string(tmp.path().join("crate_a").join("Cargo.toml.bak")).unwrap();
+        assert_eq!(backup, "changed");
+    }
+
+    #[test]
+    fn test_diff_writes_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap();
+        let diffs = increaser.diff().unwrap();
+
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].old, CRATE_A);
+        assert_eq!(diffs[0].new, CRATE_A.replace("0.4.1", "0.4.2"));
+        let crate_a = fs::read_to_string(tmp.path().join("crate_a").join("Cargo.toml")).unwrap();
+        assert_eq!(crate_a, CRATE_A);
+    }
+
+    #[test]
+    fn test_file_diff_display_unified() {
+        let diff = FileDiff {
+            path: PathBuf::from("app/Cargo.toml"),
+            old: "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nserde = \"1.0\"\nlog = \"0.4\"\n\n[features]\ndefault = []\n".to_string(),
+            new: "[package]\nname = \"app\"\nversion = \"0.1.1\"\nedition = \"2021\"\n\n[dependencies]\nserde = \"1.0\"\nlog = \"0.4\"\n\n[features]\ndefault = [\"log\"]\n".to_string(),
+        };
+
+        let expected = "--- a/app/Cargo.toml
++++ b/app/Cargo.toml
+@@ -1,6 +1,6 @@
+ [package]
+ name = \"app\"
+-version = \"0.1.0\"
++version = \"0.1.1\"
+ edition = \"2021\"
+ 
+ [dependencies]
+@@ -8,4 +8,4 @@
+ log = \"0.4\"
+ 
+ [features]
+-default = []
++default = [\"log\"]
+";
+        assert_eq!(diff.to_string(), expected);
+    }
+
+    #[test]
+    fn test_file_diff_display_added_lines_and_no_change() {
+        let diff = FileDiff {
+            path: PathBuf::from("Cargo.toml"),
+            old: "a\nb\n".to_string(),
+            new: "a\nb\nc\n".to_string(),
+        };
+        assert_eq!(
+            diff.to_string(),
+            "--- a/Cargo.toml\n+++ b/Cargo.toml\n@@ -1,2 +1,3 @@\n a\n b\n+c\n"
+        );
+
+        let unchanged = FileDiff {
+            path: PathBuf::from("Cargo.toml"),
+            old: "a\n".to_string(),
+            new: "a\n".to_string(),
+        };
+        assert_eq!(unchanged.to_string(), "");
+    }
+
+    #[test]
+    fn test_update_bumps_every_dependency_table() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nmy-helper = { version = \"0.3.0\" }\n\n[dev-dependencies]\nmy-helper = { version = \"0.3.0\" }\n\n[build-dependencies]\nmy-helper = { version = \"0.3.0\" }\n\n[target.'cfg(unix)'.dependencies]\nmy-helper = { version = \"0.3.0\" }\n";
+        fs::create_dir_all(tmp.path().join("app")).unwrap();
+        fs::write(tmp.path().join("app").join("Cargo.toml"), manifest).unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.3.0", "0.4.0").unwrap();
+        let updated = update_all(&increaser).unwrap();
+
+        assert_eq!(updated.len(), 1);
+        let content = fs::read_to_string(tmp.path().join("app").join("Cargo.toml")).unwrap();
+        assert_eq!(content, manifest.replace("0.3.0", "0.4.0"));
+    }
+
+    #[test]
+    fn test_streaming_writes_one_file_per_step() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        let read = |dir: &str| fs::read_to_string(tmp.path().join(dir).join("Cargo.toml")).unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap();
+        let mut expected: Vec<PathBuf> =
+            increaser.diff().unwrap().into_iter().map(|diff| diff.path).collect();
+        let mut stream = increaser.update_dirs_and_packages_streaming();
+        // Nothing is written before the iterator is advanced.
+        assert_eq!((read("crate_a"), read("crate_b")), (CRATE_A.to_string(), CRATE_B.to_string()));
+
+        let (first, _) = stream.next().unwrap().unwrap();
+        let written = [read("crate_a") != CRATE_A, read("crate_b") != CRATE_B];
+        assert_eq!(written.iter().filter(|&&written| written).count(), 1);
+
+        let mut streamed = vec![first];
+        streamed.extend(stream.map(|updated| updated.unwrap().0));
+        streamed.sort();
+        expected.sort();
+        assert_eq!(expected.len(), 2);
+        assert_eq!(streamed, expected);
+    }
+
+    #[test]
+    fn test_update_workspace_root_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        let root = "[workspace]\nmembers = [\"crate_a\", \"crate_b\", \"member\"]\n\n[workspace.package]\nversion = \"0.4.1\"\n";
+        let member = "[package]\nname = \"member\"\nversion.workspace = true\n";
+        fs::write(tmp.path().join("Cargo.toml"), root).unwrap();
+        fs::create_dir_all(tmp.path().join("member")).unwrap();
+        fs::write(tmp.path().join("member").join("Cargo.toml"), member).unwrap();
+
+        let updated: Vec<PathBuf> = new_increaser(tmp.path(), "0.4.1", "0.4.2")
+            .unwrap()
+            .update_dirs_and_packages_streaming()
+            .map(|updated| updated.unwrap().0)
+            .collect();
+
+        assert!(updated.contains(&tmp.path().join("Cargo.toml")));
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("Cargo.toml")).unwrap(),
+            root.replace("0.4.1", "0.4.2")
+        );
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("member").join("Cargo.toml")).unwrap(),
+            member
+        );
+    }
+
+    #[test]
+    fn test_update_workspace_root_version_and_dependencies() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        let root = "[package]\nname = \"root\"\nversion.workspace = true\n\n[workspace]\nmembers = [\"crate_a\", \"crate_b\"]\n\n[workspace.package]\nversion = \"0.4.1\"\n\n[dependencies]\ncrate_a = { version = \"0.4.1\", path = \"crate_a\" }\nserde = \"1.0\"\n";
+        fs::write(tmp.path().join("Cargo.toml"), root).unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap();
+        let root_matches = increaser
+            .package_dirs
+            .iter()
+            .find(|(path, _)| *path == tmp.path().join("Cargo.toml"))
+            .map(|(_, pkg_and_deps)| pkg_and_deps.count());
+        assert_eq!(root_matches, Some(1));
+        update_all(&increaser).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("Cargo.toml")).unwrap(),
+            root.replace("0.4.1", "0.4.2")
+        );
+    }
+
+    #[test]
+    fn test_new_ignores_workspace_roots_in_skipped_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = "[workspace]\nmembers = []\n\n[workspace.package]\nversion = \"0.4.1\"\n";
+        for dir in ["target/package/ws", "vendor/ws", "scratch"] {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+            fs::write(tmp.path().join(dir).join("Cargo.toml"), root).unwrap();
+        }
+
+        let err = Increaser::new(
+            tmp.path().to_path_buf(),
+            "0.4.1".to_string(),
+            "0.4.2".to_string(),
+            None,
+            &["scratch".to_string()],
+            TomlGrammar::Toml05,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(err, VersionUpdateError::NoMatchFound));
+    }
+
+    #[test]
+    fn test_update_virtual_workspace_only_modifies_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = "[workspace]\nmembers = [\"app\", \"core\", \"util\"]\n\n[workspace.package]\nversion = \"0.4.1\"\nedition = \"2021\"\n";
+        fs::write(tmp.path().join("Cargo.toml"), root).unwrap();
+        let members = [
+            ("app", "[package]\nname = \"app\"\nversion.workspace = true\n\n[dependencies]\ncore = { path = \"../core\" }\n"),
+            ("core", "[package]\nname = \"core\"\nversion.workspace = true\nedition.workspace = true\n"),
+            ("util", "[package]\nname = \"util\"\nversion = { workspace = true }\n"),
+        ];
+        for (dir, content) in members {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+            fs::write(tmp.path().join(dir).join("Cargo.toml"), content).unwrap();
+        }
+
+        let updated: Vec<PathBuf> = new_increaser(tmp.path(), "0.4.1", "0.4.2")
+            .unwrap()
+            .update_dirs_and_packages_streaming()
+            .map(|updated| updated.unwrap().0)
+            .collect();
+
+        assert_eq!(updated, vec![tmp.path().join("Cargo.toml")]);
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("Cargo.toml")).unwrap(),
+            root.replace("0.4.1", "0.4.2")
+        );
+        for (dir, content) in members {
+            assert_eq!(fs::read_to_string(tmp.path().join(dir).join("Cargo.toml")).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_with_skip_files_skips_matching_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2")
+            .unwrap()
+            .with_skip_files(vec!["crate_b/Cargo.toml".to_string()])
+            .unwrap();
+        assert!(increaser.package_dirs.iter().all(|(path, _)| !path.ends_with("crate_b/Cargo.toml")));
+        let updated: Vec<PathBuf> = increaser
+            .update_dirs_and_packages_streaming()
+            .map(|updated| updated.unwrap().0)
+            .collect();
+
+        assert_eq!(updated, vec![tmp.path().join("crate_a").join("Cargo.toml")]);
+        assert_eq!(fs::read_to_string(tmp.path().join("crate_b").join("Cargo.toml")).unwrap(), CRATE_B);
+    }
+
+    #[test]
+    fn test_with_skip_files_non_matching_glob() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+
+        let mut updated: Vec<PathBuf> = new_increaser(tmp.path(), "0.4.1", "0.4.2")
+            .unwrap()
+            .with_skip_files(vec!["templates/*/Cargo.toml".to_string()])
+            .unwrap()
+            .update_dirs_and_packages_streaming()
+            .map(|updated| updated.unwrap().0)
+            .collect();
+        updated.sort();
+
+        assert_eq!(
+            updated,
+            vec![
+                tmp.path().join("crate_a").join("Cargo.toml"),
+                tmp.path().join("crate_b").join("Cargo.toml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_skip_files_applies_before_matching() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        fs::create_dir_all(tmp.path().join("templates/crate_c")).unwrap();
+        fs::write(
+            tmp.path().join("templates/crate_c/Cargo.toml"),
+            "[package]\nname = \"crate_c\"\nversion = \"0.2.0\"\n\n[dependencies]\ncrate_a = \"0.4.0\"\n",
+        )
+        .unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap();
+        assert_eq!(increaser.inconsistencies().len(), 1);
+        let increaser = increaser
+            .with_skip_files(vec!["templates/*/Cargo.toml".to_string()])
+            .unwrap();
+        assert!(increaser.inconsistencies().is_empty());
+
+        let err = new_increaser(tmp.path(), "0.4.1", "0.4.2")
+            .unwrap()
+            .with_skip_files(vec!["crate_*/Cargo.toml".to_string()])
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<VersionUpdateError>(),
+            Some(VersionUpdateError::NoMatchFound)
+        ));
+    }
+
+    #[test]
+    fn test_with_only_files_leaves_the_other_files_alone() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        let crate_b = tmp.path().join("crate_b").join("Cargo.toml");
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2")
+            .unwrap()
+            .with_only_files(std::slice::from_ref(&crate_b))
+            .unwrap();
+        let updated = update_all(&increaser).unwrap();
+
+        assert_eq!(updated.len(), 1);
+        assert_eq!(updated[0].0, crate_b);
+        assert_eq!(fs::read_to_string(&crate_b).unwrap(), CRATE_B.replace("0.4.1", "0.4.2"));
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("crate_a").join("Cargo.toml")).unwrap(),
+            CRATE_A
+        );
+
+        let err = new_increaser(tmp.path(), "0.4.1", "0.4.2")
+            .unwrap()
+            .with_only_files(&[tmp.path().join("missing").join("Cargo.toml")])
+            .err()
+            .unwrap();
+        assert!(matches!(err, VersionUpdateError::NoMatchFound));
+    }
+
+    #[test]
+    fn test_with_skip_files_rejects_invalid_pattern() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+
+        let result = new_increaser(tmp.path(), "0.4.1", "0.4.2")
+            .unwrap()
+            .with_skip_files(vec!["crate_[a/Cargo.toml".to_string()]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_inconsistent_versions_are_reported() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap();
+        assert!(increaser.inconsistencies().is_empty());
+
+        fs::create_dir_all(tmp.path().join("crate_c")).unwrap();
+        fs::write(
+            tmp.path().join("crate_c").join("Cargo.toml"),
+            "[package]\nname = \"crate_c\"\nversion = \"0.2.0\"\n\n[dependencies]\ncrate_a = { version = \"0.4.0\" }\n",
+        )
+        .unwrap();
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap();
+
+        assert_eq!(
+            increaser.inconsistencies(),
+            [VersionInconsistency {
+                package: "crate_a".to_string(),
+                versions: vec![
+                    (tmp.path().join("crate_a").join("Cargo.toml"), "0.4.1".to_string()),
+                    (tmp.path().join("crate_b").join("Cargo.toml"), "0.4.1".to_string()),
+                    (tmp.path().join("crate_c").join("Cargo.toml"), "0.4.0".to_string()),
+                ],
+            }]
+        );
+        assert_eq!(
+            increaser.inconsistencies_as_table(),
+            "\
+Package  File                Version
+crate_a  crate_a/Cargo.toml  0.4.1
+         crate_b/Cargo.toml  0.4.1
+         crate_c/Cargo.toml  0.4.0"
+        );
+    }
+
+    #[test]
+    fn test_inconsistencies_skip_unparsable_and_target_manifests() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        let write = |dir: &str, content: &str| {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+            fs::write(tmp.path().join(dir).join("Cargo.toml"), content).unwrap();
+        };
+        write("broken", "[package\nname = \"broken\"\n");
+        write("target/package/crate_a-0.4.0", "[package]\nname = \"crate_a\"\nversion = \"0.4.0\"\n");
+        write(".hidden", "[package]\nname = \"crate_a\"\nversion = \"0.3.0\"\n");
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap();
+        assert!(increaser.inconsistencies().is_empty());
+    }
+
+    #[test]
+    fn test_update_lock_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_fixture(tmp.path());
+        let lock = r#"# This file is automatically @generated by Cargo.
+version = 4
+
+[[package]]
+name = "crate_a"
+version = "0.4.1"
+
+[[package]]
+name = "crate_b"
+version = "0.1.0"
+dependencies = [
+ "crate_a",
+ "crate_c",
+]
+
+[[package]]
+name = "crate_c"
+version = "0.4.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "0000"
+"#;
+        let lock_path = tmp.path().join("Cargo.lock");
+        fs::write(&lock_path, lock).unwrap();
+
+        let increaser = new_increaser(tmp.path(), "0.4.1", "0.4.2").unwrap();
+        assert_eq!(increaser.update_lock_file(&lock_path).unwrap(), 1);
+        assert_eq!(
+            fs::read_to_string(&lock_path).unwrap(),
+            lock.replacen("version = \"0.4.1\"", "version = \"0.4.2\"", 1)
+        );
+
+        // Nothing left to replace.
+        assert_eq!(increaser.update_lock_file(&lock_path).unwrap(), 0);
+    }
+}
@@ -5,22 +5,47 @@ use colored::Colorize;
 use console::style;
 use dialoguer::Confirm;
 use log::{LevelFilter, debug};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+mod git;
 mod increaser;
 mod publish;
+mod result_types;
+#[cfg(test)]
+mod test_support;
+mod validate;
+mod watch;
 
 use cratup_init::{
-    Config, initialize_configuration, initialize_logger, load_default_configuration,
+    Config, initialize_configuration, initialize_configuration_from_values, initialize_logger,
+    load_default_configuration,
+};
+use cratup_search::{
+    Search, TraversalOrder, VersionMatch, get_colored_dir_path, get_colored_pkg_deps,
+};
+use cratup_tree_sitter::PackageAndDeps;
+use git::{git_commit_changes, git_tag_version, is_in_nested_repo, render_commit_message};
+use increaser::{FileDiff, Increaser, match_thresholds};
+use result_types::IncvResult;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use watch::{WatchConfig, watch_cargo_tomls};
+use publish::{
+    PublishFlags, print_modules, publish_modules, publish_modules_json, publish_modules_parallel,
+    read_publish_list, select_publishable_dirs,
 };
-use cratup_search::Search;
-use increaser::Increaser;
-use publish::{find_publishable_dirs, print_modules, publish_modules};
 
 /// Configure logging verbosity using -v/--verbose and -q/--quiet flags.
 #[derive(Args, Debug)]
 pub struct Verbosity {
     /// Increase the level of verbosity (repeatable).
+    ///
+    /// -v shows debug logs of cratup_auto, cratup_search and cratup_init,
+    /// -vv adds trace logs and the debug logs of cratup_tree_sitter,
+    /// -vvv also shows the logs of the tree-sitter parser itself.
     #[arg(short = 'v', long, action = ArgAction::Count, display_order = 99)]
     pub verbose: u8,
 
@@ -41,6 +66,27 @@ impl Verbosity {
             }
         }
     }
+
+    /// Returns the same levels as `log_level_filter` as an `env_logger` filter string,
+    /// with the noisiest parser logs held back until the higher verbosity levels.
+    pub fn to_env_filter(&self) -> String {
+        let level = self.log_level_filter().to_string().to_lowercase();
+        let overrides: &[&str] = if self.quiet > 0 {
+            &[]
+        } else {
+            match self.verbose {
+                0 => &[],
+                1 => &["cratup_tree_sitter=info", "tree_sitter=off"],
+                2 => &["tree_sitter=off"],
+                _ => &[],
+            }
+        };
+
+        std::iter::once(level.as_str())
+            .chain(overrides.iter().copied())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
 }
 
 /// Mode-line interface for the module management tool.
@@ -54,6 +100,10 @@ struct Cli {
     #[command(flatten)]
     verbose: Verbosity,
 
+    /// Also write every log message, at trace level, to this file.
+    #[arg(long = "log-file", value_name = "PATH", global = true)]
+    log_file: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Mode,
 }
@@ -62,16 +112,36 @@ struct Cli {
 #[derive(Subcommand, Debug)]
 enum Mode {
     /// Initialize configuration
-    Init,
+    Init(InitArgs),
 
     /// Increase module version by providing the current and the next version.
     Incv(IncvArgs),
 
     /// Publish modules recursively found in the current directory.
-    Publish,
+    Publish(PublishArgs),
 
     /// Search modules with provided criteria.
     Search(SearchArgs),
+
+    /// Report Cargo.toml files that are malformed TOML.
+    Validate,
+}
+
+/// Arguments for the `init` subcommand. When any of them is given the configuration
+/// is stored without interactive prompts.
+#[derive(Args, Debug)]
+struct InitArgs {
+    /// Always ask for permission to modify files (yes/no)
+    #[arg(
+        long = "always-ask-permission",
+        value_parser = ["yes", "no"],
+        help = "Always ask for permission to modify files (yes/no)"
+    )]
+    always_ask_permission: Option<String>,
+
+    /// Print the current configuration instead of changing it
+    #[arg(long = "show", help = "Print the current configuration, highlighting changed values")]
+    show: bool,
 }
 
 /// Common arguments shared by Incv and Search modes.
@@ -92,17 +162,46 @@ struct IncvArgs {
     #[arg(
         short = 'i',
         long = "current-version",
+        required_unless_present = "constraint",
         help = "Current version of the package (e.g. 0.4.1)"
     )]
-    current_version: String,
+    current_version: Option<String>,
+
+    /// Semver requirement matching the versions to replace, instead of one current version
+    #[arg(
+        long = "constraint",
+        value_name = "REQ",
+        conflicts_with_all = ["current_version", "pre"],
+        help = "Replace every version matching the semver requirement REQ (e.g. '^0.4') instead of one current version"
+    )]
+    constraint: Option<String>,
 
     /// Next package version (e.g. 0.4.2)
     #[arg(
         short = 'r',
         long = "next-version",
+        required_unless_present_any = ["pre", "date_version"],
         help = "Next version of the package (e.g. 0.4.2)"
     )]
-    next_version: String,
+    next_version: Option<String>,
+
+    /// Pre-release to set on the current version instead of a next version
+    #[arg(
+        long = "pre",
+        value_name = "LABEL.N",
+        value_parser = parse_pre_release,
+        conflicts_with = "next_version",
+        help = "Set the pre-release of the current version to LABEL.N instead of giving a next version, e.g. --pre alpha.2 on 1.0.0-alpha.1"
+    )]
+    pre: Option<(String, u32)>,
+
+    /// Use today's date as the next version
+    #[arg(
+        long = "date-version",
+        conflicts_with_all = ["next_version", "pre", "constraint"],
+        help = "Set today's UTC date as YEAR.MONTH.DAY (e.g. 2024.3.7) instead of giving a next version"
+    )]
+    date_version: bool,
 
     /// Automatically confirm the update (skip confirmation prompt)
     #[arg(
@@ -111,6 +210,177 @@ struct IncvArgs {
         help = "Automatically confirm the update\n"
     )]
     yes: bool,
+
+    /// Write a Cargo.toml.bak next to each file before updating it
+    #[arg(
+        short = 'b',
+        long = "backup",
+        help = "Back up each Cargo.toml before updating it, rolling back on failure and removing the backups on success"
+    )]
+    backup: bool,
+
+    /// Tag the new version in git, even if auto_git_tag is off in the configuration
+    #[arg(
+        long = "tag",
+        help = "Create a git tag for the new version on the commit made after updating (needs auto_git_commit)"
+    )]
+    tag: bool,
+
+    /// Cargo.toml files to leave untouched
+    #[arg(
+        long = "skip-file",
+        value_name = "GLOB",
+        help = "Leave the Cargo.toml files matching GLOB, relative to the current directory, untouched (repeatable)"
+    )]
+    skip_files: Vec<String>,
+
+    /// Leave the Cargo.toml files of nested git repositories untouched
+    #[arg(
+        long = "skip-submodules",
+        help = "Leave the Cargo.toml files inside git submodules, or other git repositories nested in the current directory, untouched"
+    )]
+    skip_submodules: bool,
+
+    /// Update even when the versions are inconsistent across the Cargo.toml files
+    #[arg(
+        long = "force",
+        help = "Skip the check for packages with different versions across Cargo.toml files"
+    )]
+    force: bool,
+
+    /// Print a unified diff of each updated Cargo.toml
+    #[arg(
+        long = "diff",
+        help = "Print a unified diff of each updated Cargo.toml, before the confirmation prompt when it is shown"
+    )]
+    diff: bool,
+
+    /// Print the unified diff of the update without writing any file
+    #[arg(
+        long = "dry-run",
+        help = "Print a unified diff of the changes without writing any file"
+    )]
+    dry_run: bool,
+
+    /// List the replacements of a dry run as a table instead of a diff
+    #[arg(
+        long = "table",
+        requires = "dry_run",
+        help = "With --dry-run, list each replacement as a File/Package/From/To table instead of a diff"
+    )]
+    table: bool,
+
+    /// Print the result as JSON instead of text
+    #[arg(
+        long = "json",
+        requires = "yes",
+        conflicts_with_all = ["diff", "dry_run"],
+        help = "Print the modified files, the matches after the update and both versions as a JSON object instead of text (needs --yes)"
+    )]
+    json: bool,
+
+    /// Also update the Cargo.lock of the current directory
+    #[arg(
+        long = "lock",
+        help = "Also update the versions of the updated workspace packages in the Cargo.lock of the current directory"
+    )]
+    lock: bool,
+}
+
+/// Parses the `<label>.<number>` value of `--pre`, e.g. `rc.1`.
+fn parse_pre_release(value: &str) -> Result<(String, u32), String> {
+    let (label, number) = value
+        .rsplit_once('.')
+        .ok_or_else(|| format!("expected <label>.<number>, e.g. rc.1, got '{}'", value))?;
+    let number = number
+        .parse()
+        .map_err(|e| format!("invalid pre-release number '{}': {}", number, e))?;
+    Ok((label.to_string(), number))
+}
+
+/// Formats the UTC date of `time` as a `YEAR.MONTH.DAY` version, e.g. `2024.3.7`.
+fn date_version(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+    // Civil date from the days since 1970-01-01, counting in 400-year eras from March.
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{}.{}.{}", year, month, day)
+}
+
+/// Arguments for the `publish` subcommand.
+#[derive(Args, Debug)]
+struct PublishArgs {
+    /// Print the plain published/unpublished lists instead of a table
+    #[arg(long = "no-table", help = "Print plain lists instead of an aligned table")]
+    no_table: bool,
+
+    /// Number of crates to publish at the same time
+    #[arg(
+        short = 'j',
+        long = "jobs",
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Publish up to N crates at the same time"
+    )]
+    jobs: Option<u16>,
+
+    /// Features to enable when publishing
+    #[arg(
+        long = "features",
+        num_args = 1..,
+        value_delimiter = ',',
+        help = "Space or comma separated list of features to pass to cargo publish"
+    )]
+    features: Vec<String>,
+
+    /// Enable all features when publishing
+    #[arg(
+        long = "all-features",
+        conflicts_with = "features",
+        help = "Pass --all-features to cargo publish"
+    )]
+    all_features: bool,
+
+    /// Skip crates whose current version is already published
+    #[arg(
+        long = "check-crates-io",
+        help = "Skip crates whose current version is already published on crates.io"
+    )]
+    check_crates_io: bool,
+
+    /// Workspace root whose members are published
+    #[arg(
+        long = "from-manifest",
+        value_name = "WORKSPACE_ROOT",
+        help = "Publish the [workspace] members of this workspace root or Cargo.toml"
+    )]
+    from_manifest: Option<PathBuf>,
+
+    /// File listing the directories to publish, in order
+    #[arg(
+        long = "from-list",
+        value_name = "FILE",
+        conflicts_with = "from_manifest",
+        help = "Publish the directories listed in FILE, one per line, in that order"
+    )]
+    from_list: Option<PathBuf>,
+
+    /// Format of the publish report
+    #[arg(
+        long = "output-format",
+        value_name = "FORMAT",
+        default_value = "text",
+        value_parser = ["text", "json"],
+        help = "Print the report as text or as a JSON object with the summary and every module"
+    )]
+    output_format: String,
 }
 
 /// Arguments for the `search` subcommand.
@@ -126,13 +396,135 @@ struct SearchArgs {
         help = "Version of the package (e.g. 0.4.1)"
     )]
     version: Option<String>,
+
+    /// Semver requirement the version must satisfy (e.g. ">=0.4, <0.5")
+    #[arg(
+        short = 'r',
+        long = "version-range",
+        conflicts_with = "version",
+        help = "Semver requirement of the package version (e.g. \">=0.4, <0.5\")"
+    )]
+    version_range: Option<String>,
+
+    /// Prefix the version must start with (e.g. "0.4." for every 0.4.x version)
+    #[arg(
+        long = "version-prefix",
+        conflicts_with_all = ["version", "version_range"],
+        help = "Prefix of the package version (e.g. \"0.4.\" for every 0.4.x version)"
+    )]
+    version_prefix: Option<String>,
+
+    /// List the packages that do NOT depend on the given package name
+    #[arg(
+        long = "inverse",
+        requires = "package_name",
+        help = "List packages that do not depend on the package given with -p"
+    )]
+    inverse: bool,
+
+    /// Deprecated: the [dev-dependencies] are always searched
+    #[arg(
+        long = "dev-deps",
+        help = "Deprecated, does nothing: the [dev-dependencies] are always searched and listed on their own"
+    )]
+    dev_deps: bool,
+
+    /// Deprecated: the [build-dependencies] are always searched
+    #[arg(
+        long = "build-deps",
+        help = "Deprecated, does nothing: the [build-dependencies] are always searched and listed on their own"
+    )]
+    build_deps: bool,
+
+    /// Also list the path and git dependencies that have no version
+    #[arg(
+        long = "show-unversioned",
+        help = "Also list the path and git dependencies that have no version"
+    )]
+    show_unversioned: bool,
+
+    /// Re-run the search whenever a Cargo.toml changes
+    #[arg(long = "watch", help = "Re-run the search whenever a Cargo.toml changes, until Ctrl-C")]
+    watch: bool,
+
+    /// Also list the packages depending on the given package through other packages
+    #[arg(
+        long = "transitive",
+        requires = "package_name",
+        conflicts_with_all = ["inverse", "count"],
+        help = "List the packages depending on the package given with -p, directly or transitively"
+    )]
+    transitive: bool,
+
+    /// Replace the searched version with this one in the files found
+    #[arg(
+        long = "replace",
+        value_name = "NEW_VERSION",
+        requires = "version",
+        conflicts_with_all = ["inverse", "count", "watch", "transitive"],
+        help = "Replace the version given with -i by NEW_VERSION in the files found"
+    )]
+    replace: Option<String>,
+
+    /// List shallower crates first
+    #[arg(
+        long = "breadth-first",
+        help = "List the crates closest to the current directory first, instead of sorting by path"
+    )]
+    breadth_first: bool,
+
+    /// Format of the search results
+    #[arg(
+        long = "output-format",
+        value_name = "FORMAT",
+        value_parser = ["text", "toml", "csv"],
+        default_value = "text",
+        conflicts_with_all = ["count", "transitive", "replace", "inverse", "watch"],
+        help = "Print the results as text, a TOML document or CSV rows"
+    )]
+    output_format: String,
+
+    /// Output template of each result
+    #[arg(
+        long = "format",
+        value_name = "TEMPLATE",
+        conflicts_with_all = ["count", "transitive", "replace"],
+        help = "Print each result with TEMPLATE, replacing {name}, {version}, {path} and {deps}"
+    )]
+    format: Option<String>,
+
+    /// Number of threads reading the Cargo.toml files
+    #[arg(
+        short = 'j',
+        long = "jobs",
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Read and parse the Cargo.toml files on N threads"
+    )]
+    jobs: Option<u16>,
+
+    /// Only print the number of matching Cargo.toml files
+    #[arg(
+        long = "count",
+        conflicts_with_all = ["inverse", "watch"],
+        help = "Only print the number of matching Cargo.toml files"
+    )]
+    count: bool,
+
+    /// List the files with the most matches first
+    #[arg(
+        long = "sort-by-matches",
+        conflicts_with_all = ["count", "inverse", "transitive", "replace"],
+        help = "List the Cargo.toml files with the most matching packages and dependencies first"
+    )]
+    sort_by_matches: bool,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    let verbosity_level = cli.verbose.log_level_filter();
-    initialize_logger(verbosity_level).context("Failed to initialize logger")?;
+    let verbosity_level = cli.verbose.to_env_filter();
+    initialize_logger(&verbosity_level, cli.log_file.as_deref())
+        .context("Failed to initialize logger")?;
     debug!(
         "{} {:?}",
         style("Logger initialized with verbosity:").cyan(),
@@ -143,32 +535,56 @@ fn main() -> Result<()> {
     debug!("{}", style("Default configuration loaded").green());
 
     match &cli.command {
-        Mode::Init => {
-            debug!("{}", style("Initializing configuration...").yellow());
-            initialize_configuration().context("Failed to initialize configuration")?;
+        Mode::Init(args) => {
+            if args.show {
+                print_config(&config);
+            } else if let Some(ref always_ask_permission) = args.always_ask_permission {
+                debug!("{}", style("Storing configuration from flags...").yellow());
+                let mut values = config.clone();
+                values.always_ask_permission = always_ask_permission == "yes";
+                initialize_configuration_from_values(values)
+                    .context("Failed to initialize configuration")?;
+            } else {
+                debug!("{}", style("Initializing configuration...").yellow());
+                initialize_configuration().context("Failed to initialize configuration")?;
+            }
         }
         Mode::Incv(args) => {
             if let Some(ref package) = args.common.package_name {
                 debug!(
-                    "Running incv mode for package {}: updating version from {} to {}",
+                    "Running incv mode for package {}: updating version from {:?} to {:?}",
                     package, args.current_version, args.next_version
                 );
             } else {
                 debug!(
-                    "Running incv mode: updating version from {} to {}",
+                    "Running incv mode: updating version from {:?} to {:?}",
                     args.current_version, args.next_version
                 );
             }
             // Pass the config as a parameter to run_incv.
-            if let Err(e) = run_incv(args, &config) {
-                eprintln!("Error updating version: {}", e);
-                std::process::exit(1);
+            match run_incv(args, &config) {
+                Ok(incv_result) if args.json => match serde_json::to_string_pretty(&incv_result) {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => {
+                        eprintln!("Error serializing the result: {}", e);
+                        std::process::exit(1);
+                    }
+                },
+                Ok(incv_result) => {
+                    for file_path in incv_result.modified_files {
+                        println!("Updated: {}", file_path.display());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error updating version: {:#}", e);
+                    std::process::exit(1);
+                }
             }
         }
-        Mode::Publish => {
+        Mode::Publish(args) => {
             debug!("Running publish mode: publishing modules recursively");
-            if let Err(e) = run_publish() {
-                eprintln!("Error publishing modules: {}", e);
+            if let Err(e) = run_publish(args, &config, cli.verbose.verbose > 0) {
+                eprintln!("Error publishing modules: {:#}", e);
                 std::process::exit(1);
             }
         }
@@ -184,11 +600,36 @@ fn main() -> Result<()> {
                     args.version
                 );
             }
-            if let Err(e) = run_search(args) {
+            if args.dev_deps || args.build_deps {
+                println!(
+                    "{}",
+                    "Warning: --dev-deps and --build-deps are deprecated, every dependency table is always searched".yellow()
+                );
+            }
+            if args.watch {
+                if let Err(e) = run_search_watch(args, &config) {
+                    eprintln!("Error during search: {:#}", e);
+                    std::process::exit(1);
+                }
+            } else if let Err(e) = run_and_display_search(args, &config) {
                 eprintln!("Error during search: {}", e);
                 std::process::exit(1);
             }
         }
+        Mode::Validate => {
+            debug!("Running validate mode");
+            let current_dir = std::env::current_dir()?;
+            let malformed =
+                validate::find_malformed_manifests(&current_dir, &config.excluded_dirs);
+            if malformed.is_empty() {
+                println!("All Cargo.toml files are valid TOML.");
+            } else {
+                for (file_path, problem) in malformed {
+                    println!("{}: {}", file_path.display(), problem);
+                }
+                std::process::exit(1);
+            }
+        }
     }
 
     debug!("Execution completed successfully");
@@ -197,9 +638,8 @@ fn main() -> Result<()> {
 
 /// The run function for the increaser. It extracts parameters from the command-line options,
 /// retrieves the current directory, and then creates an Increaser instance to perform the update.
-fn run_incv(args: &IncvArgs, config: &Config) -> Result<()> {
-    debug!("Starting version increment process with args: {:?}", args);
-
+/// Returns the files that were written along with the updated version matches.
+fn run_incv(args: &IncvArgs, config: &Config) -> Result<IncvResult> {
     // Retrieve the current working directory as a string.
     let current_dir = std::env::current_dir().with_context(|| {
         debug!("Failed to get current working directory");
@@ -207,64 +647,296 @@ fn run_incv(args: &IncvArgs, config: &Config) -> Result<()> {
     })?;
     debug!("Current working directory: {:?}", current_dir);
 
-    // Initialize the increaser.
+    run_incv_in_dir(current_dir, args, config)
+}
+
+/// Same as `run_incv`, for the Cargo.toml files under `current_dir`.
+fn run_incv_in_dir(
+    current_dir: PathBuf,
+    args: &IncvArgs,
+    config: &Config,
+) -> Result<IncvResult> {
+    run_incv_in_files(current_dir, args, config, None)
+}
+
+/// Builds the increaser of `args`, matching either `--current-version` or `--constraint`
+/// and setting `--next-version`, the `--date-version` date or the `--pre` pre-release.
+fn new_increaser(current_dir: &Path, args: &IncvArgs, config: &Config) -> Result<Increaser> {
     debug!(
-        "Creating Increaser with current_version: {}, next_version: {}, package_name: {:?}",
-        args.current_version, args.next_version, args.common.package_name
+        "Creating Increaser with current_version: {:?}, constraint: {:?}, next_version: {:?}, pre: {:?}, package_name: {:?}",
+        args.current_version, args.constraint, args.next_version, args.pre, args.common.package_name
     );
-    let increaser = Increaser::new(
-        current_dir,
-        args.current_version.clone(),
-        args.next_version.clone(),
-        args.common.package_name.clone(),
-    )
-    .with_context(|| {
+    let package_name = args.common.package_name.clone();
+    let current_version = || {
+        args.current_version
+            .clone()
+            .context("--current-version is required without --constraint")
+    };
+    let next_version = || {
+        args.next_version
+            .clone()
+            .context("--next-version is required without --pre or --date-version")
+    };
+
+    let increaser = if let Some(constraint) = &args.constraint {
+        Increaser::update_with_semver_constraint(
+            current_dir.to_path_buf(),
+            constraint,
+            &next_version()?,
+            package_name,
+            &config.excluded_dirs,
+            config.toml_grammar,
+        )
+    } else if args.date_version {
+        let today = date_version(SystemTime::now());
+        Increaser::new_matching(
+            current_dir.to_path_buf(),
+            current_version()?,
+            package_name,
+            &config.excluded_dirs,
+            config.toml_grammar,
+        )
+        .map(|increaser| increaser.with_version_transformer(move |_| today.clone()))
+    } else if let Some((label, number)) = &args.pre {
+        let increaser = Increaser::new_matching(
+            current_dir.to_path_buf(),
+            current_version()?,
+            package_name,
+            &config.excluded_dirs,
+            config.toml_grammar,
+        )
+        .context("Failed to initialize version increaser")?;
+        let next_version = increaser.bump_prerelease(label, *number)?;
+        increaser.with_next_version(next_version)
+    } else {
+        Increaser::new(
+            current_dir.to_path_buf(),
+            current_version()?,
+            next_version()?,
+            package_name,
+            &config.excluded_dirs,
+            config.toml_grammar,
+        )
+    };
+    increaser.with_context(|| {
         debug!("Failed to initialize Increaser");
         "Failed to initialize version increaser"
-    })?;
+    })
+}
+
+/// Same as `run_incv_in_dir`, only updating the Cargo.toml files of `only_files` when
+/// given.
+fn run_incv_in_files(
+    current_dir: PathBuf,
+    args: &IncvArgs,
+    config: &Config,
+    only_files: Option<&[PathBuf]>,
+) -> Result<IncvResult> {
+    debug!("Starting version increment process with args: {:?}", args);
+
+    // Without a commit, the tag would point at the HEAD from before the update.
+    if args.tag && !config.auto_git_commit {
+        anyhow::bail!("--tag needs auto_git_commit to be enabled in the configuration");
+    }
+    let lock_path = current_dir.join("Cargo.lock");
+    if args.lock && !lock_path.is_file() {
+        anyhow::bail!("--lock needs a Cargo.lock in {:?}", current_dir);
+    }
+
+    let increaser = new_increaser(&current_dir, args, config)?
+        .with_backup(args.backup)
+        .with_match_thresholds(match_thresholds(config))
+        .with_quiet(args.json)
+        .with_skip_files(args.skip_files.clone())?;
+    let increaser = if args.skip_submodules {
+        let root = current_dir.clone();
+        increaser.with_filter_fn(move |file_path, _| !is_in_nested_repo(file_path, &root))
+    } else {
+        increaser
+    };
+    let increaser = match only_files {
+        Some(file_paths) => increaser
+            .with_only_files(file_paths)
+            .context("Failed to restrict the update to the files found")?,
+        None => increaser,
+    };
     debug!("Increaser initialized successfully");
+    // With --json, only the result is printed, by the caller.
+    let say = |message: &dyn std::fmt::Display| {
+        if !args.json {
+            println!("{}", message);
+        }
+    };
 
     // Print current version matches.
     debug!("Printing current version matches");
-    increaser.print_current_version_matches().with_context(|| {
+    let current_matches = increaser.print_current_version_matches().with_context(|| {
         debug!("Failed while printing current version matches");
         "Failed to print current version matches"
     })?;
+    let current_summary = VersionMatch::summarise(&current_matches);
 
-    // Decide if we need to ask for confirmation.
+    // Nothing is written in a dry run, so there is nothing to confirm.
+    if args.dry_run {
+        if args.table {
+            debug!("Dry run: printing the replacements without updating");
+            let table = increaser.preview_as_table().context("Failed to preview the update")?;
+            println!("{}", table);
+        } else {
+            debug!("Dry run: printing the diff without updating");
+            let diffs = increaser.diff().context("Failed to compute the diff")?;
+            print_file_diffs(&diffs, &current_dir);
+        }
+        return Ok(IncvResult {
+            modified_files: Vec::new(),
+            version_matches: Vec::new(),
+            from_version: increaser.current_version().to_string(),
+            to_version: increaser.next_version().to_string(),
+        });
+    }
+
+    // Warn about versions the update would leave behind.
+    if !args.force && !increaser.inconsistencies().is_empty() {
+        say(&"Warning: these versions differ across the Cargo.toml files:".yellow());
+        say(&increaser.inconsistencies_as_table());
+        if args.yes {
+            debug!("CLI flag 'yes' provided: proceeding despite inconsistencies");
+        } else if !ask_to_continue("Proceed anyway? (No/yes)", None) {
+            println!("Execution interrupted.");
+            process::exit(1);
+        }
+    }
+
+    // Decide if we need to ask for confirmation. With --diff, the diff shown at the
+    // prompt is the one written.
+    let mut confirmed_diffs = None;
     if args.yes {
         debug!("CLI flag 'yes' provided: skipping confirmation");
+        say(&current_summary);
     } else if config.always_ask_permission {
         // Only ask if the configuration indicates it.
-        ask_to_continue();
+        let diffs = if args.diff {
+            Some(increaser.diff().context("Failed to compute the diff")?)
+        } else {
+            None
+        };
+        let prompt = format!("{} — proceed? (No/yes)", current_summary);
+        if !ask_to_continue(&prompt, diffs.as_deref().map(|diffs| (diffs, current_dir.as_path())))
+        {
+            println!("Execution interrupted.");
+            process::exit(1);
+        }
         debug!("User confirmed continuation via config-based prompt");
+        confirmed_diffs = diffs;
     } else {
         debug!("No confirmation required");
+        println!("{}", current_summary);
     }
 
     // Execute the update process.
     debug!("Starting directory and package updates");
-    let _updated_packages = increaser.update_dirs_and_packages().with_context(|| {
-        debug!("Failed during directory and package updates");
-        "Failed to update directories and packages"
-    })?;
+    let update_result = if let Some(diffs) = confirmed_diffs {
+        increaser
+            .write_diffs(&diffs)
+            .map(|()| diffs.into_iter().map(|diff| diff.path).collect::<Vec<_>>())
+    } else if args.diff {
+        increaser.update_and_diff().map(|diffs| {
+            print_file_diffs(&diffs, &current_dir);
+            diffs.into_iter().map(|diff| diff.path).collect()
+        })
+    } else {
+        // Each Cargo.toml is read, updated and written as the stream is advanced.
+        increaser
+            .update_dirs_and_packages_streaming()
+            .map(|updated| updated.map(|(file_path, _)| file_path))
+            .collect()
+    };
+    let mut updated_files = match update_result {
+        Ok(updated_files) => updated_files,
+        Err(e) => {
+            debug!("Failed during directory and package updates: {:?}", e);
+            if args.backup {
+                let restored = increaser
+                    .rollback()
+                    .context("Failed to roll back from backups")?;
+                say(&format!("Rolled back {} file(s)", restored));
+            }
+            return Err(anyhow::Error::new(e).context("Failed to update directories and packages"));
+        }
+    };
     debug!("Successfully updated directories and packages");
+    if args.backup {
+        let removed = increaser
+            .remove_backups()
+            .context("Failed to remove the backups")?;
+        debug!("Removed {} backup(s)", removed);
+    }
 
-    println!("Updated packages:");
+    if args.lock {
+        let replaced = increaser
+            .update_lock_file(&lock_path)
+            .context("Failed to update Cargo.lock")?;
+        say(&format!("Updated {} version(s) in Cargo.lock", replaced));
+        if replaced > 0 {
+            updated_files.push(lock_path);
+        }
+    }
+
+    say(&"Updated packages:");
     // Print next version matches.
     debug!("Printing next version matches");
-    increaser.print_next_version_matches().with_context(|| {
+    let next_matches = increaser.print_next_version_matches().with_context(|| {
         debug!("Failed while printing next version matches");
         "Failed to print next version matches"
     })?;
+    say(&VersionMatch::summarise(&next_matches));
+
+    if config.auto_git_commit && !updated_files.is_empty() {
+        let message = render_commit_message(
+            &config.git_commit_message_template,
+            increaser.current_version(),
+            increaser.next_version(),
+        );
+        debug!("Committing {} updated file(s)", updated_files.len());
+        git_commit_changes(&updated_files, &message)
+            .context("Failed to commit the updated files")?;
+        say(&format!("Committed: {}", message));
+
+        // Tag the commit made above, never the HEAD from before the update.
+        if args.tag || config.auto_git_tag {
+            git_tag_version(&config.git_tag_prefix, increaser.next_version(), false, None)
+                .context("Failed to tag the new version")?;
+            say(&format!("Tagged: {}{}", config.git_tag_prefix, increaser.next_version()));
+        }
+    } else if config.auto_git_tag && !updated_files.is_empty() {
+        say(&"Warning: auto_git_tag needs auto_git_commit, the new version was not tagged".yellow());
+    }
 
     debug!("Version increment process completed successfully");
-    Ok(())
+    Ok(IncvResult {
+        modified_files: updated_files,
+        version_matches: next_matches,
+        from_version: increaser.current_version().to_string(),
+        to_version: increaser.next_version().to_string(),
+    })
 }
 
-fn run_search(args: &SearchArgs) -> Result<(), Box<dyn std::error::Error>> {
-    debug!("Starting search operation with args: {:?}", args);
+/// Prints the colorized unified diff of each file, with paths relative to `dir`.
+fn print_file_diffs(diffs: &[FileDiff], dir: &Path) {
+    for diff in diffs {
+        let relative = FileDiff {
+            path: diff.path.strip_prefix(dir).unwrap_or(&diff.path).to_path_buf(),
+            ..diff.clone()
+        };
+        println!("{}", relative.to_colored_string());
+    }
+}
 
+/// Runs the search in the current directory and prints its results.
+fn run_and_display_search(
+    args: &SearchArgs,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
     // Retrieve the current working directory.
     let current_dir = std::env::current_dir().map_err(|e| {
         debug!("Failed to get current directory: {}", e);
@@ -272,88 +944,940 @@ fn run_search(args: &SearchArgs) -> Result<(), Box<dyn std::error::Error>> {
     })?;
     debug!("Current working directory: {:?}", current_dir);
 
+    let mut search_instance = new_search(current_dir.clone(), args, config)?;
+    if args.count {
+        return write_search_count(&mut std::io::stdout(), &mut search_instance, args);
+    }
+    if args.transitive
+        && let Some(ref pkg_name) = args.common.package_name
+    {
+        let dependents = search_instance.search_transitive(pkg_name)?;
+        println!(
+            "Found {} package(s) depending on {}:",
+            dependents.len(),
+            pkg_name.yellow()
+        );
+        for dependent in dependents {
+            println!(
+                "\t{} {}: {}",
+                dependent.dependee.name.purple(),
+                dependent.dependee.version.green(),
+                dependent.chain.join(" -> ")
+            );
+        }
+        return Ok(());
+    }
+    if let Some(ref new_version) = args.replace
+        && let Some(ref current_version) = args.version
+    {
+        search_instance.search()?;
+        let found_files: Vec<PathBuf> = search_instance
+            .pkg_deps_dirs
+            .iter()
+            .map(|(file_path, _)| file_path.clone())
+            .collect();
+        println!("Found {} matching file(s)", found_files.len());
+        if found_files.is_empty() {
+            return Ok(());
+        }
+
+        // Update the files found the same way `incv` does, prompts and git included.
+        let incv_args = IncvArgs {
+            common: CommonArgs {
+                package_name: args.common.package_name.clone(),
+            },
+            current_version: Some(current_version.clone()),
+            constraint: None,
+            next_version: Some(new_version.clone()),
+            pre: None,
+            date_version: false,
+            yes: false,
+            backup: false,
+            tag: false,
+            skip_files: Vec::new(),
+            skip_submodules: false,
+            force: false,
+            diff: false,
+            dry_run: false,
+            table: false,
+            json: false,
+            lock: false,
+        };
+        let incv_result =
+            run_incv_in_files(current_dir, &incv_args, config, Some(&found_files))?;
+        for file_path in incv_result.modified_files {
+            println!("Updated: {}", file_path.display());
+        }
+        return Ok(());
+    }
+    let results = run_search(&mut search_instance, args)?;
+    match args.output_format.as_str() {
+        "toml" => print!("{}", search_instance.export_toml()?),
+        "csv" => write_search_csv(&mut std::io::stdout(), &search_instance)?,
+        _ => display_search_results(&results, &search_instance, args)?,
+    }
+    Ok(())
+}
+
+/// Writes the search results as CSV to `writer`.
+#[cfg(feature = "csv")]
+fn write_search_csv(
+    writer: &mut dyn Write,
+    search_instance: &Search,
+) -> Result<(), Box<dyn std::error::Error>> {
+    search_instance.output_csv(writer)?;
+    Ok(())
+}
+
+/// CSV output is not compiled in without the `csv` feature.
+#[cfg(not(feature = "csv"))]
+fn write_search_csv(
+    _writer: &mut dyn Write,
+    _search_instance: &Search,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("CSV output requires cratup_auto to be built with the `csv` feature".into())
+}
+
+/// Loads every Cargo.toml under `current_dir`, skipping the directories excluded by
+/// `config`.
+fn new_search(
+    current_dir: PathBuf,
+    args: &SearchArgs,
+    config: &Config,
+) -> Result<Search, Box<dyn std::error::Error>> {
     // Create a new Search instance. Note: the constructor only loads raw data.
     debug!(
         "Initializing Search with version: {:?}, package_name: {:?}",
         args.version, args.common.package_name
     );
-    let mut search_instance = Search::new(
+    let search_instance = Search::new_matching(
         current_dir,
         args.version.clone(),
         args.common.package_name.clone(),
+        &config.excluded_dirs,
+        args.jobs.map_or(1, usize::from),
+        "Cargo.toml",
+        config.toml_grammar,
     )
     .map_err(|e| {
         debug!("Search initialization failed: {:?}", e);
         "Failed to initialize search"
-    })?;
+    })?
+    .with_show_unversioned(args.show_unversioned)
+    .with_traversal_order(if args.breadth_first {
+        TraversalOrder::BreadthFirst
+    } else {
+        TraversalOrder::DepthFirst
+    });
     debug!("Search instance created successfully");
 
-    // Run the normal search using filtering functions.
-    search_instance.search()?;
+    Ok(search_instance)
+}
+
+/// How the entries returned by `run_search` were found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MatchMode {
+    /// The entries matched the search.
+    Exact,
+    /// Nothing matched the package name searched, the entries are the closest packages.
+    Fuzzy,
+}
+
+impl MatchMode {
+    /// The match mode of the entries `run_search` returned for `args`: fuzzy when a
+    /// package name was searched, outside of an inverse search, and nothing matched it.
+    fn of(search_instance: &Search, args: &SearchArgs) -> Self {
+        if !args.inverse
+            && args.common.package_name.is_some()
+            && search_instance.pkg_deps_dirs.is_empty()
+        {
+            MatchMode::Fuzzy
+        } else {
+            MatchMode::Exact
+        }
+    }
+}
+
+/// Runs the search requested by `args` and returns the found entries without printing
+/// anything. When nothing is found for a package name, the closest package names are
+/// returned instead, see `MatchMode::of`.
+fn run_search(
+    search_instance: &mut Search,
+    args: &SearchArgs,
+) -> Result<Vec<(PathBuf, PackageAndDeps)>, Box<dyn std::error::Error>> {
+    debug!("Starting search operation with args: {:?}", args);
+
+    // The inverse search lists the packages missing the dependency and skips the rest.
+    if args.inverse
+        && let Some(ref pkg_name) = args.common.package_name
+    {
+        let packages = search_instance.inverse(pkg_name)?;
+        let results = packages
+            .into_iter()
+            .map(|(path, pkg)| {
+                let pkg_and_deps = PackageAndDeps {
+                    package: Some(pkg),
+                    dependencies: Vec::new(),
+                    dev_dependencies: Vec::new(),
+                    build_dependencies: Vec::new(),
+                    target_dependencies: Vec::new(),
+                    unversioned_dependencies: Vec::new(),
+                };
+                (path, pkg_and_deps)
+            })
+            .collect();
+        return Ok(results);
+    }
+
+    // Run the normal search using filtering functions, or the range or prefix search if requested.
+    match (&args.version_range, &args.version_prefix) {
+        (Some(req), _) => search_instance.search_by_range(req)?,
+        (None, Some(prefix)) => {
+            search_instance.find_with_version_prefix(prefix)?;
+        }
+        (None, None) => search_instance.search()?,
+    }
+    if args.sort_by_matches {
+        search_instance.sort_by_match_count(true);
+    }
     // Retrieve the found packages from the updated field.
-    let mut found_packages = search_instance.pkg_deps_dirs.clone();
+    let found_packages = search_instance.pkg_deps_dirs.clone();
     debug!("Search returned {} result(s)", found_packages.len());
 
-    // If the search returns no results, try fuzzy search.
-    if found_packages.is_empty() {
+    // If the search for a package returns no results, try fuzzy search.
+    if found_packages.is_empty() && args.common.package_name.is_some() {
         debug!("No results found in search; executing fuzzy search for the closest match");
-        found_packages = search_instance.fuzzy_search()?;
-        if !found_packages.is_empty() {
-            // Print the fuzzy found package information on screen.
-            for (path, pkg_and_deps) in &found_packages {
-                println!("Found similar package (exact package name not found): {}", pkg_and_deps.package.clone().unwrap().name.green());
-                debug!(
-                    "Fuzzy search found package at {:?}: {:?}",
-                    path, pkg_and_deps
-                );
+        return search_instance.fuzzy_search();
+    }
+
+    debug!("Search operation completed successfully");
+    Ok(found_packages)
+}
+
+/// Writes the number of Cargo.toml files matching `args` as a single line, with nothing
+/// else, so scripts can read it safely.
+fn write_search_count<W: Write>(
+    out: &mut W,
+    search_instance: &mut Search,
+    args: &SearchArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let count = match (&args.version_range, &args.version_prefix) {
+        (Some(req), _) => {
+            search_instance.search_by_range(req)?;
+            search_instance.pkg_deps_dirs.len()
+        }
+        (None, Some(prefix)) => search_instance.find_with_version_prefix(prefix)?.len(),
+        (None, None) => search_instance.count_matches()?,
+    };
+    writeln!(out, "{}", count)?;
+    Ok(())
+}
+
+/// Prints the results of `run_search` for `args`, as fuzzy matches when `MatchMode::of`
+/// says so.
+fn display_search_results(
+    results: &[(PathBuf, PackageAndDeps)],
+    search_instance: &Search,
+    args: &SearchArgs,
+) -> std::io::Result<()> {
+    if args.inverse
+        && let Some(ref pkg_name) = args.common.package_name
+    {
+        println!(
+            "Found {} package(s) not depending on {}:",
+            results.len(),
+            pkg_name.yellow()
+        );
+        for (path, pkg_and_deps) in results {
+            let colored_path = get_colored_dir_path(path, search_instance.dir_path());
+            println!(
+                "{}\n{}",
+                colored_path,
+                get_colored_pkg_deps(pkg_and_deps, |s| s.green())
+            );
+        }
+        return Ok(());
+    }
+
+    let match_mode = MatchMode::of(search_instance, args);
+    if results.is_empty() {
+        match match_mode {
+            MatchMode::Exact => println!("No packages found."),
+            MatchMode::Fuzzy => println!("No packages found, even with fuzzy search."),
+        }
+    } else if match_mode == MatchMode::Fuzzy {
+        // Print the fuzzy found package information on screen.
+        println!(
+            "Found {} similar package(s) (exact package name not found):",
+            results.len()
+        );
+        for (path, pkg_and_deps) in results {
+            if let Some(ref pkg) = pkg_and_deps.package {
+                println!("\t{}", pkg.name.green());
             }
-        } else {
-            println!("No packages found, even with fuzzy search.");
+            debug!(
+                "Fuzzy search found package at {:?}: {:?}",
+                path, pkg_and_deps
+            );
         }
     } else {
         // Display the found packages with blue version coloring.
         debug!("Executing search display with blue version coloring");
-        search_instance.display(|s| {
+        let color_version = |s: &str| {
             debug!("Applying blue color to version string: {}", s);
             s.green()
-        });
+        };
+        match args.format {
+            Some(ref template) => search_instance.display_with_template(template, color_version)?,
+            None => search_instance.display(color_version)?,
+        }
     }
-    debug!("Search operation completed successfully");
     Ok(())
 }
 
-fn run_publish() -> Result<()> {
+/// Runs the search, then clears the terminal and runs it again on every Cargo.toml
+/// change under the current directory until Ctrl-C is pressed.
+fn run_search_watch(args: &SearchArgs, config: &Config) -> Result<()> {
+    let current_dir = std::env::current_dir().context("Failed to get current directory")?;
+
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let running = Arc::clone(&running);
+        ctrlc::set_handler(move || running.store(false, Ordering::SeqCst))
+            .context("Failed to set Ctrl-C handler")?;
+    }
+
+    let search_once = || {
+        // A Cargo.toml in the middle of being edited may not parse, so keep watching.
+        if let Err(e) = run_and_display_search(args, config) {
+            eprintln!("Error during search: {}", e);
+        }
+    };
+
+    search_once();
+    let watch_config = WatchConfig {
+        debounce_ms: config.watch_debounce_ms,
+    };
+    watch_cargo_tomls(&current_dir, &watch_config, &running, || {
+        console::Term::stdout()
+            .clear_screen()
+            .context("Failed to clear the terminal")?;
+        search_once();
+        Ok(())
+    })
+}
+
+/// Publishes the modules selected by `args`. With `verbose`, failed modules are listed
+/// with the exit code of their publish command.
+fn run_publish(args: &PublishArgs, config: &Config, verbose: bool) -> Result<()> {
     // Get the current directory.
     let current_dir = std::env::current_dir()?;
     debug!("Current directory: {:?}", current_dir);
 
-    // Find publishable directories.
-    let publishable_dirs = find_publishable_dirs(&current_dir);
+    // Find publishable directories, from the workspace members when there is a workspace,
+    // unless they are listed in a file.
+    let publishable_dirs = match args.from_list {
+        Some(ref list_path) => read_publish_list(list_path, &current_dir)?,
+        None => select_publishable_dirs(
+            &current_dir,
+            args.from_manifest.as_deref(),
+            config.toml_grammar,
+        )?,
+    };
     debug!(
         "Total publishable directories found: {}",
         publishable_dirs.len()
     );
 
+    let flags = PublishFlags {
+        features: args
+            .features
+            .iter()
+            .flat_map(|features| features.split_whitespace())
+            .map(str::to_string)
+            .collect(),
+        all_features: args.all_features,
+        grammar: config.toml_grammar,
+        check_crates_io: args.check_crates_io || config.publish_check_crates_io,
+        ..PublishFlags::default()
+    };
+    debug!("Publish flags: {:?}", flags);
+
+    if args.output_format == "json" {
+        let json = match args.jobs {
+            Some(jobs) => publish_modules_parallel(&publishable_dirs, jobs.into(), &flags)?.to_json()?,
+            None => publish_modules_json(&publishable_dirs, &flags)?,
+        };
+        println!("{}", json);
+        return Ok(());
+    }
+
     // Publish each module and obtain the final publish states.
-    let publish_states = publish_modules(&publishable_dirs)?;
+    let publish_result = match args.jobs {
+        Some(jobs) => publish_modules_parallel(&publishable_dirs, jobs.into(), &flags)?,
+        None => publish_modules(&publishable_dirs, &flags)?,
+    };
 
     // Print the published modules in green and unpublished in red.
-    print_modules(&publish_states);
+    print_modules(&publish_result.states, args.no_table, verbose);
+    println!("{}", publish_result.summary);
 
     Ok(())
 }
 
-fn ask_to_continue() {
-    // Prompt the user with a yes/no question. If the user presses enter, the default value (false) is returned.
-    let continue_execution = Confirm::new()
-        .with_prompt("Proceed to bump all to the new version? (No/yes)")
-        .default(false)  // default is "No" if enter is pressed
-        .interact()
+/// Prints every configuration field, highlighting in yellow those that differ from the default.
+fn print_config(config: &Config) {
+    for field in config.fields() {
+        let line = format!("{} = {}", field.name, field.current_value);
+        if field.is_changed() {
+            println!(
+                "{} (default: {})",
+                style(line).yellow(),
+                field.default_value
+            );
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Asks the user a yes/no question, defaulting to "No" when enter is pressed.
+/// Prints `diffs` first when given, with paths relative to the given directory.
+/// Returns true only if the user confirms.
+fn ask_to_continue(prompt: &str, diffs: Option<(&[FileDiff], &Path)>) -> bool {
+    if let Some((diffs, dir)) = diffs {
+        print_file_diffs(diffs, dir);
+    }
+    confirm_with(prompt, |prompt| {
+        Confirm::new()
+            .with_prompt(prompt)
+            .default(false) // default is "No" if enter is pressed
+            .interact()
+    })
+}
+
+/// Runs `ask` with the prompt and treats a failed prompt (e.g. no terminal) as "No".
+fn confirm_with<F>(prompt: &str, ask: F) -> bool
+where
+    F: FnOnce(&str) -> dialoguer::Result<bool>,
+{
+    match ask(prompt) {
+        Ok(confirmed) => {
+            debug!("User answered {} to: {}", confirmed, prompt);
+            confirmed
+        }
+        Err(e) => {
+            debug!("Failed to read confirmation for '{}': {}", prompt, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_run_incv_returns_written_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifests = [
+            ("crate_a", "[package]\nname = \"crate_a\"\nversion = \"0.4.1\"\n"),
+            (
+                "crate_b",
+                "[package]\nname = \"crate_b\"\nversion = \"0.1.0\"\n\n[dependencies]\ncrate_a = { version = \"0.4.1\" }\n",
+            ),
+            ("crate_c", "[package]\nname = \"crate_c\"\nversion = \"0.2.0\"\n"),
+        ];
+        for (dir, content) in manifests {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+            fs::write(tmp.path().join(dir).join("Cargo.toml"), content).unwrap();
+        }
+
+        let args = IncvArgs {
+            common: CommonArgs { package_name: None },
+            current_version: Some("0.4.1".to_string()),
+            constraint: None,
+            next_version: Some("0.4.2".to_string()),
+            pre: None,
+            date_version: false,
+            yes: true,
+            backup: false,
+            tag: false,
+            skip_files: Vec::new(),
+            skip_submodules: false,
+            force: false,
+            diff: false,
+            dry_run: false,
+            table: false,
+            json: false,
+            lock: false,
+        };
+
+        let mut incv_result =
+            run_incv_in_dir(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+        incv_result.modified_files.sort();
+
+        let expected_files = vec![
+            tmp.path().join("crate_a").join("Cargo.toml"),
+            tmp.path().join("crate_b").join("Cargo.toml"),
+        ];
+        assert_eq!(incv_result.modified_files, expected_files);
+        assert_eq!(incv_result.from_version, "0.4.1");
+        assert_eq!(incv_result.to_version, "0.4.2");
+
+        let mut matched_files: Vec<PathBuf> = incv_result
+            .version_matches
+            .iter()
+            .map(|version_match| PathBuf::from(&version_match.file_path))
+            .collect();
+        matched_files.sort();
+        assert_eq!(matched_files, expected_files);
+        assert!(incv_result
+            .version_matches
+            .iter()
+            .all(|version_match| version_match.pkg_deps.all_versions() == ["0.4.2"]));
+
+        let json = serde_json::to_value(&incv_result).unwrap();
+        assert_eq!(json["to_version"], "0.4.2");
+    }
+
+    #[test]
+    fn test_run_incv_tag_without_commit_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = "[package]\nname = \"crate_a\"\nversion = \"0.4.1\"\n";
+        fs::create_dir_all(tmp.path().join("crate_a")).unwrap();
+        fs::write(tmp.path().join("crate_a").join("Cargo.toml"), manifest).unwrap();
+
+        let args = IncvArgs {
+            common: CommonArgs { package_name: None },
+            current_version: Some("0.4.1".to_string()),
+            constraint: None,
+            next_version: Some("0.4.2".to_string()),
+            pre: None,
+            date_version: false,
+            yes: true,
+            backup: false,
+            tag: true,
+            skip_files: Vec::new(),
+            skip_submodules: false,
+            force: false,
+            diff: false,
+            dry_run: false,
+            table: false,
+            json: false,
+            lock: false,
+        };
+
+        let err = run_incv_in_dir(tmp.path().to_path_buf(), &args, &Config::default()).unwrap_err();
+        assert!(err.to_string().contains("auto_git_commit"));
+        let written = fs::read_to_string(tmp.path().join("crate_a").join("Cargo.toml")).unwrap();
+        assert_eq!(written, manifest);
+    }
+
+    #[test]
+    fn test_run_incv_updates_lock_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("crate_a")).unwrap();
+        fs::write(
+            tmp.path().join("crate_a").join("Cargo.toml"),
+            "[package]\nname = \"crate_a\"\nversion = \"0.4.1\"\n",
+        )
         .unwrap();
+        let mut args = IncvArgs {
+            common: CommonArgs { package_name: None },
+            current_version: Some("0.4.1".to_string()),
+            constraint: None,
+            next_version: Some("0.4.2".to_string()),
+            pre: None,
+            date_version: false,
+            yes: true,
+            backup: false,
+            tag: false,
+            skip_files: Vec::new(),
+            skip_submodules: false,
+            force: false,
+            diff: false,
+            dry_run: false,
+            table: false,
+            json: false,
+            lock: true,
+        };
 
-    if continue_execution {
-    } else {
-        println!("Execution interrupted.");
-        process::exit(1);
+        let err = run_incv_in_dir(tmp.path().to_path_buf(), &args, &Config::default()).unwrap_err();
+        assert!(err.to_string().contains("Cargo.lock"));
+
+        let lock_path = tmp.path().join("Cargo.lock");
+        fs::write(&lock_path, "[[package]]\nname = \"crate_a\"\nversion = \"0.4.1\"\n").unwrap();
+        let incv_result =
+            run_incv_in_dir(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+
+        assert!(incv_result.modified_files.contains(&lock_path));
+        assert_eq!(
+            fs::read_to_string(&lock_path).unwrap(),
+            "[[package]]\nname = \"crate_a\"\nversion = \"0.4.2\"\n"
+        );
+
+        args.lock = false;
+        args.current_version = Some("0.4.2".to_string());
+        args.next_version = Some("0.4.3".to_string());
+        run_incv_in_dir(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+        assert!(fs::read_to_string(&lock_path).unwrap().contains("0.4.2"));
+    }
+
+    #[test]
+    fn test_date_version() {
+        let at = |secs| date_version(UNIX_EPOCH + std::time::Duration::from_secs(secs));
+        assert_eq!(at(0), "1970.1.1");
+        assert_eq!(at(951_868_799), "2000.2.29");
+        assert_eq!(at(1_709_164_800), "2024.2.29");
+        assert_eq!(at(1_735_689_599), "2024.12.31");
+    }
+
+    #[test]
+    fn test_run_incv_pre_release() {
+        assert_eq!(parse_pre_release("rc.1"), Ok(("rc".to_string(), 1)));
+        assert!(parse_pre_release("rc").is_err());
+        assert!(parse_pre_release("rc.x").is_err());
+
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("crate_a")).unwrap();
+        fs::write(
+            tmp.path().join("crate_a").join("Cargo.toml"),
+            "[package]\nname = \"crate_a\"\nversion = \"1.0.0-alpha.1\"\n",
+        )
+        .unwrap();
+        let args = IncvArgs {
+            common: CommonArgs { package_name: None },
+            current_version: Some("1.0.0-alpha.1".to_string()),
+            constraint: None,
+            next_version: None,
+            pre: Some(("alpha".to_string(), 2)),
+            date_version: false,
+            yes: true,
+            backup: false,
+            tag: false,
+            skip_files: Vec::new(),
+            skip_submodules: false,
+            force: false,
+            diff: false,
+            dry_run: false,
+            table: false,
+            json: false,
+            lock: false,
+        };
+
+        let incv_result =
+            run_incv_in_dir(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+
+        assert_eq!(incv_result.to_version, "1.0.0-alpha.2");
+        let written = fs::read_to_string(tmp.path().join("crate_a").join("Cargo.toml")).unwrap();
+        assert!(written.contains(r#"version = "1.0.0-alpha.2""#));
+    }
+
+    #[test]
+    fn test_incv_json_needs_yes() {
+        let parse = |extra: &[&str]| {
+            let args = ["cratup_auto", "incv", "-i", "0.4.1", "-r", "0.4.2", "--json"];
+            Cli::try_parse_from(args.iter().chain(extra))
+        };
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["-y", "--dry-run"]).is_err());
+        assert!(parse(&["-y"]).is_ok());
+    }
+
+    #[test]
+    fn test_run_incv_constraint() {
+        let tmp = tempfile::tempdir().unwrap();
+        for (dir, version) in [("crate_a", "0.4.0"), ("crate_b", "0.4.3"), ("crate_c", "0.3.0")] {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+            fs::write(
+                tmp.path().join(dir).join("Cargo.toml"),
+                format!("[package]\nname = \"{}\"\nversion = \"{}\"\n", dir, version),
+            )
+            .unwrap();
+        }
+        let args = IncvArgs {
+            common: CommonArgs { package_name: None },
+            current_version: None,
+            constraint: Some("^0.4".to_string()),
+            next_version: Some("0.5.0".to_string()),
+            pre: None,
+            date_version: false,
+            yes: true,
+            backup: false,
+            tag: false,
+            skip_files: Vec::new(),
+            skip_submodules: false,
+            force: true,
+            diff: false,
+            dry_run: false,
+            table: false,
+            json: false,
+            lock: false,
+        };
+
+        let mut incv_result =
+            run_incv_in_dir(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+        incv_result.modified_files.sort();
+
+        assert_eq!(
+            incv_result.modified_files,
+            vec![
+                tmp.path().join("crate_a").join("Cargo.toml"),
+                tmp.path().join("crate_b").join("Cargo.toml"),
+            ]
+        );
+        assert_eq!(incv_result.from_version, "^0.4");
+        let untouched = fs::read_to_string(tmp.path().join("crate_c").join("Cargo.toml")).unwrap();
+        assert!(untouched.contains(r#"version = "0.3.0""#));
+    }
+
+    #[test]
+    fn test_run_incv_skip_submodules() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = "[package]\nname = \"crate_a\"\nversion = \"0.4.1\"\n";
+        for dir in ["crate_a", "vendor/lib"] {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+            fs::write(tmp.path().join(dir).join("Cargo.toml"), manifest).unwrap();
+        }
+        fs::write(tmp.path().join("vendor/lib/.git"), "gitdir: ../../.git/modules/lib\n").unwrap();
+
+        let args = IncvArgs {
+            common: CommonArgs { package_name: None },
+            current_version: Some("0.4.1".to_string()),
+            constraint: None,
+            next_version: Some("0.4.2".to_string()),
+            pre: None,
+            date_version: false,
+            yes: true,
+            backup: false,
+            tag: false,
+            skip_files: Vec::new(),
+            skip_submodules: true,
+            force: false,
+            diff: false,
+            dry_run: false,
+            table: false,
+            json: false,
+            lock: false,
+        };
+
+        let incv_result =
+            run_incv_in_dir(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+
+        assert_eq!(
+            incv_result.modified_files,
+            vec![tmp.path().join("crate_a").join("Cargo.toml")]
+        );
+        let vendored = fs::read_to_string(tmp.path().join("vendor/lib/Cargo.toml")).unwrap();
+        assert_eq!(vendored, manifest);
+    }
+
+    #[test]
+    fn test_run_incv_dry_run_writes_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifest = "[package]\nname = \"crate_a\"\nversion = \"0.4.1\"\n";
+        fs::create_dir_all(tmp.path().join("crate_a")).unwrap();
+        fs::write(tmp.path().join("crate_a").join("Cargo.toml"), manifest).unwrap();
+        // An inconsistent version would ask to proceed, a dry run must not ask anything.
+        fs::create_dir_all(tmp.path().join("crate_b")).unwrap();
+        fs::write(
+            tmp.path().join("crate_b").join("Cargo.toml"),
+            "[package]\nname = \"crate_b\"\nversion = \"0.1.0\"\n\n[dependencies]\ncrate_a = \"0.4.0\"\n",
+        )
+        .unwrap();
+
+        for table in [false, true] {
+            let args = IncvArgs {
+                common: CommonArgs { package_name: None },
+                current_version: Some("0.4.1".to_string()),
+                constraint: None,
+                next_version: Some("0.4.2".to_string()),
+            pre: None,
+            date_version: false,
+                yes: false,
+                backup: false,
+                tag: false,
+                skip_files: Vec::new(),
+                skip_submodules: false,
+                force: false,
+                diff: false,
+                dry_run: true,
+                table,
+                json: false,
+                lock: false,
+            };
+
+            let incv_result =
+                run_incv_in_dir(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+
+            assert!(incv_result.modified_files.is_empty());
+            let written =
+                fs::read_to_string(tmp.path().join("crate_a").join("Cargo.toml")).unwrap();
+            assert_eq!(written, manifest);
+        }
+    }
+
+    fn search_args(version: Option<&str>) -> SearchArgs {
+        SearchArgs {
+            common: CommonArgs { package_name: None },
+            version: version.map(str::to_string),
+            version_range: None,
+            version_prefix: None,
+            inverse: false,
+            dev_deps: false,
+            build_deps: false,
+            show_unversioned: false,
+            watch: false,
+            transitive: false,
+            replace: None,
+            format: None,
+            breadth_first: false,
+            output_format: "text".to_string(),
+            jobs: None,
+            count: false,
+            sort_by_matches: false,
+        }
+    }
+
+    #[test]
+    fn test_run_search_returns_matches() {
+        let tmp = tempfile::tempdir().unwrap();
+        let manifests = [
+            ("crate_a", "[package]\nname = \"crate_a\"\nversion = \"0.4.1\"\n"),
+            (
+                "crate_b",
+                "[package]\nname = \"crate_b\"\nversion = \"0.1.0\"\n\n[dependencies]\ncrate_a = { version = \"0.4.1\" }\n",
+            ),
+            ("crate_c", "[package]\nname = \"crate_c\"\nversion = \"0.2.0\"\n"),
+        ];
+        for (dir, content) in manifests {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+            fs::write(tmp.path().join(dir).join("Cargo.toml"), content).unwrap();
+        }
+
+        let args = search_args(Some("0.4.1"));
+        let mut search_instance =
+            new_search(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+        let results = run_search(&mut search_instance, &args).unwrap();
+
+        assert_eq!(MatchMode::of(&search_instance, &args), MatchMode::Exact);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, tmp.path().join("crate_a").join("Cargo.toml"));
+        assert_eq!(results[1].0, tmp.path().join("crate_b").join("Cargo.toml"));
+        assert_eq!(results[1].1.dependencies[0].name, "crate_a");
+
+        // An empty result stays exact without a package name to look up.
+        let args = search_args(Some("9.9.9"));
+        let mut search_instance =
+            new_search(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+        let results = run_search(&mut search_instance, &args).unwrap();
+        assert_eq!(MatchMode::of(&search_instance, &args), MatchMode::Exact);
+        assert!(results.is_empty());
+
+        let args = SearchArgs {
+            common: CommonArgs {
+                package_name: Some("crate_d".to_string()),
+            },
+            ..search_args(None)
+        };
+        let mut search_instance =
+            new_search(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+        let results = run_search(&mut search_instance, &args).unwrap();
+        assert_eq!(MatchMode::of(&search_instance, &args), MatchMode::Fuzzy);
+        assert_eq!(results.len(), 3);
+    }
+
+    #[test]
+    fn test_write_search_count_prints_one_integer() {
+        let tmp = tempfile::tempdir().unwrap();
+        for (dir, version) in [("crate_a", "0.4.1"), ("crate_b", "0.4.1"), ("crate_c", "0.2.0")] {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+            fs::write(
+                tmp.path().join(dir).join("Cargo.toml"),
+                format!("[package]\nname = \"{}\"\nversion = \"{}\"\n", dir, version),
+            )
+            .unwrap();
+        }
+
+        for (version, expected) in [("0.4.1", 2), ("9.9.9", 0)] {
+            let args = SearchArgs {
+                count: true,
+                ..search_args(Some(version))
+            };
+            let mut search_instance =
+                new_search(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+            let mut out = Vec::new();
+            write_search_count(&mut out, &mut search_instance, &args).unwrap();
+
+            let out = String::from_utf8(out).unwrap();
+            assert_eq!(out.lines().count(), 1, "{:?}", out);
+            assert_eq!(out.trim_end().parse::<usize>().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_write_search_csv() {
+        let tmp = tempfile::tempdir().unwrap();
+        let crate_dir = tmp.path().join("odd, name");
+        fs::create_dir_all(&crate_dir).unwrap();
+        fs::write(
+            crate_dir.join("Cargo.toml"),
+            "[package]\nname = \"crate_a\"\nversion = \"0.4.1\"\n",
+        )
+        .unwrap();
+
+        let args = search_args(Some("0.4.1"));
+        let mut search_instance =
+            new_search(tmp.path().to_path_buf(), &args, &Config::default()).unwrap();
+        run_search(&mut search_instance, &args).unwrap();
+        let mut out = Vec::new();
+        let result = write_search_csv(&mut out, &search_instance);
+
+        if cfg!(feature = "csv") {
+            result.unwrap();
+            let expected = format!(
+                "path,kind,name,version\n\"{}\",package,crate_a,0.4.1\n",
+                crate_dir.join("Cargo.toml").display()
+            );
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        } else {
+            let err = result.unwrap_err();
+            assert!(err.to_string().contains("`csv` feature"), "{}", err);
+            assert!(out.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_confirm_with_answers() {
+        assert!(confirm_with("Continue?", |_| Ok(true)));
+        assert!(!confirm_with("Continue?", |_| Ok(false)));
+        assert!(!confirm_with("Continue?", |_| {
+            Err(dialoguer::Error::IO(std::io::Error::other("no terminal")))
+        }));
+    }
+
+    #[test]
+    fn test_confirm_with_passes_prompt() {
+        assert!(confirm_with("Publish 3 crates?", |prompt| {
+            Ok(prompt == "Publish 3 crates?")
+        }));
+    }
+
+    #[test]
+    fn test_verbosity_to_env_filter() {
+        let cases = [
+            (0, 1, "warn"),
+            (1, 1, "warn"),
+            (0, 0, "info"),
+            (1, 0, "debug,cratup_tree_sitter=info,tree_sitter=off"),
+            (2, 0, "trace,tree_sitter=off"),
+            (3, 0, "trace"),
+        ];
+        for (verbose, quiet, expected) in cases {
+            let verbosity = Verbosity { verbose, quiet };
+            assert_eq!(verbosity.to_env_filter(), expected, "-v {} -q {}", verbose, quiet);
+        }
     }
 }
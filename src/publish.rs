@@ -1,24 +1,19 @@
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow};
 use colored::*;
+use cratup_tree_sitter::{DepKind, PackageAndDeps, TomlGrammar, TomlParser};
 use indicatif::{ProgressBar, ProgressStyle};
 use log::debug;
+use rayon::prelude::*;
+use serde::{Serialize, Serializer};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 
 /// Returns true if `path` contains a segment “target”
 fn is_excluded_target_dir(path: &Path) -> bool {
-    // Walk the components in pairs
-    let mut comps = path.components()
-                        .map(|c| c.as_os_str().to_string_lossy())
-                        .peekable();
-
-    while let Some(curr) = comps.next() {
-        if curr == "target" {
-                    return true
-        }
-    }
-    false
+    path.components()
+        .any(|c| c.as_os_str().to_string_lossy() == "target")
 }
 
 /// Find all subdirectories containing a Cargo.toml, excluding any under
@@ -57,16 +52,361 @@ pub fn find_publishable_dirs(current_dir: &Path) -> Vec<PathBuf> {
     publishable_dirs
 }
 
+/// Returns the directories of the `[workspace] members` of the Cargo.toml in
+/// `workspace_root`, which may also be given as the path of that Cargo.toml. Members
+/// without a Cargo.toml are skipped, and the root itself is included when it also has
+/// a `[package]`, as read with `grammar`.
+pub fn find_publishable_dirs_from_workspace(
+    workspace_root: &Path,
+    grammar: TomlGrammar,
+) -> Result<Vec<PathBuf>> {
+    let root = if workspace_root.is_file() {
+        workspace_root.parent().unwrap_or(Path::new("."))
+    } else {
+        workspace_root
+    };
+    let manifest_path = root.join("Cargo.toml");
+    debug!("Reading workspace members from {:?}", manifest_path);
+
+    let (parser, _) = TomlParser::new_from_path(&manifest_path)
+        .with_context(|| format!("Failed to read workspace manifest {:?}", manifest_path))?;
+    let parser = parser.with_grammar(grammar);
+    let mut publishable_dirs = Vec::new();
+    if parser.find_package().is_some() {
+        debug!("Workspace root is also a package: {:?}", root);
+        publishable_dirs.push(root.to_path_buf());
+    }
+    publishable_dirs.extend(
+        parser
+            .find_workspace_members(root)?
+            .into_iter()
+            .filter(|dir| {
+                let has_manifest = dir.join("Cargo.toml").exists();
+                if !has_manifest {
+                    debug!("Skipping member without Cargo.toml: {:?}", dir);
+                }
+                has_manifest
+            }),
+    );
+
+    debug!("Publishable workspace members found: {}", publishable_dirs.len());
+    Ok(publishable_dirs)
+}
+
+/// Reads the directories to publish, in order, from the file at `list_path`, one per
+/// line. Relative paths are resolved against `current_dir`, and blank lines and lines
+/// starting with `#` are skipped. Fails on the first directory that does not exist.
+pub fn read_publish_list(list_path: &Path, current_dir: &Path) -> Result<Vec<PathBuf>> {
+    debug!("Reading the directories to publish from {:?}", list_path);
+    let content = std::fs::read_to_string(list_path)
+        .with_context(|| format!("Failed to read publish list {:?}", list_path))?;
+
+    let dirs = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let dir = current_dir.join(line);
+            if dir.is_dir() {
+                Ok(dir)
+            } else {
+                Err(anyhow!("Directory {:?} of publish list {:?} does not exist", dir, list_path))
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    debug!("Publish list holds {} directories", dirs.len());
+    Ok(dirs)
+}
+
+/// Returns the directories to publish: the workspace members of `from_manifest` when it
+/// is given, else those of `current_dir` when it is a workspace root, else every crate
+/// found by `find_publishable_dirs`.
+pub fn select_publishable_dirs(
+    current_dir: &Path,
+    from_manifest: Option<&Path>,
+    grammar: TomlGrammar,
+) -> Result<Vec<PathBuf>> {
+    if let Some(workspace_root) = from_manifest {
+        debug!("Using the workspace given with --from-manifest: {:?}", workspace_root);
+        return find_publishable_dirs_from_workspace(workspace_root, grammar);
+    }
+
+    let is_workspace_root = TomlParser::new_from_path(&current_dir.join("Cargo.toml"))
+        .is_ok_and(|(parser, _)| parser.find_workspace().is_some());
+    if is_workspace_root {
+        debug!("{:?} is a workspace root, reading its members", current_dir);
+        find_publishable_dirs_from_workspace(current_dir, grammar)
+    } else {
+        Ok(find_publishable_dirs(current_dir))
+    }
+}
+
+/// The command run in each module directory to publish it.
+#[derive(Debug, Clone)]
+pub struct PublishFlags {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Features passed as `--features <feat1,feat2>`.
+    pub features: Vec<String>,
+    /// Pass `--all-features`.
+    pub all_features: bool,
+    /// TOML version the package of each Cargo.toml is read with, to order the crates.
+    pub grammar: TomlGrammar,
+    /// Skip modules whose current version is already published on crates.io.
+    pub check_crates_io: bool,
+    /// Program used to query the crates.io API when `check_crates_io` is set.
+    pub curl_program: String,
+}
+
+impl Default for PublishFlags {
+    fn default() -> Self {
+        PublishFlags {
+            program: "cargo".to_string(),
+            args: vec!["publish".to_string()],
+            features: Vec::new(),
+            all_features: false,
+            grammar: TomlGrammar::default(),
+            check_crates_io: false,
+            curl_program: "curl".to_string(),
+        }
+    }
+}
+
+impl PublishFlags {
+    /// Returns `args` followed by the feature flags.
+    fn command_args(&self) -> Vec<String> {
+        let mut args = self.args.clone();
+        if self.all_features {
+            args.push("--all-features".to_string());
+        }
+        if !self.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(self.features.join(","));
+        }
+        args
+    }
+}
+
 #[derive(Debug)]
 pub enum PublishState {
-    Published(String),
+    /// Published directory along with how long `cargo publish` took.
+    Published(String, Duration),
     Unpublished(String),
+    /// Not published because the publish command failed, with the exit code of its last
+    /// attempt, or `None` when it could not be run or was killed by a signal.
+    Failed(String, Option<i32>),
+    /// Not published because its current version is already on crates.io.
+    Skipped(String),
+}
+
+/// A `PublishState` as `publish_modules_json` writes it, e.g.
+/// `{"state": "failed", "dir": "crates/a", "exit_code": 101}`.
+#[derive(Serialize)]
+struct PublishStateRecord<'a> {
+    state: &'static str,
+    dir: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+}
+
+impl Serialize for PublishState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let record = match self {
+            PublishState::Published(dir, duration) => PublishStateRecord {
+                state: "published",
+                dir,
+                duration_secs: Some(duration.as_secs_f64()),
+                exit_code: None,
+            },
+            PublishState::Unpublished(dir) => PublishStateRecord {
+                state: "unpublished",
+                dir,
+                duration_secs: None,
+                exit_code: None,
+            },
+            PublishState::Failed(dir, exit_code) => PublishStateRecord {
+                state: "failed",
+                dir,
+                duration_secs: None,
+                exit_code: *exit_code,
+            },
+            PublishState::Skipped(dir) => PublishStateRecord {
+                state: "skipped",
+                dir,
+                duration_secs: None,
+                exit_code: None,
+            },
+        };
+        record.serialize(serializer)
+    }
+}
+
+/// Counts of the publish outcomes along with the wall-clock time of the whole run.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct PublishSummary {
+    pub total: usize,
+    pub published: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    #[serde(rename = "duration_secs", serialize_with = "serialize_duration_secs")]
+    pub duration: Duration,
+}
+
+/// Writes a duration as a number of seconds.
+fn serialize_duration_secs<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+impl PublishSummary {
+    /// Tallies the final publish states. Modules left unpublished count as failed.
+    pub fn from_states(publish_states: &[PublishState], duration: Duration) -> Self {
+        let published = publish_states
+            .iter()
+            .filter(|state| matches!(state, PublishState::Published(..)))
+            .count();
+        let failed = publish_states
+            .iter()
+            .filter(|state| matches!(state, PublishState::Unpublished(_) | PublishState::Failed(..)))
+            .count();
+        let skipped = publish_states
+            .iter()
+            .filter(|state| matches!(state, PublishState::Skipped(_)))
+            .count();
+
+        PublishSummary {
+            total: publish_states.len(),
+            published,
+            failed,
+            skipped,
+            duration,
+        }
+    }
+}
+
+impl std::fmt::Display for PublishSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Attempted: {}, Published: {}, Failed: {}, Skipped: {} in {:.1}s",
+            self.total,
+            self.published,
+            self.failed,
+            self.skipped,
+            self.duration.as_secs_f64()
+        )
+    }
+}
+
+/// Final publish states together with their summary.
+#[derive(Debug)]
+pub struct PublishResult {
+    pub states: Vec<PublishState>,
+    pub summary: PublishSummary,
+}
+
+impl PublishResult {
+    /// Returns the result as a `{"summary": {...}, "modules": [...]}` JSON object.
+    pub fn to_json(&self) -> Result<String> {
+        #[derive(Serialize)]
+        struct PublishJson<'a> {
+            summary: &'a PublishSummary,
+            modules: &'a [PublishState],
+        }
+
+        serde_json::to_string_pretty(&PublishJson {
+            summary: &self.summary,
+            modules: &self.states,
+        })
+        .context("Failed to serialize the publish result")
+    }
+}
+
+/// Publishes `dirs` with `publish_modules` and returns the result as JSON, see
+/// `PublishResult::to_json`.
+pub fn publish_modules_json(dirs: &[PathBuf], flags: &PublishFlags) -> Result<String> {
+    publish_modules(dirs, flags)?.to_json()
+}
+
+/// Returns true if `version` of the crate `name` is published on crates.io, asking the
+/// crates.io API with `curl_program`, normally `curl`. Fails when it cannot be run, when it
+/// fails, e.g. without network access, or when crates.io answers anything but 200 or 404.
+pub fn check_version_on_crates_io(curl_program: &str, name: &str, version: &str) -> Result<bool> {
+    let url = format!("https://crates.io/api/v1/crates/{}/{}", name, version);
+    debug!("Checking crates.io for {} {}: {}", name, version, url);
+
+    // crates.io rejects requests without a user agent.
+    let output = Command::new(curl_program)
+        .args(["-sS", "-o", "/dev/null", "-w", "%{http_code}"])
+        .args(["-A", concat!("cratup_auto/", env!("CARGO_PKG_VERSION"))])
+        .arg(&url)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {} for {}", curl_program, url))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} failed for {} ({}): {}",
+            curl_program,
+            url,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let status_code = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    debug!("crates.io answered {} for {} {}", status_code, name, version);
+    match status_code.as_str() {
+        "200" => Ok(true),
+        "404" => Ok(false),
+        _ => anyhow::bail!(
+            "Unexpected answer '{}' from crates.io for {} {}",
+            status_code,
+            name,
+            version
+        ),
+    }
+}
+
+/// Reads the package name and version from the Cargo.toml in `dir` with `grammar`.
+fn package_name_and_version(dir: &str, grammar: TomlGrammar) -> Result<(String, String)> {
+    let (parser, _) = TomlParser::new_from_path(&Path::new(dir).join("Cargo.toml"))?;
+    let pkg = parser
+        .with_grammar(grammar)
+        .find_package()
+        .with_context(|| format!("No [package] found in {}", dir))?;
+    Ok((
+        pkg.name.trim_matches('"').to_string(),
+        pkg.version.trim_matches('"').to_string(),
+    ))
+}
+
+/// Returns the initial state of `dir`: skipped when `flags.check_crates_io` is set and its
+/// current version is already on crates.io, unpublished otherwise. Fails when the check
+/// cannot be made, rather than publishing without it.
+fn initial_state(dir: String, flags: &PublishFlags) -> Result<PublishState> {
+    if !flags.check_crates_io {
+        return Ok(PublishState::Unpublished(dir));
+    }
+
+    let (name, version) = package_name_and_version(&dir, flags.grammar)?;
+    let published = check_version_on_crates_io(&flags.curl_program, &name, &version)
+        .with_context(|| format!("Failed to check crates.io for {} {}", name, version))?;
+    if published {
+        debug!("Version of {} already on crates.io, skipping", dir);
+        Ok(PublishState::Skipped(dir))
+    } else {
+        Ok(PublishState::Unpublished(dir))
+    }
 }
 
 /// Iterates over the vector in a nested loop. Only directories that are still unpublished
-/// will have the publish command executed. If the publish command succeeds, the state is updated.
-pub fn publish_modules(dirs: &[PathBuf]) -> Result<Vec<PublishState>> {
+/// will have the publish command of `flags` executed. If the publish command succeeds, the
+/// state is updated.
+pub fn publish_modules(dirs: &[PathBuf], flags: &PublishFlags) -> Result<PublishResult> {
     debug!("Starting module publication for {} directories", dirs.len());
+    let start = Instant::now();
     debug!("Input directories: {:?}", dirs);
 
     // Convert incoming directories into a vector of PublishState
@@ -74,10 +414,10 @@ pub fn publish_modules(dirs: &[PathBuf]) -> Result<Vec<PublishState>> {
         .iter()
         .map(|p| {
             let dir = p.to_string_lossy().into_owned();
-            debug!("Initializing Unpublished state for directory: {}", dir);
-            PublishState::Unpublished(dir)
+            debug!("Initializing state for directory: {}", dir);
+            initial_state(dir, flags)
         })
-        .collect();
+        .collect::<Result<_>>()?;
 
     // Compute total iterations as the square of the length.
     let total_iterations = (publish_states.len() * publish_states.len()) as u64;
@@ -105,21 +445,22 @@ pub fn publish_modules(dirs: &[PathBuf]) -> Result<Vec<PublishState>> {
             debug!("Processing module {} of {}", idx + 1, num_modules);
 
             // Borrow state immutably to check its variant and clone the directory string
-            if let PublishState::Unpublished(dir) = state {
+            if let PublishState::Unpublished(dir) | PublishState::Failed(dir, _) = state {
                 let dir_clone = dir.clone(); // Now work with a full owned copy
                 debug!("Attempting to publish directory: {}", dir_clone);
 
-                match publish_module(&dir_clone, "publish") {
-                    Ok(_) => {
+                match publish_module(&dir_clone, flags) {
+                    Ok(elapsed) => {
                         debug!("Successfully published directory: {}", dir_clone);
                         // Now we can safely update *state since no borrow is active.
-                        *state = PublishState::Published(dir_clone.clone());
+                        *state = PublishState::Published(dir_clone.clone(), elapsed);
                         debug!("Updated state to Published for directory: {}", dir_clone);
 
                         progress = true;
                     }
-                    Err(e) => {
-                        debug!("Publish failed for {}: {:?}", dir_clone, e);
+                    Err(exit_code) => {
+                        debug!("Publish failed for {} with exit code {:?}", dir_clone, exit_code);
+                        *state = PublishState::Failed(dir_clone, exit_code);
                     }
                 }
             } else {
@@ -145,10 +486,272 @@ pub fn publish_modules(dirs: &[PathBuf]) -> Result<Vec<PublishState>> {
     debug!("Publication process completed");
     debug!("Final states: {:?}", publish_states);
 
-    Ok(publish_states)
+    let summary = PublishSummary::from_states(&publish_states, start.elapsed());
+    debug!("Publish summary: {:?}", summary);
+
+    Ok(PublishResult {
+        states: publish_states,
+        summary,
+    })
+}
+
+/// Same as `publish_modules`, but runs up to `jobs` publish commands at the same time.
+///
+/// The crates are published level by level in dependency order, see `publish_levels`,
+/// so a crate is only published once every workspace crate it depends on is. The crates
+/// of a level are published concurrently. A crate whose dependency failed, or was
+/// skipped, is skipped in turn and stays unpublished. The states are in the order of `dirs`.
+pub fn publish_modules_parallel(
+    dirs: &[PathBuf],
+    jobs: usize,
+    flags: &PublishFlags,
+) -> Result<PublishResult> {
+    debug!(
+        "Starting parallel module publication for {} directories with {} jobs",
+        dirs.len(),
+        jobs
+    );
+    let start = Instant::now();
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.max(1))
+        .build()?;
+
+    let dir_strs: Vec<String> = dirs
+        .iter()
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    let dependencies = workspace_dependencies(dirs, flags.grammar);
+    let levels = publish_levels(&dependencies);
+    debug!("Publish levels: {:?}", levels);
+
+    let pb = ProgressBar::new(dirs.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta}) {msg}")
+            .expect("Failed to set progress bar template"),
+    );
+
+    // Crates already on crates.io are skipped up front. Their dependents are published as
+    // if they had just been.
+    let mut states: Vec<Option<PublishState>> = pool
+        .install(|| {
+            dir_strs
+                .par_iter()
+                .map(|dir| initial_state(dir.clone(), flags))
+                .collect::<Result<Vec<_>>>()
+        })?
+        .into_iter()
+        .map(|state| matches!(state, PublishState::Skipped(_)).then_some(state))
+        .collect();
+    let mut not_published = vec![false; dirs.len()];
+    for (level_idx, level) in levels.iter().enumerate() {
+        let (ready, blocked): (Vec<usize>, Vec<usize>) = level
+            .iter()
+            .filter(|&&idx| states[idx].is_none())
+            .partition(|&&idx| !dependencies[idx].iter().any(|&dep| not_published[dep]));
+        for idx in blocked {
+            debug!("Skipping {}: one of its dependencies was not published", dir_strs[idx]);
+            not_published[idx] = true;
+            states[idx] = Some(PublishState::Unpublished(dir_strs[idx].clone()));
+        }
+
+        debug!("Publishing level {} with {} module(s)", level_idx + 1, ready.len());
+        let results: Vec<(usize, Result<Duration, Option<i32>>)> = pool.install(|| {
+            ready
+                .par_iter()
+                .map(|&idx| (idx, publish_module(&dir_strs[idx], flags)))
+                .collect()
+        });
+        for (idx, result) in results {
+            let dir = dir_strs[idx].clone();
+            states[idx] = Some(match result {
+                Ok(elapsed) => {
+                    debug!("Successfully published directory: {}", dir);
+                    pb.inc(1);
+                    PublishState::Published(dir, elapsed)
+                }
+                Err(exit_code) => {
+                    debug!("Publish failed for {} with exit code {:?}", dir, exit_code);
+                    not_published[idx] = true;
+                    PublishState::Failed(dir, exit_code)
+                }
+            });
+        }
+    }
+
+    pb.finish_with_message("All publish commands completed.");
+
+    let publish_states: Vec<PublishState> = states
+        .into_iter()
+        .map(|state| state.expect("Every module is in one publish level"))
+        .collect();
+    debug!("Final states: {:?}", publish_states);
+
+    let summary = PublishSummary::from_states(&publish_states, start.elapsed());
+    debug!("Publish summary: {:?}", summary);
+
+    Ok(PublishResult {
+        states: publish_states,
+        summary,
+    })
+}
+
+/// Returns, for each crate of `dirs`, the indices of the other crates of `dirs` it depends
+/// on through its `[dependencies]`, `[build-dependencies]` or target dependencies. Its
+/// `[dev-dependencies]` are left out, they are not needed to publish it. A crate whose
+/// Cargo.toml cannot be read depends on nothing.
+fn workspace_dependencies(dirs: &[PathBuf], grammar: TomlGrammar) -> Vec<Vec<usize>> {
+    let manifests: Vec<Option<PackageAndDeps>> = dirs
+        .iter()
+        .map(|dir| {
+            let manifest_path = dir.join("Cargo.toml");
+            match TomlParser::new_from_path(&manifest_path) {
+                Ok((parser, _)) => parser
+                    .with_grammar(grammar)
+                    .find_package_and_deps()
+                    .map(PackageAndDeps::from),
+                Err(e) => {
+                    debug!("Failed to read {:?}: {}", manifest_path, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    let names: Vec<Option<&str>> = manifests
+        .iter()
+        .map(|manifest| {
+            let package = manifest.as_ref()?.package.as_ref()?;
+            Some(package.name.trim_matches('"'))
+        })
+        .collect();
+
+    manifests
+        .iter()
+        .enumerate()
+        .map(|(idx, manifest)| {
+            let Some(manifest) = manifest else {
+                return Vec::new();
+            };
+            let target_dependencies = manifest
+                .target_dependencies
+                .iter()
+                .map(|(_, dep)| dep)
+                .filter(|dep| dep.kind != DepKind::Dev);
+            let mut deps: Vec<usize> = manifest
+                .dependencies
+                .iter()
+                .chain(&manifest.build_dependencies)
+                .chain(target_dependencies)
+                .filter_map(|dep| {
+                    let dep_name = dep.name.trim_matches('"');
+                    names.iter().position(|name| *name == Some(dep_name))
+                })
+                .filter(|&dep| dep != idx)
+                .collect();
+            deps.sort_unstable();
+            deps.dedup();
+            deps
+        })
+        .collect()
+}
+
+/// Sorts the crates of `dependencies`, as returned by `workspace_dependencies`,
+/// topologically into levels: the first level holds the crates without dependencies, and
+/// every other crate goes one level after the last of its dependencies. The crates of a
+/// level are sorted by index. Crates in a dependency cycle go into a last level of their own.
+fn publish_levels(dependencies: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut placed = vec![false; dependencies.len()];
+    let mut levels: Vec<Vec<usize>> = Vec::new();
+    loop {
+        let level: Vec<usize> = (0..dependencies.len())
+            .filter(|&idx| !placed[idx] && dependencies[idx].iter().all(|&dep| placed[dep]))
+            .collect();
+        if level.is_empty() {
+            break;
+        }
+        for &idx in &level {
+            placed[idx] = true;
+        }
+        levels.push(level);
+    }
+
+    let cyclic: Vec<usize> = (0..dependencies.len()).filter(|&idx| !placed[idx]).collect();
+    if !cyclic.is_empty() {
+        debug!("Crates in a dependency cycle: {:?}", cyclic);
+        levels.push(cyclic);
+    }
+    levels
 }
 
-pub fn print_modules(publish_states: &[PublishState]) {
+/// Prints the publish states, either as an aligned table or, when `no_table` is set,
+/// as the plain published/unpublished lists. With `verbose`, failed modules are shown
+/// with the exit code of their publish command.
+pub fn print_modules(publish_states: &[PublishState], no_table: bool, verbose: bool) {
+    if no_table {
+        print_modules_list(publish_states, verbose);
+    } else {
+        println!("{}", print_modules_table(publish_states, verbose));
+    }
+}
+
+/// Describes the exit code of a failed publish command.
+fn exit_code_display(exit_code: Option<i32>) -> String {
+    match exit_code {
+        Some(code) => format!("exit code {}", code),
+        None => "no exit code".to_string(),
+    }
+}
+
+/// Formats the publish states as a table with the directory names padded to the same width,
+/// a colored status column and the time each successful publish took. With `verbose`,
+/// the last column of failed modules holds the exit code of their publish command.
+pub fn print_modules_table(publish_states: &[PublishState], verbose: bool) -> String {
+    debug!("Building publish table for {} modules", publish_states.len());
+
+    let header = ("Module", "Status", "Duration");
+    let rows: Vec<(&str, &str, String)> = publish_states
+        .iter()
+        .map(|state| match state {
+            PublishState::Published(module, elapsed) => {
+                (module.as_str(), "OK", format!("{:.1}s", elapsed.as_secs_f64()))
+            }
+            PublishState::Unpublished(module) => (module.as_str(), "FAIL", "-".to_string()),
+            PublishState::Failed(module, exit_code) if verbose => {
+                (module.as_str(), "FAIL", exit_code_display(*exit_code))
+            }
+            PublishState::Failed(module, _) => (module.as_str(), "FAIL", "-".to_string()),
+            PublishState::Skipped(module) => (module.as_str(), "SKIP", "-".to_string()),
+        })
+        .collect();
+
+    let module_width = rows
+        .iter()
+        .map(|(module, _, _)| module.len())
+        .chain(std::iter::once(header.0.len()))
+        .max()
+        .unwrap_or_default();
+    let status_width = header.1.len();
+
+    let mut lines = vec![format!(
+        "{:<module_width$}  {:<status_width$}  {}",
+        header.0, header.1, header.2
+    )];
+    lines.extend(rows.into_iter().map(|(module, status, duration)| {
+        // Pad before coloring so the escape codes do not count towards the width.
+        let padded_status = format!("{:<status_width$}", status);
+        let colored_status = match status {
+            "OK" => padded_status.green(),
+            "SKIP" => padded_status.yellow(),
+            _ => padded_status.red(),
+        };
+        format!("{:<module_width$}  {}  {}", module, colored_status, duration)
+    }));
+
+    lines.join("\n")
+}
+
+fn print_modules_list(publish_states: &[PublishState], verbose: bool) {
     debug!("Starting to print module publication status");
     debug!("Total modules to print: {}", publish_states.len());
 
@@ -158,7 +761,7 @@ pub fn print_modules(publish_states: &[PublishState]) {
     // Track published count for debugging
     let mut published_count = 0;
     for state in publish_states {
-        if let PublishState::Published(module) = state {
+        if let PublishState::Published(module, _) = state {
             debug!("Printing published module: {}", module);
             println!("{}", module.green());
             published_count += 1;
@@ -172,33 +775,70 @@ pub fn print_modules(publish_states: &[PublishState]) {
     // Track unpublished count for debugging
     let mut unpublished_count = 0;
     for state in publish_states {
-        if let PublishState::Unpublished(module) = state {
-            debug!("Printing unpublished module: {}", module);
-            println!("{}", module.red());
-            unpublished_count += 1;
+        match state {
+            PublishState::Unpublished(module) => {
+                debug!("Printing unpublished module: {}", module);
+                println!("{}", module.red());
+                unpublished_count += 1;
+            }
+            PublishState::Failed(module, exit_code) => {
+                debug!("Printing failed module: {} ({:?})", module, exit_code);
+                if verbose {
+                    println!("{} ({})", module.red(), exit_code_display(*exit_code));
+                } else {
+                    println!("{}", module.red());
+                }
+                unpublished_count += 1;
+            }
+            _ => {}
         }
     }
     debug!("Printed {} unpublished modules", unpublished_count);
 
+    let skipped: Vec<&String> = publish_states
+        .iter()
+        .filter_map(|state| match state {
+            PublishState::Skipped(module) => Some(module),
+            _ => None,
+        })
+        .collect();
+    if !skipped.is_empty() {
+        println!("\nSkipped modules (already on crates.io):");
+        for module in skipped {
+            debug!("Printing skipped module: {}", module);
+            println!("{}", module.yellow());
+        }
+    }
+
     debug!(
         "Finished printing modules ({} published, {} unpublished)",
         published_count, unpublished_count
     );
 }
 
-fn publish_module(dir: &str, command: &str) -> Result<()> {
-    debug!("Attempting to publish module in directory: {}", dir);
-    debug!("Using cargo command: {}", command);
-
-    let mut cmd = Command::new("cargo");
-    cmd.arg(command)
+/// Builds the publish command of `flags`, run in `dir` with its output discarded.
+fn publish_command(dir: &str, flags: &PublishFlags) -> Command {
+    let mut cmd = Command::new(&flags.program);
+    cmd.args(flags.command_args())
         .current_dir(dir)
         .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::null());
+    cmd
+}
+
+/// Runs the publish command of `flags` in `dir` and returns how long it took when it succeeds.
+/// On failure, returns the exit code of the command, `None` when it could not be run or
+/// was killed by a signal.
+fn publish_module(dir: &str, flags: &PublishFlags) -> Result<Duration, Option<i32>> {
+    debug!("Attempting to publish module in directory: {}", dir);
+    debug!("Using command: {} {:?}", flags.program, flags.command_args());
+
+    let mut cmd = publish_command(dir, flags);
 
     debug!("Constructed command: {:?}", cmd);
 
+    let start = Instant::now();
     let status = match cmd.status() {
         Ok(s) => {
             debug!("Command executed successfully, status: {}", s);
@@ -206,23 +846,469 @@ fn publish_module(dir: &str, command: &str) -> Result<()> {
         }
         Err(e) => {
             debug!("Command execution failed: {:?}", e);
-            return Err(e.into());
+            return Err(None);
         }
     };
 
     if status.success() {
         debug!("Publish succeeded for directory: {}", dir);
-        Ok(())
+        Ok(start.elapsed())
     } else {
         debug!(
             "Publish failed for directory: {}, exit status: {:?}",
             dir,
             status.code()
         );
-        Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Publish failed for {} with status {:?}", dir, status.code()),
+        Err(status.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::strip_ansi;
+
+    #[test]
+    fn test_print_modules_table_alignment() {
+        let states = vec![
+            PublishState::Published("crates/a".to_string(), Duration::from_millis(1500)),
+            PublishState::Unpublished("crates/longer_name".to_string()),
+            PublishState::Published("b".to_string(), Duration::from_secs(12)),
+        ];
+
+        let table = strip_ansi(&print_modules_table(&states, false));
+        let expected = "\
+Module              Status  Duration
+crates/a            OK      1.5s
+crates/longer_name  FAIL    -
+b                   OK      12.0s";
+        assert_eq!(table, expected);
+
+        // Every status column starts at the same offset.
+        let offsets: Vec<usize> = table
+            .lines()
+            .skip(1)
+            .map(|line| line.find(['O', 'F']).unwrap())
+            .collect();
+        assert!(offsets.iter().all(|&o| o == offsets[0]));
+    }
+
+    #[test]
+    fn test_publish_summary_counts() {
+        let states = vec![
+            PublishState::Published("a".to_string(), Duration::from_secs(1)),
+            PublishState::Unpublished("b".to_string()),
+            PublishState::Published("c".to_string(), Duration::from_secs(2)),
+            PublishState::Unpublished("d".to_string()),
+            PublishState::Published("e".to_string(), Duration::from_secs(3)),
+        ];
+
+        let summary = PublishSummary::from_states(&states, Duration::from_millis(32_400));
+        assert_eq!(summary.total, 5);
+        assert_eq!(summary.published, 3);
+        assert_eq!(summary.failed, 2);
+        assert_eq!(summary.skipped, 0);
+        assert_eq!(
+            summary.published + summary.failed + summary.skipped,
+            summary.total
+        );
+        assert_eq!(
+            summary.to_string(),
+            "Attempted: 5, Published: 3, Failed: 2, Skipped: 0 in 32.4s"
+        );
+    }
+
+    #[test]
+    fn test_publish_levels_follow_dependencies() {
+        // 0 <- 1 <- 2, 3 on its own, 4 and 5 depending on each other.
+        let dependencies = vec![vec![], vec![0], vec![1], vec![], vec![5], vec![4]];
+
+        assert_eq!(
+            publish_levels(&dependencies),
+            vec![vec![0, 3], vec![1], vec![2], vec![4, 5]]
+        );
+    }
+
+    /// Writes crates `app` depending on `core`, `core` depending on `base` and `tool` on
+    /// its own, and returns their directories, dependents first.
+    fn write_dependent_crates(root: &Path) -> Vec<PathBuf> {
+        let manifests = [
+            ("app", "[dependencies]\ncore = { version = \"0.1.0\", path = \"../core\" }\n"),
+            ("core", "[build-dependencies]\nbase = { version = \"0.1.0\", path = \"../base\" }\n"),
+            ("base", "[dev-dependencies]\napp = { version = \"0.1.0\", path = \"../app\" }\n"),
+            ("tool", ""),
+        ];
+        manifests
+            .iter()
+            .map(|(name, deps)| {
+                let dir = root.join(name);
+                std::fs::create_dir_all(&dir).unwrap();
+                std::fs::write(
+                    dir.join("Cargo.toml"),
+                    format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n\n{}", name, deps),
+                )
+                .unwrap();
+                dir
+            })
+            .collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_publish_modules_parallel_publishes_in_dependency_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dirs = write_dependent_crates(tmp.path());
+        let log = tmp.path().join("published.log");
+        let flags = PublishFlags {
+            program: crate::test_support::fake_program(
+                tmp.path(),
+                "publish",
+                &format!("basename \"$PWD\" >> {:?}\n", log),
+            )
+            .to_string_lossy()
+            .into_owned(),
+            args: Vec::new(),
+            ..PublishFlags::default()
+        };
+
+        let result = publish_modules_parallel(&dirs, 4, &flags).unwrap();
+
+        assert_eq!(result.summary.published, 4);
+        let published = std::fs::read_to_string(&log).unwrap();
+        let order: Vec<&str> = published.lines().collect();
+        let position = |name: &str| order.iter().position(|published| *published == name).unwrap();
+        assert_eq!(order.len(), 4, "{:?}", order);
+        assert!(position("base") < position("core"), "{:?}", order);
+        assert!(position("core") < position("app"), "{:?}", order);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_publish_modules_parallel_skips_dependents_of_failed_crates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dirs = write_dependent_crates(tmp.path());
+        let flags = PublishFlags {
+            program: crate::test_support::fake_program(
+                tmp.path(),
+                "publish",
+                "[ \"$(basename \"$PWD\")\" != core ]\n",
+            )
+            .to_string_lossy()
+            .into_owned(),
+            args: Vec::new(),
+            ..PublishFlags::default()
+        };
+
+        let result = publish_modules_parallel(&dirs, 2, &flags).unwrap();
+
+        assert!(matches!(result.states[0], PublishState::Unpublished(_)));
+        assert!(matches!(result.states[1], PublishState::Failed(_, Some(1))));
+        assert!(matches!(result.states[2], PublishState::Published(..)));
+        assert!(matches!(result.states[3], PublishState::Published(..)));
+    }
+
+    #[test]
+    fn test_publish_modules_parallel_reports_failures() {
+        let tmp = tempfile::tempdir().unwrap();
+        let flags = PublishFlags {
+            program: "false".to_string(),
+            args: Vec::new(),
+            ..PublishFlags::default()
+        };
+
+        let result = publish_modules_parallel(&[tmp.path().to_path_buf()], 2, &flags).unwrap();
+
+        assert_eq!(result.summary.failed, 1);
+        assert!(matches!(result.states[0], PublishState::Failed(_, Some(1))));
+    }
+
+    /// Writes a script exiting with `code` and returns its path.
+    #[cfg(unix)]
+    fn fake_exit(dir: &Path, code: i32) -> String {
+        let name = format!("exit_{}", code);
+        crate::test_support::fake_program(dir, &name, &format!("exit {}\n", code))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_state_keeps_exit_code() {
+        let tmp = tempfile::tempdir().unwrap();
+        let flags = PublishFlags {
+            program: fake_exit(tmp.path(), 101),
+            args: Vec::new(),
+            ..PublishFlags::default()
+        };
+
+        let serial = publish_modules(&[tmp.path().to_path_buf()], &flags).unwrap();
+        let parallel = publish_modules_parallel(&[tmp.path().to_path_buf()], 2, &flags).unwrap();
+
+        for result in [serial, parallel] {
+            assert_eq!(result.summary.failed, 1);
+            assert!(matches!(result.states[0], PublishState::Failed(_, Some(101))));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_publish_modules_json_summary() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dirs = vec![tmp.path().join("a"), tmp.path().join("b"), tmp.path().join("c")];
+        for dir in &dirs {
+            std::fs::create_dir(dir).unwrap();
+        }
+        let flags = PublishFlags {
+            program: fake_exit(tmp.path(), 101),
+            args: Vec::new(),
+            ..PublishFlags::default()
+        };
+
+        let json = publish_modules_json(&dirs, &flags).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["summary"]["total"], dirs.len());
+        assert_eq!(value["summary"]["failed"], 3);
+        assert!(value["summary"]["duration_secs"].is_f64());
+        let modules = value["modules"].as_array().unwrap();
+        assert_eq!(modules.len(), 3);
+        assert_eq!(modules[0]["state"], "failed");
+        assert_eq!(modules[0]["dir"], dirs[0].to_string_lossy().as_ref());
+        assert_eq!(modules[0]["exit_code"], 101);
+    }
+
+    #[test]
+    fn test_publish_state_json_records() {
+        let states = vec![
+            PublishState::Published("a".to_string(), Duration::from_millis(1500)),
+            PublishState::Skipped("b".to_string()),
+            PublishState::Failed("c".to_string(), None),
+        ];
+
+        let value = serde_json::to_value(&states).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!([
+                {"state": "published", "dir": "a", "duration_secs": 1.5},
+                {"state": "skipped", "dir": "b"},
+                {"state": "failed", "dir": "c"},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_failed_state_without_program() {
+        let tmp = tempfile::tempdir().unwrap();
+        let flags = PublishFlags {
+            program: tmp.path().join("missing").to_string_lossy().into_owned(),
+            args: Vec::new(),
+            ..PublishFlags::default()
+        };
+
+        let result = publish_modules(&[tmp.path().to_path_buf()], &flags).unwrap();
+
+        assert!(matches!(result.states[0], PublishState::Failed(_, None)));
+    }
+
+    #[test]
+    fn test_print_modules_table_verbose_exit_code() {
+        let states = vec![
+            PublishState::Failed("a".to_string(), Some(101)),
+            PublishState::Failed("b".to_string(), None),
+        ];
+
+        assert_eq!(
+            strip_ansi(&print_modules_table(&states, true)),
+            "Module  Status  Duration\na       FAIL    exit code 101\nb       FAIL    no exit code"
+        );
+        assert_eq!(
+            strip_ansi(&print_modules_table(&states, false)),
+            "Module  Status  Duration\na       FAIL    -\nb       FAIL    -"
+        );
+    }
+
+    /// Writes a fake `curl` printing 200 for the URLs containing `published`, 404 otherwise.
+    #[cfg(unix)]
+    fn fake_curl(dir: &Path, published: &str) -> String {
+        let body = format!(
+            "case \"$*\" in\n  *{}*) printf 200 ;;\n  *) printf 404 ;;\nesac\n",
+            published
+        );
+        crate::test_support::fake_program(dir, "curl", &body)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn write_crate(root: &Path, name: &str, version: &str) -> PathBuf {
+        let dir = root.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            format!("[package]\nname = \"{}\"\nversion = \"{}\"\n", name, version),
         )
-        .into())
+        .unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_version_on_crates_io_status_codes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let curl = fake_curl(tmp.path(), "crates/alpha/0.1.0");
+
+        assert!(check_version_on_crates_io(&curl, "alpha", "0.1.0").unwrap());
+        assert!(!check_version_on_crates_io(&curl, "alpha", "0.2.0").unwrap());
+
+        let unexpected = crate::test_support::fake_program(tmp.path(), "busy", "printf 503\n");
+        let error = check_version_on_crates_io(&unexpected.to_string_lossy(), "alpha", "0.1.0")
+            .unwrap_err();
+        assert!(error.to_string().contains("503"), "{}", error);
+
+        // curl's own error message is passed on.
+        let offline = crate::test_support::fake_program(
+            tmp.path(),
+            "offline",
+            "echo 'Could not resolve host: crates.io' >&2\nprintf 000\nexit 6\n",
+        );
+        let error = check_version_on_crates_io(&offline.to_string_lossy(), "alpha", "0.1.0")
+            .unwrap_err();
+        assert!(error.to_string().contains("Could not resolve host"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_publish_modules_skips_versions_on_crates_io() {
+        let tmp = tempfile::tempdir().unwrap();
+        let curl = fake_curl(tmp.path(), "crates/alpha/0.1.0");
+        let beta = write_crate(tmp.path(), "beta", "0.3.0");
+        // beta depends on alpha, which is already published.
+        std::fs::write(
+            beta.join("Cargo.toml"),
+            "[package]\nname = \"beta\"\nversion = \"0.3.0\"\n\n[dependencies]\nalpha = \"0.1.0\"\n",
+        )
+        .unwrap();
+        let dirs = vec![write_crate(tmp.path(), "alpha", "0.1.0"), beta];
+        let flags = PublishFlags {
+            program: "true".to_string(),
+            args: Vec::new(),
+            check_crates_io: true,
+            curl_program: curl,
+            ..PublishFlags::default()
+        };
+
+        let result = publish_modules(&dirs, &flags).unwrap();
+        assert!(matches!(&result.states[0], PublishState::Skipped(dir) if dir.ends_with("alpha")));
+        assert!(matches!(&result.states[1], PublishState::Published(dir, _) if dir.ends_with("beta")));
+        assert_eq!(result.summary.published, 1);
+        assert_eq!(result.summary.skipped, 1);
+        assert_eq!(result.summary.failed, 0);
+        assert!(result.to_json().unwrap().contains("\"skipped\": 1"));
+
+        let parallel = publish_modules_parallel(&dirs, 2, &flags).unwrap();
+        assert!(matches!(&parallel.states[0], PublishState::Skipped(_)));
+        assert!(matches!(&parallel.states[1], PublishState::Published(..)));
+        assert_eq!(parallel.summary.published, 1);
+        assert_eq!(parallel.summary.skipped, 1);
+
+        // Without a working check nothing is published.
+        let flags = PublishFlags {
+            curl_program: "false".to_string(),
+            ..flags
+        };
+        assert!(publish_modules(&dirs, &flags).is_err());
+        assert!(publish_modules_parallel(&dirs, 2, &flags).is_err());
+    }
+
+    #[test]
+    fn test_read_publish_list_publishes_listed_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        for dir in ["alpha", "beta", "gamma"] {
+            std::fs::create_dir_all(tmp.path().join(dir)).unwrap();
+        }
+        let absolute_beta = tmp.path().join("beta");
+        let list_path = tmp.path().join("publish.txt");
+        std::fs::write(
+            &list_path,
+            format!("# publish order\nalpha\n\n{}\n", absolute_beta.display()),
+        )
+        .unwrap();
+
+        let dirs = read_publish_list(&list_path, tmp.path()).unwrap();
+        assert_eq!(dirs, vec![tmp.path().join("alpha"), absolute_beta]);
+
+        let flags = PublishFlags {
+            program: "true".to_string(),
+            args: Vec::new(),
+            ..PublishFlags::default()
+        };
+        let result = publish_modules(&dirs, &flags).unwrap();
+        assert_eq!(result.summary.published, 2);
+        assert_eq!(result.summary.total, 2);
+    }
+
+    #[test]
+    fn test_read_publish_list_reports_missing_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let list_path = tmp.path().join("publish.txt");
+        std::fs::write(&list_path, "missing_crate\n").unwrap();
+
+        let error = read_publish_list(&list_path, tmp.path()).unwrap_err();
+        assert!(error.to_string().contains("missing_crate"), "{}", error);
+    }
+
+    #[test]
+    fn test_find_publishable_dirs_from_workspace_members_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"alpha\", \"beta\"]\n",
+        )
+        .unwrap();
+        let alpha = write_crate(tmp.path(), "alpha", "0.1.0");
+        let beta = write_crate(tmp.path(), "beta", "0.1.0");
+        write_crate(tmp.path(), "scratch", "0.1.0");
+
+        let expected = vec![alpha, beta];
+        assert_eq!(find_publishable_dirs_from_workspace(tmp.path(), TomlGrammar::Toml05).unwrap(), expected);
+        assert_eq!(
+            find_publishable_dirs_from_workspace(&tmp.path().join("Cargo.toml"), TomlGrammar::Toml05)
+                .unwrap(),
+            expected
+        );
+        assert_eq!(select_publishable_dirs(tmp.path(), None, TomlGrammar::Toml05).unwrap(), expected);
+
+        // Without a workspace root, every crate is found by walking the directory.
+        let nested = tmp.path().join("scratch");
+        assert_eq!(select_publishable_dirs(&nested, None, TomlGrammar::Toml05).unwrap(), vec![nested.clone()]);
+        assert_eq!(select_publishable_dirs(&nested, Some(tmp.path()), TomlGrammar::Toml05).unwrap(), expected);
+    }
+
+    fn command_args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_publish_command_feature_flags() {
+        let cmd = publish_command("crates/a", &PublishFlags::default());
+        assert_eq!(cmd.get_program(), "cargo");
+        assert_eq!(command_args(&cmd), vec!["publish"]);
+
+        let flags = PublishFlags {
+            features: vec!["serde".to_string(), "cli".to_string()],
+            ..PublishFlags::default()
+        };
+        let cmd = publish_command("crates/a", &flags);
+        assert_eq!(command_args(&cmd), vec!["publish", "--features", "serde,cli"]);
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("crates/a")));
+
+        let flags = PublishFlags {
+            all_features: true,
+            ..PublishFlags::default()
+        };
+        let cmd = publish_command("crates/a", &flags);
+        assert_eq!(command_args(&cmd), vec!["publish", "--all-features"]);
     }
 }
@@ -0,0 +1,14 @@
+use cratup_search::VersionMatch;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// What `run_incv` changed, printed as a JSON object by `incv --json`.
+#[derive(Debug, Serialize)]
+pub struct IncvResult {
+    /// The Cargo.toml files written by the update.
+    pub modified_files: Vec<PathBuf>,
+    /// The packages and dependencies at `to_version` after the update, per file.
+    pub version_matches: Vec<VersionMatch>,
+    pub from_version: String,
+    pub to_version: String,
+}
@@ -0,0 +1,32 @@
+//! Helpers shared by the unit tests of the binary.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Writes an executable shell script `name` running `body` into `dir` and returns its path,
+/// to stand in for an external program such as `git` or `cargo`.
+#[cfg(unix)]
+pub fn fake_program(dir: &Path, name: &str, body: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let script = dir.join(name);
+    fs::write(&script, format!("#!/bin/sh\n{}", body)).unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+/// Removes the ANSI color codes from `s`, so colored output can be compared as plain text
+/// whatever the global `colored` override of the other tests running in parallel.
+pub fn strip_ansi(s: &str) -> String {
+    let mut plain = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip the `[...m` of a Select Graphic Rendition sequence.
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
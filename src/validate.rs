@@ -0,0 +1,97 @@
+use cratup_search::find_manifests;
+use cratup_tree_sitter::{TomlParser, TomlParserError};
+use log::debug;
+use std::path::{Path, PathBuf};
+
+/// Returns the Cargo.toml files below `dir_path` that `search` and `incv` would load,
+/// skipping `excluded_dirs`, which cannot be parsed or whose TOML tree has error nodes,
+/// with the problem of each, sorted by path.
+pub fn find_malformed_manifests(
+    dir_path: &Path,
+    excluded_dirs: &[String],
+) -> Vec<(PathBuf, TomlParserError)> {
+    debug!("Validating the Cargo.toml files in: {:?}", dir_path);
+
+    let mut malformed = Vec::new();
+    for file_path in find_manifests(dir_path, excluded_dirs) {
+        // Malformed TOML fails `new_strict` with the number of error nodes.
+        let problem = match TomlParser::new_from_path(&file_path) {
+            Ok((_, content)) => TomlParser::new_strict(&content).err(),
+            Err(e) => Some(e),
+        };
+        if let Some(e) = problem {
+            debug!("Failed to parse {:?}: {}", file_path, e);
+            malformed.push((file_path, e));
+        }
+    }
+
+    debug!("Malformed Cargo.toml files found: {}", malformed.len());
+    malformed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_find_malformed_manifests_reports_only_broken_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good");
+        let bad = dir.path().join("bad");
+        fs::create_dir_all(&good).unwrap();
+        fs::create_dir_all(&bad).unwrap();
+        fs::write(
+            good.join("Cargo.toml"),
+            "[package]\nname = \"good\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            bad.join("Cargo.toml"),
+            "[package]\nname = \"bad\"\nversion = \"0.1.0\n[dependencies\n",
+        )
+        .unwrap();
+
+        let malformed = find_malformed_manifests(dir.path(), &[]);
+
+        assert_eq!(malformed.len(), 1);
+        assert_eq!(malformed[0].0, bad.join("Cargo.toml"));
+        assert!(matches!(
+            malformed[0].1,
+            TomlParserError::ParseFailed { error_nodes } if error_nodes > 0
+        ));
+    }
+
+    #[test]
+    fn test_find_malformed_manifests_reports_unreadable_files_and_keeps_going() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["binary", "empty", "good", "target/package/good-0.1.0"] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+        }
+        fs::write(dir.path().join("binary/Cargo.toml"), [0xff, 0xfe, 0x00]).unwrap();
+        fs::write(dir.path().join("empty/Cargo.toml"), "").unwrap();
+        fs::write(
+            dir.path().join("good/Cargo.toml"),
+            "[package]\nname = \"good\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("target/package/good-0.1.0/Cargo.toml"),
+            "[package\n",
+        )
+        .unwrap();
+
+        let malformed = find_malformed_manifests(dir.path(), &[]);
+
+        let paths: Vec<PathBuf> = malformed.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                dir.path().join("binary/Cargo.toml"),
+                dir.path().join("empty/Cargo.toml"),
+            ]
+        );
+        assert!(matches!(malformed[0].1, TomlParserError::InvalidUtf8));
+        assert!(matches!(malformed[1].1, TomlParserError::EmptySource));
+    }
+}
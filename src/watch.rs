@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use log::debug;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// How often the watch loop checks whether it should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Settings of the `--watch` loop.
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    /// Events arriving within this many milliseconds of the first one are batched
+    /// into a single re-run.
+    pub debounce_ms: u64,
+}
+
+/// Returns true if the event creates, modifies or removes a Cargo.toml.
+fn is_cargo_toml_event(event: &Event) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) && event
+        .paths
+        .iter()
+        .any(|path| path.file_name().is_some_and(|name| name == "Cargo.toml"))
+}
+
+/// Watches `dir_path` recursively and calls `on_change` once for every batch of
+/// Cargo.toml changes, until `running` is set to false.
+pub fn watch_cargo_tomls<F>(
+    dir_path: &Path,
+    watch_config: &WatchConfig,
+    running: &AtomicBool,
+    on_change: F,
+) -> Result<()>
+where
+    F: FnMut() -> Result<()>,
+{
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to create file watcher")?;
+    watcher
+        .watch(dir_path, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch {:?}", dir_path))?;
+    debug!("Watching {:?} for Cargo.toml changes", dir_path);
+
+    run_watch_loop(&rx, watch_config, running, on_change)
+}
+
+/// Calls `on_change` once for every batch of Cargo.toml events received on `rx`, until
+/// `running` is set to false or every sender is dropped.
+fn run_watch_loop<F>(
+    rx: &Receiver<notify::Result<Event>>,
+    watch_config: &WatchConfig,
+    running: &AtomicBool,
+    mut on_change: F,
+) -> Result<()>
+where
+    F: FnMut() -> Result<()>,
+{
+    let debounce = Duration::from_millis(watch_config.debounce_ms);
+    while running.load(Ordering::SeqCst) {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(Ok(event)) if is_cargo_toml_event(&event) => {
+                debug!("Cargo.toml change detected: {:?}", event);
+
+                // Swallow the rest of the burst, e.g. the several events of a single save.
+                let deadline = Instant::now() + debounce;
+                while rx
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    .is_ok()
+                {}
+
+                on_change()?;
+            }
+            Ok(Ok(event)) => debug!("Ignoring event: {:?}", event),
+            Ok(Err(e)) => debug!("Watch error: {:?}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                debug!("Watcher disconnected, stopping");
+                break;
+            }
+        }
+    }
+
+    debug!("Watch loop stopped");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{DataChange, ModifyKind};
+    use std::path::PathBuf;
+
+    fn modify_event(path: &str) -> notify::Result<Event> {
+        Ok(Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+            .add_path(PathBuf::from(path)))
+    }
+
+    /// Runs the watch loop over `events`, stopping once they are all received, and
+    /// returns the number of re-runs.
+    fn count_reruns(events: Vec<notify::Result<Event>>) -> usize {
+        let (tx, rx) = mpsc::channel();
+        for event in events {
+            tx.send(event).unwrap();
+        }
+        drop(tx);
+
+        let running = AtomicBool::new(true);
+        let mut runs = 0;
+        run_watch_loop(&rx, &WatchConfig { debounce_ms: 300 }, &running, || {
+            runs += 1;
+            Ok(())
+        })
+        .unwrap();
+        runs
+    }
+
+    #[test]
+    fn test_modification_triggers_one_rerun() {
+        let runs = count_reruns(vec![
+            modify_event("/ws/notes.txt"),
+            modify_event("/ws/Cargo.toml"),
+            modify_event("/ws/Cargo.toml"),
+            modify_event("/ws/a/Cargo.toml"),
+        ]);
+        assert_eq!(runs, 1);
+    }
+
+    #[test]
+    fn test_other_files_trigger_no_rerun() {
+        let runs = count_reruns(vec![
+            modify_event("/ws/notes.txt"),
+            Ok(Event::new(EventKind::Access(notify::event::AccessKind::Any))
+                .add_path(PathBuf::from("/ws/Cargo.toml"))),
+        ]);
+        assert_eq!(runs, 0);
+    }
+
+    #[test]
+    fn test_stops_when_not_running() {
+        let (_tx, rx) = mpsc::channel();
+        let running = AtomicBool::new(false);
+        run_watch_loop(&rx, &WatchConfig { debounce_ms: 300 }, &running, || {
+            panic!("no event was sent")
+        })
+        .unwrap();
+    }
+}